@@ -0,0 +1,77 @@
+// Counts the allocations made while evaluating large buffers: run with
+// `cargo bench -p forth-core`
+
+use forth_core::Forth;
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Instant;
+
+struct CountingAllocator;
+
+static ALLOCATIONS: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATIONS.fetch_add(1, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+fn measure(name: &str, source: &str) {
+    let mut forth = Forth::new();
+    let before = ALLOCATIONS.load(Ordering::Relaxed);
+    let start = Instant::now();
+    let result = forth.eval(source);
+    let elapsed = start.elapsed();
+    let allocations = ALLOCATIONS.load(Ordering::Relaxed) - before;
+    assert!(result.is_ok(), "{}: {:?}", name, result);
+    println!(
+        "{:<24} {:>8} KiB {:>10} allocations {:>10.2?}",
+        name,
+        source.len() / 1024,
+        allocations,
+        elapsed
+    );
+}
+
+fn main() {
+    // plain words, no definitions
+    let words = "1 2 + 3 * dup drop 4 swap over drop drop drop\n".repeat(20_000);
+    measure("built-in words", &words);
+
+    // many small definitions, each called once
+    let mut definitions = String::new();
+    for i in 0..20_000 {
+        definitions.push_str(&format!(": word{} {} dup * drop ;\n", i, i));
+    }
+    for i in 0..20_000 {
+        definitions.push_str(&format!("word{} ", i));
+    }
+    measure("many definitions", &definitions);
+
+    // fewer definitions, of many words each
+    let body = "dup 1 + swap drop ".repeat(25);
+    let mut long = String::new();
+    for i in 0..1_000 {
+        long.push_str(&format!(": long{} {};\n", i, body));
+    }
+    measure("long definitions", &long);
+
+    // a few definitions calling each other, called many times
+    let mut calls =
+        String::from(": square dup * ;\n: step 1 + square drop ;\n: twice step 3 step ;\n");
+    calls.push_str(&"2 twice ".repeat(50_000));
+    measure("nested calls", &calls);
+}
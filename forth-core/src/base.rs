@@ -0,0 +1,146 @@
+use crate::{Error, ErrorKind, Forth, ForthResult, Unsigned, Value};
+
+// The numeric base numbers are read and printed in, from 2 to 36, digits
+// past 9 being letters:
+//
+//     BASE ( -- a-addr )   address of the cell holding the base
+//     HEX ( -- )           base 16
+//     DECIMAL ( -- )       base 10
+//
+// The base is only used for the code evaluated: definition bodies keep their
+// numbers in decimal, whatever base they were read in. BASE's cell is only
+// reserved once asked for, the base being kept aside until then.
+//
+// A prefix reads a number in a base of its own, whatever BASE is: $FF and
+// 0xFF in hexadecimal, #99 in decimal, %1010 in binary, the sign after the
+// prefix like $-FF. 'A' is the code of the character between the quotes.
+// A number ending with a dot is a double number, see doubles.rs.
+
+const DEFAULT_BASE: Value = 10;
+
+impl Forth {
+    // Base numbers are read and printed in; 10 if BASE holds no valid one
+    pub fn base(&self) -> u32 {
+        let base = match self.base_address {
+            Some(address) => self.cell_at(address).unwrap_or(self.base),
+            None => self.base,
+        };
+        match base {
+            2..=36 => base as u32,
+            _ => DEFAULT_BASE as u32,
+        }
+    }
+
+    pub fn set_base(&mut self, base: u32) {
+        let base = Value::from(base);
+        self.base = base;
+        if let Some(address) = self.base_address {
+            let _ = self.set_cell(address, base);
+        }
+    }
+
+    // A value as written in the current base
+    pub fn format_number(&self, value: Value) -> String {
+        format_in_base(value.into(), self.base())
+    }
+
+    // BASE ( -- a-addr ), its cell reserved the first time it is asked for
+    pub(crate) fn base_word(&mut self) -> ForthResult {
+        let address = match self.base_address {
+            Some(address) if self.cell_at(address).is_ok() => address,
+            _ => {
+                let address = self.reserve_cell()?;
+                self.set_cell(address, self.base)?;
+                self.base_address = Some(address);
+                address
+            }
+        };
+        self.push(address)
+    }
+
+    // Keeps the base aside, before BASE's cell may be given back
+    pub(crate) fn keep_base(&mut self) {
+        if let Some(address) = self.base_address {
+            if let Ok(base) = self.cell_at(address) {
+                self.base = base;
+            }
+        }
+    }
+
+    // Base the code seeing the first `visible` definitions reads numbers in:
+    // the current one for code evaluated now, decimal for bodies
+    pub(crate) fn number_base(&self, visible: usize) -> u32 {
+        if visible < self.definitions.len() {
+            DEFAULT_BASE as u32
+        } else {
+            self.base()
+        }
+    }
+}
+
+// The number a word stands for, if it is one, read in `base` unless it has a
+// prefix. A prefixed word with digits its base doesn't have isn't a word either.
+pub(crate) fn number_literal(word: &str, base: u32) -> Option<Result<Value, Error>> {
+    if let Some(quoted) = word
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+        .filter(|quoted| !quoted.is_empty())
+    {
+        let mut chars = quoted.chars();
+        return Some(match (chars.next(), chars.next()) {
+            (Some(c), None) => Ok(c as Value),
+            _ => Err(ErrorKind::InvalidNumber.into()),
+        });
+    }
+    match split_prefix(word) {
+        Some((digits, base)) => {
+            Some(parse_prefixed(digits, base).ok_or(ErrorKind::InvalidNumber.into()))
+        }
+        None => Value::from_str_radix(word, base).ok().map(Ok),
+    }
+}
+
+// Digits after the prefix of a word, if it has one, and the base they're in
+pub(crate) fn split_prefix(word: &str) -> Option<(&str, u32)> {
+    [("$", 16), ("#", 10), ("%", 2), ("0x", 16), ("0X", 16)]
+        .into_iter()
+        .find_map(|(prefix, base)| Some((word.strip_prefix(prefix)?, base)))
+        .filter(|(digits, _)| !digits.is_empty())
+}
+
+// Digits after a prefix, the sign first if any. Written unsigned, they can
+// go past the largest value, $FFFFFFFFFFFFFFFF being -1.
+fn parse_prefixed(digits: &str, base: u32) -> Option<Value> {
+    let (negative, digits) = match digits.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, digits),
+    };
+    if !digits.chars().all(|c| c.is_digit(base)) {
+        return None;
+    }
+    let magnitude = Unsigned::from_str_radix(digits, base).ok()? as Value;
+    Some(if negative {
+        magnitude.wrapping_neg()
+    } else {
+        magnitude
+    })
+}
+
+// A number, a cell or a double, as written in a base
+pub(crate) fn format_in_base(value: i128, base: u32) -> String {
+    let mut magnitude = value.unsigned_abs();
+    let mut digits = Vec::new();
+    loop {
+        let digit = (magnitude % u128::from(base)) as u32;
+        let digit = char::from_digit(digit, base).unwrap_or('?');
+        digits.push(digit.to_ascii_uppercase());
+        magnitude /= u128::from(base);
+        if magnitude == 0 {
+            break;
+        }
+    }
+    if value < 0 {
+        digits.push('-');
+    }
+    digits.iter().rev().collect()
+}
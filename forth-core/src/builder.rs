@@ -0,0 +1,211 @@
+use crate::prelude::PRELUDE;
+use crate::random::Random;
+use crate::shared::Shared;
+use crate::{
+    CancelToken, CaseMode, DivisionMode, Forth, ForthFiles, ForthInput, ForthOutput, Output,
+    OverflowMode, Screen, Strictness, Tracer,
+};
+use std::time::{Duration, Instant};
+
+// Builds an interpreter with non-default settings; `Forth::new()` is the same
+// as `ForthBuilder::new().build()`
+#[derive(Default)]
+pub struct ForthBuilder {
+    deadline: Option<Instant>,
+    fuel: Option<u64>,
+    stack_limit: Option<usize>,
+    recursion_limit: Option<usize>,
+    tracer: Option<Tracer>,
+    cancel_token: Option<CancelToken>,
+    screen_size: Option<(usize, usize)>,
+    seed: Option<u64>,
+    input: Option<Box<dyn ForthInput>>,
+    files: Option<Box<dyn ForthFiles>>,
+    provenance: bool,
+    output_limit: Option<usize>,
+    data_limit: Option<usize>,
+    output: Option<Box<dyn ForthOutput>>,
+    overflow_mode: OverflowMode,
+    division_mode: DivisionMode,
+    strictness: Strictness,
+    case_mode: CaseMode,
+    bare: bool,
+}
+
+impl ForthBuilder {
+    pub fn new() -> ForthBuilder {
+        ForthBuilder::default()
+    }
+
+    // Evaluation fails with a timeout once the deadline is past
+    pub fn deadline(mut self, deadline: Instant) -> ForthBuilder {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    // A deadline this long from now, if it can be represented at all
+    pub fn timeout(self, timeout: Duration) -> ForthBuilder {
+        match Instant::now().checked_add(timeout) {
+            Some(deadline) => self.deadline(deadline),
+            None => self,
+        }
+    }
+
+    // Evaluation fails once this many steps are taken, over all evaluations:
+    // each word run takes one, and so does each round of a loop
+    pub fn fuel(mut self, steps: u64) -> ForthBuilder {
+        self.fuel = Some(steps);
+        self
+    }
+
+    // Pushing more values than this fails with a stack overflow
+    pub fn stack_limit(mut self, limit: usize) -> ForthBuilder {
+        self.stack_limit = Some(limit);
+        self
+    }
+
+    // RECURSE calls nesting deeper than this fail with a recursion limit
+    // error, 256 otherwise; calls and control structures still stop 1000
+    // levels deep
+    pub fn recursion_limit(mut self, limit: usize) -> ForthBuilder {
+        self.recursion_limit = Some(limit);
+        self
+    }
+
+    pub fn tracer(mut self, tracer: Tracer) -> ForthBuilder {
+        self.tracer = Some(tracer);
+        self
+    }
+
+    // Evaluation fails once the token is cancelled
+    pub fn cancel_token(mut self, token: CancelToken) -> ForthBuilder {
+        self.cancel_token = Some(token);
+        self
+    }
+
+    // Size of the virtual screen, 80 columns by 24 rows otherwise
+    pub fn screen_size(mut self, width: usize, height: usize) -> ForthBuilder {
+        self.screen_size = Some((width, height));
+        self
+    }
+
+    // RANDOM returns the same numbers for the same seed, instead of picking
+    // one from the clock
+    pub fn seed(mut self, seed: u64) -> ForthBuilder {
+        self.seed = Some(seed);
+        self
+    }
+
+    // What KEY and ACCEPT read, nothing otherwise
+    pub fn input(mut self, input: Box<dyn ForthInput>) -> ForthBuilder {
+        self.input = Some(input);
+        self
+    }
+
+    // What INCLUDE and INCLUDED read, no file otherwise
+    pub fn files(mut self, files: Box<dyn ForthFiles>) -> ForthBuilder {
+        self.files = Some(files);
+        self
+    }
+
+    // Tracks where each stack value came from, at some cost in speed
+    pub fn provenance(mut self, enabled: bool) -> ForthBuilder {
+        self.provenance = enabled;
+        self
+    }
+
+    // Bytes of output kept, the oldest being dropped first; 64 KiB otherwise
+    pub fn output_limit(mut self, limit: usize) -> ForthBuilder {
+        self.output_limit = Some(limit);
+        self
+    }
+
+    // Bytes data space can grow to, ALLOT and the like failing with an
+    // invalid address past them; 4 MiB otherwise
+    pub fn data_limit(mut self, limit: usize) -> ForthBuilder {
+        self.data_limit = Some(limit);
+        self
+    }
+
+    // Where printed text goes, instead of the buffer `Forth::output` returns
+    pub fn output(mut self, output: Box<dyn ForthOutput>) -> ForthBuilder {
+        self.output = Some(output);
+        self
+    }
+
+    // What arithmetic does past the largest or smallest value, wrapping
+    // around otherwise
+    pub fn overflow_mode(mut self, mode: OverflowMode) -> ForthBuilder {
+        self.overflow_mode = mode;
+        self
+    }
+
+    // How division rounds quotients, towards zero otherwise
+    pub fn division_mode(mut self, mode: DivisionMode) -> ForthBuilder {
+        self.division_mode = mode;
+        self
+    }
+
+    // Whether an unknown word fails the evaluation or is skipped with a warning
+    pub fn strictness(mut self, strictness: Strictness) -> ForthBuilder {
+        self.strictness = strictness;
+        self
+    }
+
+    // How the names of definitions are kept and found, upper-cased otherwise
+    pub fn case_mode(mut self, mode: CaseMode) -> ForthBuilder {
+        self.case_mode = mode;
+        self
+    }
+
+    // Without the prelude, only the words built into the interpreter, like
+    // `Forth::new_bare()`
+    pub fn bare(mut self) -> ForthBuilder {
+        self.bare = true;
+        self
+    }
+
+    pub fn build(self) -> Forth {
+        let mut forth = Forth::new_bare();
+        // before the limits and the tracer, the prelude not counting as the code's
+        forth.case_mode = self.case_mode;
+        if !self.bare {
+            let loaded = forth.eval_prelude(PRELUDE);
+            debug_assert!(loaded.is_ok(), "the prelude failed: {:?}", loaded);
+        }
+        forth.deadline = self.deadline;
+        forth.fuel = self.fuel;
+        forth.stack_limit = self.stack_limit;
+        if let Some(limit) = self.recursion_limit {
+            forth.recursion_limit = limit;
+        }
+        forth.tracer = self.tracer;
+        forth.cancel_token = self.cancel_token;
+        if let Some((width, height)) = self.screen_size {
+            forth.screen = Shared::new(Screen::new(width, height));
+        }
+        if let Some(seed) = self.seed {
+            forth.random = Random::seeded(seed);
+        }
+        if let Some(input) = self.input {
+            forth.input = input;
+        }
+        if let Some(files) = self.files {
+            forth.files = files;
+        }
+        if let Some(limit) = self.output_limit {
+            forth.output = Shared::new(Output::new(limit));
+        }
+        if let Some(limit) = self.data_limit {
+            forth.data_limit = limit;
+        }
+        forth.sink = self.output;
+        forth.overflow_mode = self.overflow_mode;
+        forth.division_mode = self.division_mode;
+        forth.strictness = self.strictness;
+        if self.provenance {
+            forth.provenance = Some(Vec::new());
+        }
+        forth
+    }
+}
@@ -0,0 +1,32 @@
+// How the names of definitions are kept and told apart. By default they are
+// upper-cased when defined and found whatever the case they are written in,
+// like in most Forths.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CaseMode {
+    #[default]
+    Insensitive,
+    // names kept as typed, still found whatever their case
+    Preserve,
+    // names kept as typed, and only found written the same. Built-in words
+    // are found whatever their case all the same.
+    Sensitive,
+}
+
+impl CaseMode {
+    // The name a definition is given
+    pub fn name(self, name: &str) -> String {
+        match self {
+            CaseMode::Insensitive => name.to_ascii_uppercase(),
+            CaseMode::Preserve | CaseMode::Sensitive => name.to_string(),
+        }
+    }
+
+    // Whether a word stands for the definition with a name
+    pub fn matches(self, name: &str, word: &str) -> bool {
+        match self {
+            CaseMode::Insensitive | CaseMode::Preserve => name.eq_ignore_ascii_case(word),
+            CaseMode::Sensitive => name == word,
+        }
+    }
+}
@@ -0,0 +1,57 @@
+use crate::{Forth, Instruction, Token};
+use std::sync::Arc;
+
+// Definitions run from code compiled from their body: each word looked up
+// once, when the definition is made or first called, rather than every time
+// it runs. A word not found is only reported when it runs, as before.
+//
+// Lookups depend on which definitions are hidden or restricted, so changing
+// that drops the code compiled so far. Code is kept with the body it was compiled
+// from, so that a body replaced by TO, IS or DOES> is compiled again.
+
+// A word of a body compiled: its instruction, None if it wasn't found
+pub(crate) type Step = Option<Instruction>;
+
+#[derive(Clone)]
+pub(crate) struct CompiledBody {
+    words: Arc<[Token]>,
+    code: Arc<[Step]>,
+}
+
+impl Forth {
+    // Looks words up, as code seeing the first `visible` definitions
+    pub(crate) fn compile_code<S, C>(&self, words: &[S], visible: usize) -> C
+    where
+        S: AsRef<str>,
+        C: FromIterator<Step>,
+    {
+        words
+            .iter()
+            .map(|word| self.instruction_from_word(word.as_ref(), visible).ok())
+            .collect()
+    }
+
+    // Code of a definition, compiled from its body unless it already was
+    pub(crate) fn definition_code(&mut self, index: usize) -> Arc<[Step]> {
+        let words = &self.definitions[index].instructions;
+        if let Some(Some(compiled)) = self.code.get(index) {
+            if Arc::ptr_eq(&compiled.words, words) {
+                return compiled.code.clone();
+            }
+        }
+        let words = words.clone();
+        let code: Arc<[Step]> = self.compile_code(&words, index);
+        if self.code.len() <= index {
+            self.code.resize_with(index + 1, || None);
+        }
+        self.code[index] = Some(CompiledBody {
+            words,
+            code: code.clone(),
+        });
+        code
+    }
+
+    pub(crate) fn drop_code(&mut self) {
+        self.code.clear();
+    }
+}
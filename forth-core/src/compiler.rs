@@ -0,0 +1,194 @@
+use crate::doubles::double_literal;
+use crate::words::Words;
+use crate::{
+    check_structures, is_parsing_word, parsed_literal, DefinitionKind, Error, ErrorKind, Forth,
+    ForthResult, Instruction, Token, CELL_SIZE,
+};
+use std::borrow::Cow;
+use std::sync::Arc;
+
+// Compile state: between the name of a colon definition and its `;`, words
+// are compiled, added to the body, rather than run. Immediate words run even
+// then, so that they can compile words themselves:
+//
+//     : SQUARED  POSTPONE DUP POSTPONE * ; IMMEDIATE
+//     : CUBE  DUP SQUARED * ;          CUBE's body being DUP DUP * *
+//
+// POSTPONE compiles a word, or the compiling of a word, rather than running
+// it; LITERAL compiles the number on top of the stack. `[` runs the words up
+// to `]` while compiling, so `[ 6 7 * ] LITERAL` compiles 42. STATE pushes
+// the address of a cell holding true while compiling.
+//
+// The body being compiled is kept as `compiling`, None while interpreting.
+
+impl Forth {
+    // Words of a definition up to its `;`, which must have its control
+    // structures whole. They are gathered in the buffer kept from the
+    // definition before, so that only the body made of them is allocated.
+    pub(crate) fn compile_body(&mut self, words: &mut Words) -> Result<Arc<[Token]>, Error> {
+        self.compiling = Some(std::mem::take(&mut self.compile_buffer));
+        self.update_state();
+        let result = self.compile_words(words);
+        let mut body = self.compiling.take().unwrap_or_default();
+        self.update_state();
+        let body_made = result
+            .and_then(|()| check_structures(&body))
+            .map(|()| Arc::from(&body[..]));
+        body.clear();
+        self.compile_buffer = body;
+        body_made
+    }
+
+    fn compile_words(&mut self, words: &mut Words) -> ForthResult {
+        while let Some(word) = words.next() {
+            if word == ";" {
+                return Ok(());
+            }
+            // most likely the ; of the definition before is missing
+            if word == ":" {
+                let error = Error::from(ErrorKind::NestedDefinition);
+                return Err(error.at(word, Some(&words.word_position())));
+            }
+            let word = if is_parsing_word(word) {
+                Cow::Owned(parsed_literal(word, words)?)
+            } else {
+                Cow::Borrowed(word)
+            };
+            // the definition being compiled can't be called by its name yet
+            let visible = self.definitions.len();
+            match self.instruction_from_word(&word, visible) {
+                Ok(Instruction::LeftBracket) => self.interpret_until_bracket(words)?,
+                Ok(Instruction::Literal) => self.literal()?,
+                Ok(Instruction::Postpone) => self.compile_postpone(&word, visible)?,
+                Ok(Instruction::CallDefinition(index)) if self.definitions[index].immediate => {
+                    self.eval_instruction(&word, visible)?
+                }
+                // numbers are kept in decimal, whatever BASE they were read in
+                Ok(Instruction::Number(n)) if !word.contains(' ') => {
+                    let token = self.tokens.written(n);
+                    self.compile_token(token)?
+                }
+                Ok(Instruction::DoubleNumber) => {
+                    let double = double_literal(&word, self.number_base(visible));
+                    let double = double.ok_or(ErrorKind::Internal)?;
+                    let token = self.tokens.written(format_args!("{}.", double));
+                    self.compile_token(token)?
+                }
+                _ => self.compile(&word)?,
+            }
+        }
+        Err(ErrorKind::InvalidWord.into())
+    }
+
+    // Runs the words after a `[` up to the `]` going back to compiling
+    fn interpret_until_bracket(&mut self, words: &mut Words) -> ForthResult {
+        let mut interpreted = Vec::new();
+        loop {
+            match words.next() {
+                Some("]") => break,
+                Some(word) if is_parsing_word(word) => {
+                    interpreted.push(parsed_literal(word, words)?)
+                }
+                Some(word) => interpreted.push(word.to_string()),
+                None => return Err(ErrorKind::InvalidWord.into()),
+            }
+        }
+        let body = self.compiling.take();
+        self.update_state();
+        let result = self.eval_words(&interpreted, &[], self.definitions.len());
+        self.compiling = body;
+        self.update_state();
+        result
+    }
+
+    // Adds a word to the body being compiled
+    fn compile(&mut self, word: &str) -> ForthResult {
+        let token = self.tokens.get(word);
+        self.compile_token(token)
+    }
+
+    fn compile_token(&mut self, token: Token) -> ForthResult {
+        self.compiling
+            .as_mut()
+            .ok_or(ErrorKind::InvalidWord)?
+            .push(token);
+        Ok(())
+    }
+
+    // `POSTPONE name` within a definition: an immediate word is compiled, to
+    // run when the definition does; any other word is compiled by the
+    // definition when it runs, so the definition had better be immediate
+    fn compile_postpone(&mut self, word: &str, visible: usize) -> ForthResult {
+        let name = postpone_target(word).ok_or(ErrorKind::Internal)?;
+        if name == ";" || is_parsing_word(name) {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        let immediate = match self.instruction_from_word(name, visible)? {
+            Instruction::Literal => true,
+            Instruction::CallDefinition(index) => self.definitions[index].immediate,
+            _ => false,
+        };
+        match immediate {
+            true => self.compile(name),
+            false => self.compile(word),
+        }
+    }
+
+    // POSTPONE name ( -- ), run by an immediate word: compiles name
+    pub(crate) fn postpone(&mut self, word: &str) -> ForthResult {
+        let name = postpone_target(word).ok_or(ErrorKind::Internal)?;
+        self.compile(name)
+    }
+
+    // LITERAL ( x -- ), compiling x
+    pub(crate) fn literal(&mut self) -> ForthResult {
+        if self.compiling.is_none() {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        let value = self.pop()?;
+        let token = self.tokens.written(value);
+        self.compile_token(token)
+    }
+
+    // IMMEDIATE ( -- ) makes the latest colon definition run while compiling
+    pub(crate) fn make_immediate(&mut self) -> ForthResult {
+        self.visibility.check_can_define()?;
+        // the prelude's words are left as they are
+        let prelude = self.prelude.min(self.definitions.len());
+        match self.definitions[prelude..].last_mut() {
+            Some(definition) if definition.kind == DefinitionKind::Colon => {
+                definition.immediate = true;
+                Ok(())
+            }
+            _ => Err(ErrorKind::InvalidWord.into()),
+        }
+    }
+
+    // STATE ( -- a-addr ), its cell reserved the first time it is asked for
+    pub(crate) fn state(&mut self) -> ForthResult {
+        let address = match self.state_address {
+            Some(address) if self.data_range(address, CELL_SIZE as usize).is_ok() => address,
+            _ => {
+                let address = self.reserve_cell()?;
+                self.state_address = Some(address);
+                address
+            }
+        };
+        self.update_state();
+        self.push(address)
+    }
+
+    // Keeps STATE's cell up to date, unless ALLOT gave it back
+    fn update_state(&mut self) {
+        if let Some(address) = self.state_address {
+            let flag = if self.compiling.is_some() { -1 } else { 0 };
+            let _ = self.set_cell(address, flag);
+        }
+    }
+}
+
+// Name of a `POSTPONE name` word
+pub(crate) fn postpone_target(word: &str) -> Option<&str> {
+    let (opening, name) = word.split_once(' ')?;
+    opening.eq_ignore_ascii_case("POSTPONE").then_some(name)
+}
@@ -0,0 +1,53 @@
+use crate::{Error, ErrorKind, Forth, Location, Position, Words};
+
+// An error found by eval_all, and the word of the input it is at
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostic {
+    pub location: Location,
+    pub error: Error,
+}
+
+impl Forth {
+    // Evaluates like eval, though an error doesn't stop the evaluation: it
+    // goes on with the next word, for all the errors of the input to be found
+    // at once, in order. Running out of time or steps, or a cancellation,
+    // still stops it.
+    pub fn eval_all(&mut self, input: &str) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let mut words = Words::new(input);
+        while let Some(word) = words.next() {
+            let position = words.word_position();
+            if self.eval_word_diagnosed(word, &mut words, position, &mut diagnostics) {
+                break;
+            }
+        }
+        diagnostics
+    }
+
+    // Evaluates a word for eval_all, adding its error to the diagnostics.
+    // True when the error stops the evaluation.
+    pub(crate) fn eval_word_diagnosed(
+        &mut self,
+        word: &str,
+        words: &mut Words,
+        position: Position,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) -> bool {
+        let warned = self.warnings.len();
+        let result = self.eval_word(word, words);
+        self.locate_warnings(warned, word, Some(&position));
+        let error = match result {
+            Ok(()) => return false,
+            Err(error) => error.at(word, Some(&position)),
+        };
+        let stops = matches!(
+            error.kind(),
+            ErrorKind::Timeout | ErrorKind::Cancelled | ErrorKind::FuelExhausted
+        );
+        if let Some(location) = error.location().cloned() {
+            diagnostics.push(Diagnostic { location, error });
+        }
+        stops
+    }
+}
@@ -0,0 +1,149 @@
+use crate::variables::definition_name;
+use crate::{DefinitionKind, ErrorKind, EvalEvent, Forth, ForthResult, Value};
+use std::collections::HashMap;
+use std::sync::Arc;
+
+// Definitions indexed by name, so that looking a word up doesn't go through
+// every definition. Each name maps to the definitions made with it in order,
+// the latest one visible shadowing the others.
+//
+// Definitions can also be forgotten, along with every definition made after
+// them, which are the only ones that could call them:
+//
+//     FORGET NAME          forgets the latest definition of NAME
+//     MARKER CHECKPOINT    CHECKPOINT forgetting itself when it runs, and
+//                          giving back the data space reserved after it
+
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Dictionary {
+    // by name in upper case, whatever the case mode
+    indexes: HashMap<String, Vec<usize>>,
+    // how many definitions are indexed: any pushed onto `definitions` from
+    // outside since are looked up one by one
+    indexed: usize,
+}
+
+impl Forth {
+    // Indexes the definitions made since last time
+    pub(crate) fn index_definitions(&mut self) {
+        for index in self.dictionary.indexed..self.definitions.len() {
+            let name = &self.definitions[index].name;
+            // nameless definitions, from :NONAME, are only reached by their token
+            if !name.is_empty() {
+                self.dictionary
+                    .indexes
+                    .entry(name.to_ascii_uppercase())
+                    .or_default()
+                    .push(index);
+            }
+        }
+        self.dictionary.indexed = self.definitions.len();
+        self.keep_orders();
+    }
+
+    // The latest definition of a name among the first `visible` ones, leaving
+    // out hidden ones, in the first wordlist of the search order having one
+    pub(crate) fn find_definition(&self, name: &str, visible: usize) -> Option<usize> {
+        let order = self.order_seen(visible);
+        let visible = visible.min(self.definitions.len());
+        let indexed = self.dictionary.indexed.min(visible);
+        order.iter().find_map(|&wordlist| {
+            let found = |index: usize| {
+                index < visible
+                    && self.definitions[index].wordlist == wordlist
+                    && !self.visibility.is_hidden(index)
+                    && self.case_mode.matches(&self.definitions[index].name, name)
+            };
+            let unindexed = (indexed..visible).rev().find(|&i| found(i));
+            if unindexed.is_some() {
+                return unindexed;
+            }
+            with_upper_case(name, |name| {
+                let indexes = self.dictionary.indexes.get(name)?;
+                indexes.iter().rev().copied().find(|&i| found(i))
+            })
+        })
+    }
+
+    // `FORGET name` ( -- )
+    pub(crate) fn forget(&mut self, word: &str, visible: usize) -> ForthResult {
+        let name = forget_target(word).ok_or(ErrorKind::Internal)?;
+        self.visibility.check_can_define()?;
+        let index = self
+            .find_definition(name, visible)
+            .ok_or(ErrorKind::UnknownWord)?;
+        self.forget_from(index);
+        Ok(())
+    }
+
+    pub(crate) fn add_marker<'a, I>(&mut self, words: &mut I) -> ForthResult
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        self.visibility.check_can_define()?;
+        let name = definition_name(words.next())?;
+        let here = Value::try_from(self.data.len()).map_err(|_| ErrorKind::InvalidAddress)?;
+        let body = Arc::from([self.tokens.written(here)]);
+        self.define(name, DefinitionKind::Marker, body);
+        Ok(())
+    }
+
+    // Runs a marker, forgetting it and the data space reserved since
+    pub(crate) fn call_marker(&mut self, index: usize) -> ForthResult {
+        self.visibility.check_can_define()?;
+        let here = self.definitions[index].instructions[0]
+            .parse()
+            .map_err(|_| ErrorKind::Internal)?;
+        self.forget_from(index);
+        self.truncate_data(here);
+        Ok(())
+    }
+
+    // Forgets a definition and every one made after it
+    fn forget_from(&mut self, index: usize) {
+        self.prelude = self.prelude.min(index);
+        for definition in self.definitions.drain(index..).collect::<Vec<_>>() {
+            if !definition.name.is_empty() {
+                self.record(EvalEvent::Forgotten(definition.name));
+            }
+        }
+        self.forget_orders(index);
+        self.dictionary = Dictionary::default();
+        self.index_definitions();
+        self.code.truncate(index);
+        self.visibility.forget_from(index);
+        self.forget_execution_tokens(index);
+        self.tests.retain(|test| test.visible <= index);
+    }
+
+    // Whether a definition was ever made with a name, even a hidden one
+    pub(crate) fn is_defined(&self, name: &str) -> bool {
+        let indexed = self.dictionary.indexed.min(self.definitions.len());
+        let matches = |index: &usize| self.case_mode.matches(&self.definitions[*index].name, name);
+        with_upper_case(name, |upper| {
+            let indexes = self.dictionary.indexes.get(upper);
+            indexes.is_some_and(|indexes| indexes.iter().any(matches))
+        }) || (indexed..self.definitions.len()).any(|index| matches(&index))
+    }
+}
+
+// Calls f with a word in upper case, only allocating for long words
+fn with_upper_case<T>(word: &str, f: impl FnOnce(&str) -> T) -> T {
+    let mut buffer = [0; 32];
+    if let Some(upper) = buffer.get_mut(..word.len()) {
+        upper.copy_from_slice(word.as_bytes());
+        upper.make_ascii_uppercase();
+        // changing the case of ASCII letters keeps UTF-8 valid
+        if let Ok(upper) = std::str::from_utf8(upper) {
+            return f(upper);
+        }
+    }
+    f(&word.to_ascii_uppercase())
+}
+
+// Name of a `FORGET name` word
+pub(crate) fn forget_target(word: &str) -> Option<&str> {
+    let (opening, name) = word.split_once(' ')?;
+    opening.eq_ignore_ascii_case("FORGET").then_some(name)
+}
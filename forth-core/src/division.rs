@@ -0,0 +1,28 @@
+// How / MOD /MOD */ and */MOD round a quotient that isn't whole. Division
+// is symmetric by default: quotients are rounded towards zero and remainders
+// take the sign of the dividend, so -7 2 /MOD gives -1 -3. Floored, they are
+// rounded towards negative infinity and remainders take the sign of the
+// divisor, so -7 2 /MOD gives 1 -4.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DivisionMode {
+    #[default]
+    Symmetric,
+    Floored,
+}
+
+impl DivisionMode {
+    // The quotient and remainder of a division by a divisor other than 0.
+    // Done in 128 bits, it can't overflow, though the quotient may not fit
+    // a cell.
+    pub(crate) fn divide(self, dividend: i128, divisor: i128) -> (i128, i128) {
+        let quotient = dividend / divisor;
+        let remainder = dividend % divisor;
+        match self {
+            DivisionMode::Floored if remainder != 0 && (remainder < 0) != (divisor < 0) => {
+                (quotient - 1, remainder + divisor)
+            }
+            _ => (quotient, remainder),
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use crate::base::{format_in_base, split_prefix};
+use crate::{Error, ErrorKind, Forth, ForthResult, Instruction, Unsigned, Value};
+
+// Double numbers take two cells, the high one on top, so that products and
+// quotients twice as wide as a cell can be chained without losing digits:
+//
+//     D+ D- DNEGATE D.   on doubles
+//     S>D ( n -- d )     a double with the same value
+//     M* ( n1 n2 -- d ) and UM* ( u1 u2 -- ud ), multiplying into a double
+//     UM/MOD ( ud u1 -- u2 u3 ), dividing a double by a cell
+//
+// A number ending with a dot, like 123. or $FF., is a double literal.
+
+// Two cells as a single number
+type Double = i128;
+type UnsignedDouble = u128;
+
+const CELL_BITS: u32 = Value::BITS;
+
+impl Forth {
+    pub(crate) fn double_word(&mut self, instruction: Instruction) -> ForthResult {
+        match instruction {
+            Instruction::DPlus | Instruction::DMinus => {
+                let [d1, d2] = self.double_operands()?;
+                let result = match instruction {
+                    Instruction::DPlus => d1.wrapping_add(d2),
+                    _ => d1.wrapping_sub(d2),
+                };
+                self.pop_cells(4)?;
+                self.push_double(result)
+            }
+            Instruction::DNegate => {
+                let [d] = self.double_operands()?;
+                self.pop_cells(2)?;
+                self.push_double(d.wrapping_neg())
+            }
+            Instruction::DDot => {
+                let [d] = self.double_operands()?;
+                self.pop_cells(2)?;
+                self.print(&format!("{} ", format_in_base(d, self.base())));
+                Ok(())
+            }
+            Instruction::SToD => {
+                let n = self.pop()?;
+                self.push_double(Double::from(n))
+            }
+            Instruction::MStar => {
+                let [n1, n2] = self.operands()?;
+                self.pop_cells(2)?;
+                self.push_double(Double::from(n1) * Double::from(n2))
+            }
+            Instruction::UmStar => {
+                let [u1, u2] = self
+                    .operands()?
+                    .map(|u| UnsignedDouble::from(u as Unsigned));
+                self.pop_cells(2)?;
+                self.push_double((u1 * u2) as Double)
+            }
+            Instruction::UmSlashMod => self.um_slash_mod(),
+            _ => Err(ErrorKind::Internal.into()),
+        }
+    }
+
+    // UM/MOD ( ud u1 -- u2 u3 ), the remainder and the quotient. A quotient
+    // too big for a cell overflows like /
+    fn um_slash_mod(&mut self) -> ForthResult {
+        let [low, high, divisor] = self.operands()?;
+        if divisor == 0 {
+            return Err(ErrorKind::DivisionByZero.into());
+        }
+        let dividend = cells_to_double(low, high) as UnsignedDouble;
+        let divisor = UnsignedDouble::from(divisor as Unsigned);
+        let quotient = self.overflow_mode.narrow_unsigned(dividend / divisor)?;
+        self.pop_cells(3)?;
+        self.push((dividend % divisor) as Value)?;
+        self.push(quotient)
+    }
+
+    // The top N doubles of the stack, the topmost last, left on it
+    fn double_operands<const N: usize>(&self) -> Result<[Double; N], Error> {
+        let start = self
+            .stack
+            .len()
+            .checked_sub(2 * N)
+            .ok_or(ErrorKind::StackUnderflow)?;
+        let mut doubles = [0; N];
+        for (double, cells) in doubles.iter_mut().zip(self.stack[start..].chunks(2)) {
+            *double = cells_to_double(cells[0], cells[1]);
+        }
+        Ok(doubles)
+    }
+
+    pub(crate) fn push_double(&mut self, double: Double) -> ForthResult {
+        self.push(double as Value)?;
+        self.push((double >> CELL_BITS) as Value)
+    }
+
+    fn pop_cells(&mut self, count: usize) -> ForthResult {
+        for _ in 0..count {
+            self.pop()?;
+        }
+        Ok(())
+    }
+}
+
+fn cells_to_double(low: Value, high: Value) -> Double {
+    (Double::from(high) << CELL_BITS) | Double::from(low as Unsigned)
+}
+
+// The double a word ending with a dot stands for, read in `base` unless it
+// has a prefix
+pub(crate) fn double_literal(word: &str, base: u32) -> Option<Double> {
+    let number = word.strip_suffix('.')?;
+    let (digits, base) = split_prefix(number).unwrap_or((number, base));
+    let (negative, digits) = match digits.strip_prefix('-') {
+        Some(digits) => (true, digits),
+        None => (false, digits),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_digit(base)) {
+        return None;
+    }
+    let magnitude = UnsignedDouble::from_str_radix(digits, base).ok()? as Double;
+    Some(if negative {
+        magnitude.wrapping_neg()
+    } else {
+        magnitude
+    })
+}
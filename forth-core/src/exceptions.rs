@@ -0,0 +1,113 @@
+use crate::{ErrorKind, Forth, ForthResult, Value};
+
+// Exceptions: a program can trap errors rather than have them end the eval.
+//
+//     CATCH ( i*x xt -- j*x 0 | i*x n )   runs xt, pushing 0 if it went
+//                                         fine, or the code of its error,
+//                                         the stack depth being as before
+//     THROW ( k*x n -- k*x | i*x n )      fails with the error of code n,
+//                                         unless n is 0
+//     ABORT                               -1 THROW
+//     ABORT" text"  ( x -- )              -2 THROW with a message, if x isn't 0
+//
+// Errors have the codes of the standard, like -4 for a stack underflow, and
+// THROW with one of them fails with that error. Running out of time or
+// steps, being cancelled and errors in the interpreter itself can't be
+// caught.
+
+impl ErrorKind {
+    // Code THROW gives the error, None if CATCH lets it through
+    pub fn throw_code(&self) -> Option<Value> {
+        let code = match self {
+            ErrorKind::Thrown(code) => *code,
+            ErrorKind::Aborted(_) => -2,
+            ErrorKind::StackOverflow => -3,
+            ErrorKind::StackUnderflow => -4,
+            ErrorKind::ReturnStackOverflow => -5,
+            ErrorKind::ReturnStackUnderflow => -6,
+            ErrorKind::InvalidAddress => -9,
+            ErrorKind::DivisionByZero => -10,
+            ErrorKind::Overflow => -11,
+            ErrorKind::UnknownWord => -13,
+            ErrorKind::UnexpectedSemicolon => -14,
+            ErrorKind::InvalidWord => -21,
+            ErrorKind::UnbalancedControlFlow => -22,
+            ErrorKind::InvalidNumber => -24,
+            ErrorKind::NestedDefinition => -29,
+            ErrorKind::CannotInclude(..) => -38,
+            ErrorKind::Included(_, error) => return error.kind().throw_code(),
+            ErrorKind::RecursionLimit => -256,
+            ErrorKind::Timeout
+            | ErrorKind::Cancelled
+            | ErrorKind::FuelExhausted
+            | ErrorKind::DictionaryFrozen
+            | ErrorKind::Internal => return None,
+        };
+        Some(code)
+    }
+}
+
+// The error a code stands for
+fn thrown_error(code: Value) -> ErrorKind {
+    match code {
+        -3 => ErrorKind::StackOverflow,
+        -4 => ErrorKind::StackUnderflow,
+        -5 => ErrorKind::ReturnStackOverflow,
+        -6 => ErrorKind::ReturnStackUnderflow,
+        -9 => ErrorKind::InvalidAddress,
+        -10 => ErrorKind::DivisionByZero,
+        -11 => ErrorKind::Overflow,
+        -13 => ErrorKind::UnknownWord,
+        -14 => ErrorKind::UnexpectedSemicolon,
+        -21 => ErrorKind::InvalidWord,
+        -22 => ErrorKind::UnbalancedControlFlow,
+        -24 => ErrorKind::InvalidNumber,
+        -29 => ErrorKind::NestedDefinition,
+        -256 => ErrorKind::RecursionLimit,
+        code => ErrorKind::Thrown(code),
+    }
+}
+
+impl Forth {
+    // CATCH ( i*x xt -- j*x 0 | i*x n )
+    pub(crate) fn catch(&mut self, visible: usize) -> ForthResult {
+        let xt = self.pop()?;
+        let depth = self.stack.len();
+        let return_depth = self.return_stack.len();
+        let float_depth = self.floats.len();
+        match self.execute_xt(xt, visible) {
+            Ok(()) => self.push(0),
+            Err(error) => {
+                let Some(code) = error.kind().throw_code() else {
+                    return Err(error);
+                };
+                // values the word took are put back as zeros
+                while self.stack.len() > depth {
+                    self.pop()?;
+                }
+                while self.stack.len() < depth {
+                    self.push(0)?;
+                }
+                self.return_stack.truncate(return_depth);
+                self.floats.resize(float_depth, 0.0);
+                self.push(code)
+            }
+        }
+    }
+
+    // THROW ( k*x n -- k*x | i*x n )
+    pub(crate) fn throw(&mut self) -> ForthResult {
+        match self.pop()? {
+            0 => Ok(()),
+            code => Err(thrown_error(code).into()),
+        }
+    }
+
+    // ABORT" text" ( x -- )
+    pub(crate) fn abort_with_message(&mut self, message: &str) -> ForthResult {
+        match self.pop()? {
+            0 => Ok(()),
+            _ => Err(ErrorKind::Aborted(message.to_string()).into()),
+        }
+    }
+}
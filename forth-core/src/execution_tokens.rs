@@ -0,0 +1,174 @@
+use crate::base::number_literal;
+use crate::variables::definition_name;
+use crate::words::Words;
+use crate::{
+    control_word, is_parsing_word, Definition, DefinitionKind, Error, ErrorKind, Forth,
+    ForthResult, Instruction, Value, BUILT_INS,
+};
+use std::sync::Arc;
+
+// Execution tokens: `' NAME` pushes a number standing for a word, which
+// EXECUTE runs. Tokens number the entries of a table of the words ticked so
+// far rather than the definitions themselves, so that built-in words and
+// nameless definitions get one too. Token 0 is never given out.
+//
+// A deferred word, from `DEFER NAME`, runs the word whose token `IS NAME`
+// gave it last: its body is that token, or nothing until IS sets it.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) enum ExecutionToken {
+    BuiltIn(Instruction),
+    Definition(usize),
+    // a definition since forgotten, its token never given out again
+    Forgotten,
+}
+
+impl Forth {
+    // ' name and ['] name ( -- xt ), looking name up like the code around it
+    pub(crate) fn tick(&mut self, word: &str, visible: usize) -> ForthResult {
+        let name = tick_target(word).ok_or(ErrorKind::Internal)?;
+        if control_word(name).is_some() || is_parsing_word(name) {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        let instruction = match self.instruction_from_word(name, visible) {
+            Err(error) if *error.kind() == ErrorKind::UnknownWord => {
+                return Err(self.unknown_word(name, visible))
+            }
+            result => result?,
+        };
+        let token = match instruction {
+            Instruction::DoubleNumber | Instruction::FloatNumber => {
+                return Err(ErrorKind::UnknownWord.into())
+            }
+            _ if number_literal(name, self.number_base(visible)).is_some() => {
+                return Err(ErrorKind::UnknownWord.into())
+            }
+            Instruction::CallDefinition(index) => ExecutionToken::Definition(index),
+            instruction => ExecutionToken::BuiltIn(instruction),
+        };
+        let xt = self.execution_token(token)?;
+        self.push(xt)
+    }
+
+    // Name of the word a token stands for, if it stands for one
+    pub fn execution_token_name(&self, xt: Value) -> Option<&str> {
+        match self.token(xt)? {
+            ExecutionToken::Definition(index) => match self.definitions[index].name.as_str() {
+                "" => Some(":NONAME"),
+                name => Some(name),
+            },
+            ExecutionToken::BuiltIn(instruction) => BUILT_INS
+                .iter()
+                .find(|&&(_, built_in)| built_in == instruction)
+                .map(|&(name, _)| name),
+            ExecutionToken::Forgotten => None,
+        }
+    }
+
+    fn token(&self, xt: Value) -> Option<ExecutionToken> {
+        let position = usize::try_from(xt).ok()?.checked_sub(1)?;
+        self.execution_tokens.get(position).copied()
+    }
+
+    fn execution_token(&mut self, token: ExecutionToken) -> Result<Value, Error> {
+        let position = match self.execution_tokens.iter().position(|&t| t == token) {
+            Some(position) => position,
+            None => {
+                self.execution_tokens.push(token);
+                self.execution_tokens.len() - 1
+            }
+        };
+        Value::try_from(position + 1).map_err(|_| ErrorKind::Internal.into())
+    }
+
+    // EXECUTE ( i*x xt -- j*x ) runs the word xt stands for
+    pub(crate) fn execute_token(&mut self, visible: usize) -> ForthResult {
+        let xt = self.pop()?;
+        self.execute_xt(xt, visible)
+    }
+
+    pub(crate) fn execute_xt(&mut self, xt: Value, visible: usize) -> ForthResult {
+        match self.token(xt).ok_or(ErrorKind::InvalidWord)? {
+            ExecutionToken::Definition(index) => self.call(index),
+            ExecutionToken::BuiltIn(instruction) => self.perform(instruction, "", visible),
+            ExecutionToken::Forgotten => Err(ErrorKind::InvalidWord.into()),
+        }
+    }
+
+    // Tokens of forgotten definitions keep their place, so that the tokens
+    // after them still stand for the same words
+    pub(crate) fn forget_execution_tokens(&mut self, first_forgotten: usize) {
+        for token in &mut self.execution_tokens {
+            if matches!(*token, ExecutionToken::Definition(index) if index >= first_forgotten) {
+                *token = ExecutionToken::Forgotten;
+            }
+        }
+    }
+
+    // `:NONAME ... ;` ( -- xt ), a definition only reachable by its token
+    pub(crate) fn add_noname(&mut self, words: &mut Words) -> ForthResult {
+        self.visibility.check_can_define()?;
+        let stack_effect = words.stack_effect();
+        let instructions = self.compile_body(words)?;
+        let creates = self.body_creates(&instructions);
+        self.definitions.push(Definition {
+            name: String::new(),
+            instructions,
+            kind: DefinitionKind::Colon,
+            stack_effect,
+            immediate: false,
+            wordlist: self.current_wordlist(),
+            creates,
+        });
+        self.index_definitions();
+        let xt = self.execution_token(ExecutionToken::Definition(self.definitions.len() - 1))?;
+        self.push(xt)
+    }
+
+    // Runs a deferred word, which fails until IS gives it a word to run
+    pub(crate) fn call_deferred(&mut self, index: usize) -> ForthResult {
+        let xt = match self.definitions[index].instructions.first() {
+            Some(xt) => xt.parse().map_err(|_| ErrorKind::Internal)?,
+            None => return Err(ErrorKind::InvalidWord.into()),
+        };
+        self.execute_xt(xt, index)
+    }
+
+    pub(crate) fn add_deferred<'a, I>(&mut self, words: &mut I) -> ForthResult
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        self.visibility.check_can_define()?;
+        let name = definition_name(words.next())?;
+        self.define(name, DefinitionKind::Deferred, Arc::from([]));
+        Ok(())
+    }
+
+    // `IS name` ( xt -- ), setting what a deferred word runs
+    pub(crate) fn defer_to(&mut self, word: &str, visible: usize) -> ForthResult {
+        let name = is_target(word).ok_or(ErrorKind::Internal)?;
+        let index = self.target_definition(name, visible)?;
+        if self.definitions[index].kind != DefinitionKind::Deferred {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        let xt = self.pop()?;
+        if self.token(xt).is_none() {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        self.definitions[index].instructions = Arc::from([self.tokens.written(xt)]);
+        Ok(())
+    }
+}
+
+// Name of a `' name` or `['] name` word
+pub(crate) fn tick_target(word: &str) -> Option<&str> {
+    let (opening, name) = word.split_once(' ')?;
+    (opening == "'" || opening == "[']").then_some(name)
+}
+
+// Name of an `IS name` word
+pub(crate) fn is_target(word: &str) -> Option<&str> {
+    let (opening, name) = word.split_once(' ')?;
+    opening.eq_ignore_ascii_case("IS").then_some(name)
+}
@@ -0,0 +1,113 @@
+use crate::memory::byte_count;
+use crate::{Error, ErrorKind, Forth, ForthResult};
+use std::fs;
+use std::io;
+use std::path::{Component, Path, PathBuf};
+
+// Where INCLUDE and INCLUDED read source files from. The default has no
+// files, for hosts keeping programs away from the file system. Like inputs,
+// files are Send.
+pub trait ForthFiles: Send {
+    // The text of the file a program named
+    fn read(&mut self, name: &str) -> io::Result<String>;
+}
+
+pub(crate) struct NoFiles;
+
+impl ForthFiles for NoFiles {
+    fn read(&mut self, _name: &str) -> io::Result<String> {
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "files can't be included here",
+        ))
+    }
+}
+
+// Files read from a directory, names being relative to it. Confined, names
+// leading out of the directory, absolute or through `..` or a link, can't
+// be read.
+pub struct DirectoryFiles {
+    root: PathBuf,
+    confined: bool,
+}
+
+impl DirectoryFiles {
+    pub fn new(root: impl Into<PathBuf>) -> DirectoryFiles {
+        DirectoryFiles {
+            root: root.into(),
+            confined: false,
+        }
+    }
+
+    pub fn confined(root: impl Into<PathBuf>) -> DirectoryFiles {
+        DirectoryFiles {
+            root: root.into(),
+            confined: true,
+        }
+    }
+}
+
+impl ForthFiles for DirectoryFiles {
+    fn read(&mut self, name: &str) -> io::Result<String> {
+        let path = self.root.join(name);
+        if self.confined {
+            let outside =
+                || io::Error::new(io::ErrorKind::PermissionDenied, "outside of the workspace");
+            if Path::new(name)
+                .components()
+                .any(|component| !matches!(component, Component::Normal(_) | Component::CurDir))
+            {
+                return Err(outside());
+            }
+            // a link may still lead out
+            if !path.canonicalize()?.starts_with(self.root.canonicalize()?) {
+                return Err(outside());
+            }
+        }
+        fs::read_to_string(path)
+    }
+}
+
+impl Forth {
+    // Evaluates a source file, an error in it naming the file and the line
+    pub(crate) fn include(&mut self, name: &str) -> ForthResult {
+        // a file including itself would never end
+        if self.including.iter().any(|file| file == name) {
+            return Err(
+                ErrorKind::CannotInclude(name.to_string(), "included already".into()).into(),
+            );
+        }
+        let source = self.files.read(name).map_err(|err| {
+            let reason = match err.kind() {
+                io::ErrorKind::NotFound => String::from("no such file"),
+                _ => err.to_string(),
+            };
+            ErrorKind::CannotInclude(name.to_string(), reason)
+        })?;
+        self.including.push(name.to_string());
+        let result = self.eval(&source);
+        self.including.pop();
+        result.map_err(|error| match error.kind() {
+            // stopping the evaluation isn't about the file's code
+            ErrorKind::Timeout | ErrorKind::Cancelled | ErrorKind::FuelExhausted => {
+                Error::new(error.into_kind(), None)
+            }
+            _ => ErrorKind::Included(name.to_string(), Box::new(error)).into(),
+        })
+    }
+
+    // INCLUDED ( c-addr u -- )
+    pub(crate) fn included(&mut self) -> ForthResult {
+        let size = byte_count(self.pop()?);
+        let address = self.pop()?;
+        let range = self.data_range(address, size)?;
+        let name = String::from_utf8_lossy(&self.data[range]).into_owned();
+        self.include(&name)
+    }
+}
+
+// Name of an `INCLUDE name` word
+pub(crate) fn include_target(word: &str) -> Option<&str> {
+    let (opening, name) = word.split_once(' ')?;
+    opening.eq_ignore_ascii_case("INCLUDE").then_some(name)
+}
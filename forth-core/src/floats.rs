@@ -0,0 +1,105 @@
+use crate::{Error, ErrorKind, Forth, ForthResult, Instruction, FALSE, TRUE};
+
+// Floating-point numbers live on a stack of their own, so that words on
+// cells don't have to tell them apart:
+//
+//     F+ F- F* F/ ( F: r1 r2 -- r3 )
+//     F. ( F: r -- )                 prints r
+//     FDUP FSWAP FDROP               like DUP SWAP DROP
+//     F< ( F: r1 r2 -- ) ( -- flag ) comparing, the flag on the data stack
+//
+// A number with an exponent, like 1.5e0 or 2E, is a float literal, only
+// read while BASE is decimal. Dividing by zero gives an infinity.
+
+pub type Float = f64;
+
+impl Forth {
+    pub fn float_stack(&self) -> &[Float] {
+        &self.floats
+    }
+
+    pub fn push_float(&mut self, float: Float) -> ForthResult {
+        if self
+            .stack_limit
+            .is_some_and(|limit| self.floats.len() >= limit)
+        {
+            return Err(ErrorKind::StackOverflow.into());
+        }
+        self.floats.push(float);
+        Ok(())
+    }
+
+    pub fn pop_float(&mut self) -> Result<Float, Error> {
+        self.floats.pop().ok_or(ErrorKind::StackUnderflow.into())
+    }
+
+    pub(crate) fn float_word(&mut self, instruction: Instruction) -> ForthResult {
+        match instruction {
+            Instruction::FPlus
+            | Instruction::FMinus
+            | Instruction::FStar
+            | Instruction::FSlash
+            | Instruction::FLess => {
+                let [r1, r2] = self.float_operands()?;
+                self.floats.truncate(self.floats.len() - 2);
+                match instruction {
+                    Instruction::FPlus => self.push_float(r1 + r2),
+                    Instruction::FMinus => self.push_float(r1 - r2),
+                    Instruction::FStar => self.push_float(r1 * r2),
+                    Instruction::FSlash => self.push_float(r1 / r2),
+                    _ => self.push(if r1 < r2 { TRUE } else { FALSE }),
+                }
+            }
+            Instruction::FDot => {
+                let r = self.pop_float()?;
+                self.print(&format!("{} ", r));
+                Ok(())
+            }
+            Instruction::FDup => {
+                let [r] = self.float_operands()?;
+                self.push_float(r)
+            }
+            Instruction::FSwap => {
+                let [r1, r2] = self.float_operands()?;
+                let top = self.floats.len() - 1;
+                self.floats[top - 1] = r2;
+                self.floats[top] = r1;
+                Ok(())
+            }
+            Instruction::FDrop => self.pop_float().map(|_| ()),
+            _ => Err(ErrorKind::Internal.into()),
+        }
+    }
+
+    // The top N floats, the topmost last, left on the float stack
+    fn float_operands<const N: usize>(&self) -> Result<[Float; N], Error> {
+        let start = self
+            .floats
+            .len()
+            .checked_sub(N)
+            .ok_or(ErrorKind::StackUnderflow)?;
+        let mut operands = [0.0; N];
+        operands.copy_from_slice(&self.floats[start..]);
+        Ok(operands)
+    }
+}
+
+// The float a word with an exponent stands for, if it is one
+pub(crate) fn float_literal(word: &str, base: u32) -> Option<Float> {
+    let exponent = word.find(['e', 'E'])?;
+    let (mantissa, exponent) = (&word[..exponent], &word[exponent + 1..]);
+    let exponent_digits = exponent.trim_start_matches(['+', '-']);
+    if base != 10
+        || !mantissa
+            .trim_start_matches(['+', '-'])
+            .starts_with(|c: char| c.is_ascii_digit())
+        || !exponent_digits.bytes().all(|b| b.is_ascii_digit())
+    {
+        return None;
+    }
+    // 2E is 2E0
+    match exponent_digits {
+        "" => mantissa.parse().ok(),
+        _ => word.parse().ok(),
+    }
+}
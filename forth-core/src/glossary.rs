@@ -0,0 +1,636 @@
+// Extracts a Markdown glossary from Forth source code.
+//
+// A definition's stack effect is the `( ... )` comment right after its name,
+// and its documentation is the block of `\` comment lines right above it :
+//
+//     \ Squares the top of the stack
+//     : SQUARE ( n -- n*n ) DUP * ;
+
+#[derive(Debug, PartialEq, Eq)]
+pub struct GlossaryEntry {
+    pub name: String,
+    pub stack_effect: Option<String>,
+    pub doc: String,
+}
+
+pub fn entries(source: &str) -> Vec<GlossaryEntry> {
+    let mut entries = Vec::new();
+    let mut doc = Vec::<&str>::new();
+
+    for line in source.lines() {
+        let trimmed = line.trim();
+        if let Some(comment) = trimmed.strip_prefix('\\') {
+            doc.push(comment.trim());
+            continue;
+        }
+
+        let mut words = trimmed.split_whitespace();
+        while let Some(word) = words.next() {
+            if word != ":" {
+                continue;
+            }
+            let name = match words.next() {
+                Some(name) => name.to_ascii_uppercase(),
+                None => break,
+            };
+            let mut rest = words.clone();
+            let stack_effect = match rest.next() {
+                Some("(") => {
+                    let mut comment = Vec::new();
+                    for word in rest {
+                        if let Some(last) = word.strip_suffix(')') {
+                            comment.push(last);
+                            break;
+                        }
+                        comment.push(word);
+                    }
+                    comment.retain(|w| !w.is_empty());
+                    Some(format!("( {} )", comment.join(" ")))
+                }
+                _ => None,
+            };
+            entries.push(GlossaryEntry {
+                name,
+                stack_effect,
+                doc: doc.join(" "),
+            });
+            doc.clear();
+        }
+        doc.clear();
+    }
+    entries
+}
+
+// Stack effect and description of the words the interpreter has built in
+const BUILT_INS: [(&str, &str, &str); 175] = [
+    ("+", "( n1 n2 -- n3 )", "Adds n1 and n2"),
+    ("-", "( n1 n2 -- n3 )", "Subtracts n2 from n1"),
+    ("*", "( n1 n2 -- n3 )", "Multiplies n1 by n2"),
+    (
+        "/",
+        "( n1 n2 -- n3 )",
+        "Divides n1 by n2, rounding towards zero unless division is floored",
+    ),
+    (
+        "MOD",
+        "( n1 n2 -- rem )",
+        "Remainder of n1 / n2, with the sign of n1 unless division is floored",
+    ),
+    (
+        "/MOD",
+        "( n1 n2 -- rem quot )",
+        "Remainder and quotient of n1 / n2",
+    ),
+    (
+        "*/",
+        "( n1 n2 n3 -- quot )",
+        "n1 * n2 / n3, without overflowing the product",
+    ),
+    (
+        "*/MOD",
+        "( n1 n2 n3 -- rem quot )",
+        "Remainder and quotient of n1 * n2 / n3",
+    ),
+    ("MIN", "( n1 n2 -- n3 )", "The lesser of n1 and n2"),
+    ("MAX", "( n1 n2 -- n3 )", "The greater of n1 and n2"),
+    ("ABS", "( n -- u )", "The absolute value of n"),
+    ("NEGATE", "( n1 -- n2 )", "n1 with the opposite sign"),
+    (
+        "WITHIN",
+        "( n lo hi -- flag )",
+        "True if lo <= n < hi, going round past the largest value when hi < lo",
+    ),
+    ("=", "( x1 x2 -- flag )", "True if x1 equals x2"),
+    ("<>", "( x1 x2 -- flag )", "True if x1 differs from x2"),
+    ("<", "( n1 n2 -- flag )", "True if n1 is less than n2"),
+    (">", "( n1 n2 -- flag )", "True if n1 is greater than n2"),
+    ("<=", "( n1 n2 -- flag )", "True if n1 is at most n2"),
+    (">=", "( n1 n2 -- flag )", "True if n1 is at least n2"),
+    (
+        "U<",
+        "( u1 u2 -- flag )",
+        "True if u1 is less than u2, both read as unsigned",
+    ),
+    (
+        "U>",
+        "( u1 u2 -- flag )",
+        "True if u1 is greater than u2, both read as unsigned",
+    ),
+    ("0=", "( x -- flag )", "True if x is 0"),
+    ("0<", "( n -- flag )", "True if n is negative"),
+    ("0>", "( n -- flag )", "True if n is positive"),
+    ("AND", "( x1 x2 -- x3 )", "Bitwise and"),
+    ("OR", "( x1 x2 -- x3 )", "Bitwise or"),
+    ("XOR", "( x1 x2 -- x3 )", "Bitwise exclusive or"),
+    ("INVERT", "( x1 -- x2 )", "Flips every bit of x1"),
+    (
+        "LSHIFT",
+        "( x1 u -- x2 )",
+        "Shifts x1 left by u bits, 0 if u is the width of a cell or more",
+    ),
+    (
+        "RSHIFT",
+        "( x1 u -- x2 )",
+        "Shifts x1 right by u bits, shifting in zeros, 0 if u is the width of a cell or more",
+    ),
+    ("2*", "( x1 -- x2 )", "Shifts x1 left by a bit"),
+    (
+        "2/",
+        "( x1 -- x2 )",
+        "Shifts x1 right by a bit, keeping its sign",
+    ),
+    ("TRUE", "( -- true )", "The true flag, -1, all bits set"),
+    ("FALSE", "( -- false )", "The false flag, 0"),
+    ("DUP", "( x -- x x )", "Duplicates the top of the stack"),
+    ("DROP", "( x -- )", "Removes the top of the stack"),
+    ("SWAP", "( x1 x2 -- x2 x1 )", "Exchanges the top two values"),
+    (
+        "OVER",
+        "( x1 x2 -- x1 x2 x1 )",
+        "Copies the second value to the top",
+    ),
+    (
+        "ROT",
+        "( x1 x2 x3 -- x2 x3 x1 )",
+        "Brings the third value to the top",
+    ),
+    (
+        "-ROT",
+        "( x1 x2 x3 -- x3 x1 x2 )",
+        "Buries the top value under the next two",
+    ),
+    ("NIP", "( x1 x2 -- x2 )", "Removes the second value"),
+    (
+        "TUCK",
+        "( x1 x2 -- x2 x1 x2 )",
+        "Copies the top value under the second one",
+    ),
+    (
+        "PICK",
+        "( xu ... x0 u -- xu ... x0 xu )",
+        "Copies the value u below the top, 0 PICK being DUP",
+    ),
+    (
+        "ROLL",
+        "( xu xu-1 ... x0 u -- xu-1 ... x0 xu )",
+        "Moves the value u below the top to the top, 2 ROLL being ROT",
+    ),
+    (
+        "2DUP",
+        "( x1 x2 -- x1 x2 x1 x2 )",
+        "Duplicates the top two values",
+    ),
+    ("2DROP", "( x1 x2 -- )", "Removes the top two values"),
+    (
+        "2SWAP",
+        "( x1 x2 x3 x4 -- x3 x4 x1 x2 )",
+        "Exchanges the top two pairs",
+    ),
+    (
+        "2OVER",
+        "( x1 x2 x3 x4 -- x1 x2 x3 x4 x1 x2 )",
+        "Copies the second pair to the top",
+    ),
+    ("?DUP", "( x -- 0 | x x )", "Duplicates x unless it is 0"),
+    (">R", "( x -- ) ( R: -- x )", "Moves x to the return stack"),
+    (
+        "R>",
+        "( -- x ) ( R: x -- )",
+        "Moves x back from the return stack",
+    ),
+    (
+        "R@",
+        "( -- x ) ( R: x -- x )",
+        "Copies the top of the return stack",
+    ),
+    (
+        "2>R",
+        "( x1 x2 -- ) ( R: -- x1 x2 )",
+        "Moves a pair to the return stack",
+    ),
+    (
+        "2R>",
+        "( -- x1 x2 ) ( R: x1 x2 -- )",
+        "Moves a pair back from the return stack",
+    ),
+    ("CELLS", "( n1 -- n2 )", "Size in address units of n1 cells"),
+    (
+        "CELL+",
+        "( addr1 -- addr2 )",
+        "Adds the size of a cell to addr1",
+    ),
+    (
+        "CHARS",
+        "( n1 -- n2 )",
+        "Size in address units of n1 characters",
+    ),
+    (
+        "CHAR+",
+        "( addr1 -- addr2 )",
+        "Adds the size of a character to addr1",
+    ),
+    ("ALIGN", "( -- )", "Aligns the data space pointer to a cell"),
+    (
+        "ALIGNED",
+        "( addr -- a-addr )",
+        "First cell aligned address from addr on",
+    ),
+    (
+        "COUNT",
+        "( c-addr -- addr len )",
+        "Address and length of a counted string",
+    ),
+    ("PAGE", "( -- )", "Clears the screen"),
+    ("AT-XY", "( column row -- )", "Moves the screen cursor"),
+    ("RANDOM", "( n -- u )", "Random number from 0 to n - 1"),
+    (
+        "KEY",
+        "( -- char )",
+        "Reads a character, -1 at the end of the input",
+    ),
+    (
+        "ACCEPT",
+        "( c-addr n1 -- n2 )",
+        "Reads a line of up to n1 characters",
+    ),
+    ("HERE", "( -- addr )", "Next free address of data space"),
+    (
+        "ALLOT",
+        "( n -- )",
+        "Reserves n address units of data space",
+    ),
+    ("@", "( a-addr -- x )", "Reads the cell at a-addr"),
+    ("!", "( x a-addr -- )", "Writes x to the cell at a-addr"),
+    ("+!", "( n a-addr -- )", "Adds n to the cell at a-addr"),
+    ("C@", "( c-addr -- char )", "Reads the byte at c-addr"),
+    (
+        "C!",
+        "( char c-addr -- )",
+        "Writes the lowest byte of char to c-addr",
+    ),
+    (",", "( x -- )", "Reserves a cell of data space holding x"),
+    ("BEGIN", "( -- )", "Starts a loop"),
+    ("AGAIN", "( -- )", "Goes back to BEGIN, forever"),
+    (
+        "UNTIL",
+        "( flag -- )",
+        "Goes back to BEGIN until flag is not 0",
+    ),
+    (
+        "WHILE",
+        "( flag -- )",
+        "Leaves a BEGIN ... REPEAT loop when flag is 0",
+    ),
+    ("REPEAT", "( -- )", "Goes back to BEGIN"),
+    (
+        "IF",
+        "( flag -- )",
+        "Runs the words up to ELSE or THEN if flag is not 0",
+    ),
+    (
+        "ELSE",
+        "( -- )",
+        "Starts the words run when the IF flag is 0",
+    ),
+    ("THEN", "( -- )", "Ends an IF"),
+    (
+        "DO",
+        "( limit start -- )",
+        "Starts a loop counting from start up to limit",
+    ),
+    (
+        "LOOP",
+        "( -- )",
+        "Adds 1 to the loop index, looping until limit",
+    ),
+    (
+        "+LOOP",
+        "( n -- )",
+        "Adds n to the loop index, looping until it crosses limit",
+    ),
+    (
+        "CASE",
+        "( x -- x )",
+        "Starts choosing the words to run by the value of x",
+    ),
+    (
+        "OF",
+        "( x1 x2 -- | x1 )",
+        "Runs the words up to ENDOF, dropping x1, if x1 equals x2",
+    ),
+    (
+        "ENDOF",
+        "( -- )",
+        "Ends an OF clause, going on after ENDCASE",
+    ),
+    (
+        "ENDCASE",
+        "( x -- )",
+        "Ends a CASE, dropping x when no OF matched it",
+    ),
+    ("RECURSE", "( -- )", "Calls the definition being run"),
+    ("'", "( \"name\" -- xt )", "Execution token of name"),
+    ("[']", "( \"name\" -- xt )", "Execution token of name"),
+    (
+        "EXECUTE",
+        "( i*x xt -- j*x )",
+        "Runs the word xt stands for",
+    ),
+    (
+        "DEFER",
+        "( \"name\" -- )",
+        "Defines name, running the word IS gives it",
+    ),
+    ("IS", "( xt \"name\" -- )", "Makes deferred name run xt"),
+    (
+        "CREATE",
+        "( \"name\" -- )",
+        "Defines name, pushing the address of the data space after it",
+    ),
+    (
+        "DOES>",
+        "( -- )",
+        "Ends a defining word, the rest running when the word it created runs",
+    ),
+    (
+        "C,",
+        "( char -- )",
+        "Reserves a byte of data space holding char",
+    ),
+    (
+        "IMMEDIATE",
+        "( -- )",
+        "Makes the latest definition run, rather than be compiled, within definitions",
+    ),
+    (
+        "STATE",
+        "( -- a-addr )",
+        "Address of a cell holding true while compiling",
+    ),
+    ("D+", "( d1 d2 -- d3 )", "Adds two double numbers"),
+    ("D-", "( d1 d2 -- d3 )", "Subtracts d2 from d1"),
+    ("DNEGATE", "( d -- -d )", "Negates a double number"),
+    (
+        "D.",
+        "( d -- )",
+        "Prints a double number followed by a space",
+    ),
+    ("S>D", "( n -- d )", "The double number with the value of n"),
+    (
+        "M*",
+        "( n1 n2 -- d )",
+        "Multiplies n1 by n2 into a double number",
+    ),
+    (
+        "UM*",
+        "( u1 u2 -- ud )",
+        "Multiplies unsigned u1 by u2 into a double number",
+    ),
+    (
+        "UM/MOD",
+        "( ud u1 -- u2 u3 )",
+        "Divides unsigned ud by u1, giving the remainder u2 and the quotient u3",
+    ),
+    ("F+", "( F: r1 r2 -- r3 )", "Adds two floats"),
+    ("F-", "( F: r1 r2 -- r3 )", "Subtracts r2 from r1"),
+    ("F*", "( F: r1 r2 -- r3 )", "Multiplies two floats"),
+    ("F/", "( F: r1 r2 -- r3 )", "Divides r1 by r2"),
+    ("F.", "( F: r -- )", "Prints a float followed by a space"),
+    ("FDUP", "( F: r -- r r )", "Duplicates the top float"),
+    ("FSWAP", "( F: r1 r2 -- r2 r1 )", "Swaps the top two floats"),
+    ("FDROP", "( F: r -- )", "Removes the top float"),
+    (
+        "F<",
+        "( F: r1 r2 -- ) ( -- flag )",
+        "True if r1 is less than r2, the flag going to the data stack",
+    ),
+    (
+        "BASE",
+        "( -- a-addr )",
+        "Address of a cell holding the base numbers are read and printed in",
+    ),
+    ("HEX", "( -- )", "Reads and prints numbers in base 16"),
+    ("DECIMAL", "( -- )", "Reads and prints numbers in base 10"),
+    ("[", "( -- )", "Runs the words up to ] within a definition"),
+    ("]", "( -- )", "Goes back to compiling after ["),
+    ("LITERAL", "( x -- )", "Compiles x into the definition"),
+    (
+        "POSTPONE",
+        "( \"name\" -- )",
+        "Compiles name, or the compiling of name when it isn't immediate",
+    ),
+    (
+        "FORGET",
+        "( \"name\" -- )",
+        "Forgets name and every definition made after it",
+    ),
+    (
+        "INCLUDE",
+        "( \"file\" -- )",
+        "Evaluates the source file named next",
+    ),
+    (
+        "INCLUDED",
+        "( c-addr u -- )",
+        "Evaluates the source file named by the string",
+    ),
+    (
+        "MARKER",
+        "( \"name\" -- )",
+        "Defines name, forgetting itself and everything defined or reserved since",
+    ),
+    (
+        "FORTH-WORDLIST",
+        "( -- wid )",
+        "The wordlist definitions go to at first",
+    ),
+    ("WORDLIST", "( -- wid )", "Makes a new, empty wordlist"),
+    (
+        "GET-ORDER",
+        "( -- widn ... wid1 n )",
+        "The search order, wid1 searched first",
+    ),
+    (
+        "SET-ORDER",
+        "( widn ... wid1 n -- )",
+        "Sets the search order, n = -1 for FORTH-WORDLIST alone",
+    ),
+    (
+        "GET-CURRENT",
+        "( -- wid )",
+        "The wordlist definitions go to",
+    ),
+    ("SET-CURRENT", "( wid -- )", "Makes definitions go to wid"),
+    (
+        "DEFINITIONS",
+        "( -- )",
+        "Makes definitions go to the first wordlist of the search order",
+    ),
+    (
+        "VOCABULARY",
+        "( \"name\" -- )",
+        "Defines name, making a wordlist of its own the first of the search order",
+    ),
+    (
+        "ALSO",
+        "( -- )",
+        "Repeats the first wordlist of the search order",
+    ),
+    (
+        "ONLY",
+        "( -- )",
+        "Leaves FORTH-WORDLIST alone in the search order",
+    ),
+    (
+        "PREVIOUS",
+        "( -- )",
+        "Takes the first wordlist out of the search order",
+    ),
+    (
+        "FORTH",
+        "( -- )",
+        "Makes FORTH-WORDLIST the first of the search order",
+    ),
+    (
+        ":NONAME",
+        "( -- xt )",
+        "Starts a definition without a name, pushing its execution token",
+    ),
+    ("I", "( -- n )", "Index of the innermost DO loop"),
+    (
+        "J",
+        "( -- n )",
+        "Index of the DO loop around the innermost one",
+    ),
+    (
+        "C\"",
+        "( -- c-addr )",
+        "Counted string of the text up to the next quote",
+    ),
+    (
+        "CHAR",
+        "( \"name\" -- xchar )",
+        "Code point of the first character of name",
+    ),
+    (
+        "[CHAR]",
+        "( \"name\" -- xchar )",
+        "Code point of the first character of name",
+    ),
+    (
+        "XC-SIZE",
+        "( xchar -- u )",
+        "Bytes of the UTF-8 encoding of xchar",
+    ),
+    (
+        "XC@+",
+        "( xc-addr1 -- xc-addr2 xchar )",
+        "Reads a character and moves past it",
+    ),
+    (
+        "XC!+",
+        "( xchar xc-addr1 -- xc-addr2 )",
+        "Writes a character and moves past it",
+    ),
+    (
+        "XCHAR+",
+        "( xc-addr1 -- xc-addr2 )",
+        "Moves past a character",
+    ),
+    (
+        "X-SIZE",
+        "( xc-addr u1 -- u2 )",
+        "Bytes of the first character of a string",
+    ),
+    ("XEMIT", "( xchar -- )", "Prints a character"),
+    (".", "( n -- )", "Prints n followed by a space"),
+    (
+        "U.",
+        "( u -- )",
+        "Prints u, read as unsigned, followed by a space",
+    ),
+    (".S", "( -- )", "Prints the depth and values of the stack"),
+    ("EMIT", "( xchar -- )", "Prints a character"),
+    ("CR", "( -- )", "Starts a new line"),
+    ("SPACE", "( -- )", "Prints a space"),
+    ("SPACES", "( n -- )", "Prints n spaces"),
+    (".\"", "( -- )", "Prints the text up to the next quote"),
+    (
+        "CATCH",
+        "( i*x xt -- j*x 0 | i*x n )",
+        "Runs xt, pushing 0, or the code of its error with the stack as deep as before",
+    ),
+    (
+        "THROW",
+        "( k*x n -- )",
+        "Fails with the error of code n, unless n is 0",
+    ),
+    ("ABORT", "( -- )", "Fails with code -1"),
+    (
+        "ABORT\"",
+        "( x -- )",
+        "Fails with code -2 and the text up to the next quote, unless x is 0",
+    ),
+    ("(", "( -- )", "Comment up to the next )"),
+    ("\\", "( -- )", "Comment up to the end of the line"),
+    (
+        "S\"",
+        "( -- c-addr u )",
+        "Address and length of the text up to the next quote",
+    ),
+    ("TYPE", "( c-addr u -- )", "Prints u bytes from c-addr"),
+    (
+        "MOVE",
+        "( addr1 addr2 u -- )",
+        "Copies u bytes from addr1 to addr2",
+    ),
+    (
+        "FILL",
+        "( c-addr u char -- )",
+        "Sets u bytes from c-addr to char",
+    ),
+    (
+        "VARIABLE",
+        "( \"name\" -- )",
+        "Defines name, pushing the address of a new cell",
+    ),
+    ("CONSTANT", "( x \"name\" -- )", "Defines name, pushing x"),
+    (
+        "VALUE",
+        "( x \"name\" -- )",
+        "Defines name, pushing x until TO changes it",
+    ),
+    ("TO", "( x \"name\" -- )", "Makes the VALUE name push x"),
+    (
+        "TEST:",
+        "( \"name\" -- )",
+        "Inline test, up to ;TEST : the words before -> must leave the stack the words after do",
+    ),
+];
+
+// Glossary entry of a built-in word, found without regard to case
+pub fn built_in(name: &str) -> Option<GlossaryEntry> {
+    BUILT_INS
+        .iter()
+        .find(|(built_in, _, _)| built_in.eq_ignore_ascii_case(name))
+        .map(|&(name, stack_effect, doc)| GlossaryEntry {
+            name: name.to_string(),
+            stack_effect: Some(stack_effect.to_string()),
+            doc: doc.to_string(),
+        })
+}
+
+pub fn markdown(title: &str, source: &str) -> String {
+    let mut text = format!("# {}\n", title);
+    for entry in entries(source) {
+        text.push_str(&format!("\n## `{}`", entry.name));
+        if let Some(stack_effect) = &entry.stack_effect {
+            text.push_str(&format!(" `{}`", stack_effect));
+        }
+        text.push('\n');
+        if !entry.doc.is_empty() {
+            text.push_str(&format!("\n{}\n", entry.doc));
+        }
+    }
+    text
+}
@@ -0,0 +1,107 @@
+use crate::{is_parsing_word, parsed_literal, Error, ErrorKind, Forth, ForthResult, Value};
+use std::sync::Arc;
+
+// Tests written next to the code, collected as the code is evaluated and run
+// on demand:
+//
+//     TEST: SQUARE 3 SQUARE -> 9 ;TEST
+//
+// The first word names the test. The words before `->` must leave the same
+// stack as the words after it, both starting from an empty stack and seeing
+// the definitions made before the test.
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InlineTest {
+    pub name: String,
+    pub input: Arc<[String]>,
+    pub expected: Arc<[String]>,
+    // definitions the test can call
+    pub(crate) visible: usize,
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum TestFailure {
+    Mismatch {
+        actual: Vec<Value>,
+        expected: Vec<Value>,
+    },
+    Error(Error),
+}
+
+pub struct TestOutcome {
+    pub name: String,
+    pub result: Result<(), TestFailure>,
+}
+
+impl Forth {
+    pub fn tests(&self) -> &[InlineTest] {
+        &self.tests
+    }
+
+    // Runs every test collected so far, in order. The stack is left as it was.
+    pub fn run_tests(&mut self) -> Vec<TestOutcome> {
+        let stack = self.stack.clone();
+        let tests = std::mem::take(&mut self.tests);
+        let outcomes = tests
+            .iter()
+            .map(|test| TestOutcome {
+                name: test.name.clone(),
+                result: self.run_test(test),
+            })
+            .collect();
+        self.tests = tests;
+        // the stack fitted before, so it fits again
+        let _ = self.replace_stack(stack);
+        outcomes
+    }
+
+    fn run_test(&mut self, test: &InlineTest) -> Result<(), TestFailure> {
+        let mut run = |words: &[String]| {
+            self.truncate(0);
+            self.eval_words(words, &[], test.visible)
+                .map(|()| self.stack.clone())
+                .map_err(TestFailure::Error)
+        };
+        let actual = run(&test.input)?;
+        let expected = run(&test.expected)?;
+        if actual == expected {
+            Ok(())
+        } else {
+            Err(TestFailure::Mismatch { actual, expected })
+        }
+    }
+
+    pub(crate) fn add_test<'a, I>(&mut self, words: &mut I) -> ForthResult
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        let name = self
+            .case_mode
+            .name(words.next().ok_or(ErrorKind::InvalidWord)?);
+        let mut input = Vec::new();
+        let mut expected: Option<Vec<String>> = None;
+        while let Some(word) = words.next() {
+            if word.eq_ignore_ascii_case(";TEST") {
+                let expected = expected.ok_or(ErrorKind::InvalidWord)?;
+                self.tests.push(InlineTest {
+                    name,
+                    input: input.into(),
+                    expected: expected.into(),
+                    visible: self.definitions.len(),
+                });
+                return Ok(());
+            } else if word == "->" && expected.is_none() {
+                expected = Some(Vec::new());
+            } else {
+                let word = if is_parsing_word(word) {
+                    parsed_literal(word, words)?
+                } else {
+                    word.to_string()
+                };
+                expected.as_mut().unwrap_or(&mut input).push(word);
+            }
+        }
+        Err(ErrorKind::InvalidWord.into())
+    }
+}
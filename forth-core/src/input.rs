@@ -0,0 +1,51 @@
+use std::io::BufRead;
+
+// Where KEY and ACCEPT read from. The default input is always at its end, for
+// hosts with nothing to type into the program. Inputs are Send, for an
+// interpreter to be moved to the thread evaluating for the host.
+pub trait ForthInput: Send {
+    // The next character, or None at the end of the input
+    fn key(&mut self) -> Option<char>;
+}
+
+pub(crate) struct NoInput;
+
+impl ForthInput for NoInput {
+    fn key(&mut self) -> Option<char> {
+        None
+    }
+}
+
+// Input read a line at a time from a reader, such as stdin. Reading errors
+// and invalid UTF-8 end the input.
+pub struct ReaderInput<R> {
+    reader: R,
+    line: String,
+    position: usize,
+}
+
+impl<R: BufRead> ReaderInput<R> {
+    pub fn new(reader: R) -> ReaderInput<R> {
+        ReaderInput {
+            reader,
+            line: String::new(),
+            position: 0,
+        }
+    }
+}
+
+impl<R: BufRead + Send> ForthInput for ReaderInput<R> {
+    fn key(&mut self) -> Option<char> {
+        if self.position >= self.line.len() {
+            self.line.clear();
+            self.position = 0;
+            match self.reader.read_line(&mut self.line) {
+                Ok(read) if read > 0 => {}
+                _ => return None,
+            }
+        }
+        let c = self.line[self.position..].chars().next()?;
+        self.position += c.len_utf8();
+        Some(c)
+    }
+}
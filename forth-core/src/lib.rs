@@ -0,0 +1,2545 @@
+mod base;
+mod builder;
+mod cancel;
+mod case_mode;
+mod code;
+mod compiler;
+mod diagnostics;
+mod dictionary;
+mod division;
+mod doubles;
+mod exceptions;
+mod execution_tokens;
+mod files;
+mod floats;
+pub mod glossary;
+mod inline_tests;
+mod input;
+mod line_cache;
+mod memory;
+mod messages;
+mod output;
+mod overflow;
+mod prelude;
+mod provenance;
+mod random;
+mod screen;
+mod shared;
+mod snapshot;
+mod stepping;
+mod strictness;
+mod suggestions;
+mod tokens;
+mod variables;
+mod visibility;
+mod wordlists;
+mod words;
+
+pub use builder::ForthBuilder;
+pub use cancel::CancelToken;
+pub use case_mode::CaseMode;
+pub use diagnostics::Diagnostic;
+pub use division::DivisionMode;
+pub use files::{DirectoryFiles, ForthFiles};
+pub use floats::Float;
+pub use inline_tests::{InlineTest, TestFailure, TestOutcome};
+pub use input::{ForthInput, ReaderInput};
+pub use line_cache::{LineCache, CHECKPOINTS_SIZE};
+pub use output::{ForthOutput, Output, WriterOutput};
+pub use overflow::OverflowMode;
+pub use provenance::Provenance;
+pub use screen::Screen;
+pub use snapshot::ForthSnapshot;
+pub use stepping::StepCursor;
+pub use strictness::Strictness;
+pub use suggestions::Suggestions;
+pub use tokens::Token;
+
+use base::number_literal;
+use code::{CompiledBody, Step};
+use compiler::postpone_target;
+use dictionary::{forget_target, Dictionary};
+use doubles::double_literal;
+use execution_tokens::{is_target, tick_target, ExecutionToken};
+use files::{include_target, NoFiles};
+use floats::float_literal;
+use input::NoInput;
+use random::Random;
+use shared::Shared;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokens::Tokens;
+use variables::{definition_name, does_index};
+use visibility::Visibility;
+use wordlists::Wordlists;
+use words::{Position, Words};
+
+pub type Value = i64;
+// a cell read as an unsigned number
+type Unsigned = u64;
+pub type ForthResult = Result<(), Error>;
+
+// Address units (bytes) in a cell; a character takes a single unit
+const CELL_SIZE: Value = std::mem::size_of::<Value>() as Value;
+
+// Bytes of data space, unless the builder allows another size: plenty for
+// code written in the editor, and little enough for the line cache and
+// snapshots to keep copies of it. Past it, ALLOT and the like fail.
+const DEFAULT_DATA_LIMIT: usize = 4 * 1024 * 1024;
+
+// Deep enough for any sensible program, shallow enough for the native stack:
+// calls and control structures each take a level, as each takes a few native
+// stack frames to run
+const MAX_NESTING: usize = 1000;
+
+// RECURSE calls nesting deeper fail with a recursion limit error, before
+// a recursive definition with a conditional or two reaches MAX_NESTING
+const DEFAULT_RECURSION_LIMIT: usize = 256;
+
+// Flags are cells with all bits set, or none
+const TRUE: Value = -1;
+const FALSE: Value = 0;
+
+pub struct Forth {
+    stack: Vec<Value>,
+    floats: Vec<Float>,
+    // values moved aside with >R and the like
+    return_stack: Vec<Value>,
+    pub definitions: Vec<Definition>,
+    // how many of the first definitions the prelude made
+    prelude: usize,
+    deadline: Option<Instant>,
+    // steps left before evaluation fails, if they are counted
+    fuel: Option<u64>,
+    stack_limit: Option<usize>,
+    tracer: Option<Tracer>,
+    cancel_token: Option<CancelToken>,
+    // how many definition calls deep the current word is
+    call_depth: usize,
+    // how many calls and control structures deep
+    nesting: usize,
+    // how many RECURSE calls deep, and how deep they can go
+    recursion_depth: usize,
+    recursion_limit: usize,
+    // what happened during the current eval_with_events call
+    events: Option<Vec<EvalEvent>>,
+    visibility: Visibility,
+    // data space, addressed in bytes from 0
+    data: Shared<Vec<u8>>,
+    // how many bytes it can grow to
+    data_limit: usize,
+    // where each distinct C" literal was stored in data space
+    counted_strings: HashMap<String, Value>,
+    // and each distinct S" literal
+    strings: HashMap<String, Value>,
+    screen: Shared<Screen>,
+    output: Shared<Output>,
+    // sink installed by the host, printed to instead of `output`
+    sink: Option<Box<dyn ForthOutput>>,
+    random: Random,
+    input: Box<dyn ForthInput>,
+    // what INCLUDE and INCLUDED read, and the files being included
+    files: Box<dyn ForthFiles>,
+    including: Vec<String>,
+    // where each value of the stack came from, when tracked
+    provenance: Option<Vec<Provenance>>,
+    // provenance of the values the running built-in word pushes
+    origin: Option<Provenance>,
+    tests: Vec<InlineTest>,
+    // name given to the CREATE run by the current top-level word
+    next_name: Option<String>,
+    // body of the colon definition being compiled, if one is
+    compiling: Option<Vec<Token>>,
+    // emptied, its room kept for the next body compiled
+    compile_buffer: Vec<Token>,
+    // the words compiled into bodies, each stored once
+    tokens: Tokens,
+    // STATE's cell, once asked for
+    state_address: Option<Value>,
+    // the base numbers are read and printed in, until BASE's cell holds it
+    base: Value,
+    base_address: Option<Value>,
+    // each definition's code, once compiled
+    code: Vec<Option<CompiledBody>>,
+    dictionary: Dictionary,
+    wordlists: Wordlists,
+    // what each execution token stands for, from token 1 on
+    execution_tokens: Vec<ExecutionToken>,
+    // index and limit of each running DO loop, the innermost last
+    loops: Vec<(Value, Value)>,
+    overflow_mode: OverflowMode,
+    division_mode: DivisionMode,
+    strictness: Strictness,
+    case_mode: CaseMode,
+    // unknown words skipped in tolerant mode, the oldest first
+    warnings: Vec<Error>,
+}
+
+// Called after each word is executed
+pub type Tracer = Box<dyn FnMut(&TraceEvent) + Send>;
+
+pub struct TraceEvent<'a> {
+    pub word: &'a str,
+    pub depth: usize,
+    pub stack: &'a [Value],
+}
+
+#[derive(Debug, PartialEq, Eq)]
+pub enum EvalEvent {
+    Pushed(Value),
+    Popped(Value),
+    Defined(String),
+    // a definition replacing a word that already existed, built-in or not
+    Redefined(String),
+    // a definition removed by FORGET or a marker
+    Forgotten(String),
+    // text printed, one event for what is printed between other events
+    Output(String),
+}
+
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Definition {
+    pub name: String,
+    // shared, so that calling a definition doesn't copy its body
+    pub instructions: Arc<[Token]>,
+    pub kind: DefinitionKind,
+    // the `( before -- after )` comment right after a colon definition's name
+    pub stack_effect: Option<String>,
+    // run rather than compiled within colon definitions, from IMMEDIATE
+    pub immediate: bool,
+    // the wordlist it was defined in, 0 being FORTH-WORDLIST
+    pub wordlist: usize,
+    // whether running it runs CREATE, itself or through a definition it
+    // calls, so that it takes the name after it at the top level
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub creates: bool,
+}
+
+// The word a definition was made with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DefinitionKind {
+    Colon,
+    Variable,
+    Constant,
+    Value,
+    Deferred,
+    Created,
+    Marker,
+    Vocabulary,
+}
+
+// An error, and the word of the evaluated code it happened at. Boxed, so
+// that results stay a pointer wide, deep calls returning them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Error(Box<Details>);
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Details {
+    kind: ErrorKind,
+    location: Option<Location>,
+    suggestions: Option<Suggestions>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Location {
+    // as written
+    pub word: String,
+    // where the word starts, counting lines and characters from 1
+    pub line: usize,
+    pub column: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ErrorKind {
+    DivisionByZero,
+    StackUnderflow,
+    StackOverflow,
+    UnknownWord,
+    InvalidWord,
+    Timeout,
+    Cancelled,
+    // the steps evaluation was given all taken
+    FuelExhausted,
+    // definitions calling each other, or control structures nested, deeper
+    // than MAX_NESTING
+    ReturnStackOverflow,
+    // R> and the like with too few values on the return stack
+    ReturnStackUnderflow,
+    DictionaryFrozen,
+    // a data space address out of the data space
+    InvalidAddress,
+    // a bug in the interpreter rather than in the program
+    Internal,
+    // a control word without its match, like an IF without its THEN, or I
+    // outside of a DO loop
+    UnbalancedControlFlow,
+    // RECURSE calls nested deeper than the recursion limit
+    RecursionLimit,
+    // THROW with a code no other error has, -1 for ABORT
+    Thrown(Value),
+    // ABORT" and its message
+    Aborted(String),
+    // a number with a prefix, like $ or 0x, and digits its base doesn't
+    // have, or a character literal with more than a character between quotes
+    InvalidNumber,
+    // arithmetic with a result too big for a cell, in checked overflow mode
+    Overflow,
+    // a ; ending no definition
+    UnexpectedSemicolon,
+    // a : in the body of a definition, before its ;
+    NestedDefinition,
+    // a file INCLUDED couldn't be read, and why
+    CannotInclude(String, String),
+    // an error in a file INCLUDED, located in the file
+    Included(String, Box<Error>),
+}
+
+impl From<ErrorKind> for Error {
+    fn from(kind: ErrorKind) -> Error {
+        Error::new(kind, None)
+    }
+}
+
+impl Error {
+    pub fn new(kind: ErrorKind, location: Option<Location>) -> Error {
+        Error(Box::new(Details {
+            kind,
+            location,
+            suggestions: None,
+        }))
+    }
+
+    pub fn with_suggestions(mut self, suggestions: Suggestions) -> Error {
+        self.0.suggestions = Some(suggestions);
+        self
+    }
+
+    pub fn kind(&self) -> &ErrorKind {
+        &self.0.kind
+    }
+
+    pub fn into_kind(self) -> ErrorKind {
+        self.0.kind
+    }
+
+    // None when the error isn't about evaluated code
+    pub fn location(&self) -> Option<&Location> {
+        self.0.location.as_ref()
+    }
+
+    // For an unknown word, the words it may have been meant as
+    pub fn suggestions(&self) -> Option<&Suggestions> {
+        self.0.suggestions.as_ref()
+    }
+
+    // The error at a word of the evaluated code, unless it is at one already.
+    // Kept out of run, which calls it, for its stack frame to stay small.
+    #[cold]
+    #[inline(never)]
+    fn at(mut self, word: &str, position: Option<&Position>) -> Error {
+        if let (None, Some(&(line, column))) = (&self.0.location, position) {
+            self.0.location = Some(Location {
+                word: word.to_string(),
+                line,
+                column,
+            });
+        }
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Instruction {
+    Number(Value),
+    // a double number literal, read from the word when it runs
+    DoubleNumber,
+    // and a float literal
+    FloatNumber,
+    Add,
+    Subtract,
+    Multiply,
+    Divide,
+    Min,
+    Max,
+    Abs,
+    Negate,
+    Within,
+    Mod,
+    DivMod,
+    StarSlash,
+    StarSlashMod,
+    Dup,
+    Drop,
+    Over,
+    Swap,
+    Rot,
+    MinusRot,
+    Nip,
+    Tuck,
+    TwoDup,
+    TwoDrop,
+    TwoSwap,
+    TwoOver,
+    Pick,
+    Roll,
+    QuestionDup,
+    ToR,
+    FromR,
+    RFetch,
+    TwoToR,
+    TwoFromR,
+    Cells,
+    CellPlus,
+    Chars,
+    CharPlus,
+    Align,
+    Aligned,
+    Count,
+    Page,
+    AtXy,
+    Random,
+    Key,
+    Accept,
+    Here,
+    Allot,
+    Fetch,
+    Store,
+    PlusStore,
+    CFetch,
+    CStore,
+    Comma,
+    XcSize,
+    XcFetchPlus,
+    XcStorePlus,
+    XcharPlus,
+    XSize,
+    Xemit,
+    Dot,
+    UDot,
+    DotS,
+    Emit,
+    Cr,
+    Space,
+    Spaces,
+    // calls the definition it is part of
+    Recurse,
+    Execute,
+    Create,
+    Does,
+    CComma,
+    Immediate,
+    State,
+    DPlus,
+    DMinus,
+    DNegate,
+    DDot,
+    SToD,
+    MStar,
+    UmStar,
+    UmSlashMod,
+    FPlus,
+    FMinus,
+    FStar,
+    FSlash,
+    FDot,
+    FDup,
+    FSwap,
+    FDrop,
+    FLess,
+    Base,
+    Hex,
+    Decimal,
+    LeftBracket,
+    RightBracket,
+    Literal,
+    Postpone,
+    Forget,
+    // INCLUDE with its file's name, and INCLUDED
+    Include,
+    Included,
+    ForthWordlist,
+    Wordlist,
+    GetOrder,
+    SetOrder,
+    GetCurrent,
+    SetCurrent,
+    Definitions,
+    Also,
+    Only,
+    Previous,
+    Forth,
+    Type,
+    Move,
+    Fill,
+    Equal,
+    NotEqual,
+    Less,
+    Greater,
+    LessOrEqual,
+    GreaterOrEqual,
+    ULess,
+    UGreater,
+    ZeroEqual,
+    ZeroLess,
+    ZeroGreater,
+    And,
+    Or,
+    Xor,
+    Invert,
+    LShift,
+    RShift,
+    TwoStar,
+    TwoSlash,
+    // index of the innermost DO loop, and of the one around it
+    LoopIndex,
+    OuterLoopIndex,
+    // a `C" text"` literal, gathered into a single word
+    CountedString,
+    // a `S" text"`, gathered into a single word too
+    String,
+    // a `." text"`, gathered into a single word too
+    PrintString,
+    AbortQuote,
+    Catch,
+    Throw,
+    Abort,
+    // a `TO name` gathered into a single word too
+    To,
+    // a `' name` or `['] name` gathered into a single word too
+    Tick,
+    // an `IS name` gathered into a single word too
+    Is,
+    // loop control, only valid within a loop's own words
+    Begin,
+    Again,
+    // conditionals, only valid within their own words too
+    If,
+    Else,
+    Then,
+    Do,
+    Loop,
+    PlusLoop,
+    Until,
+    While,
+    Repeat,
+    Case,
+    Of,
+    Endof,
+    Endcase,
+    CallDefinition(usize),
+}
+
+impl Default for Forth {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Forth {
+    // An interpreter with the prelude's words defined, the same as
+    // `Forth::builder().build()`
+    pub fn new() -> Forth {
+        ForthBuilder::new().build()
+    }
+
+    // And one with only the words built into the interpreter
+    pub fn new_bare() -> Forth {
+        Forth {
+            stack: Vec::<Value>::new(),
+            floats: Vec::new(),
+            return_stack: Vec::new(),
+            definitions: Vec::<Definition>::new(),
+            prelude: 0,
+            deadline: None,
+            fuel: None,
+            stack_limit: None,
+            tracer: None,
+            cancel_token: None,
+            call_depth: 0,
+            nesting: 0,
+            recursion_depth: 0,
+            recursion_limit: DEFAULT_RECURSION_LIMIT,
+            events: None,
+            visibility: Visibility::default(),
+            data: Shared::default(),
+            data_limit: DEFAULT_DATA_LIMIT,
+            counted_strings: HashMap::new(),
+            strings: HashMap::new(),
+            screen: Shared::default(),
+            output: Shared::default(),
+            sink: None,
+            random: Random::default(),
+            input: Box::new(NoInput),
+            files: Box::new(NoFiles),
+            including: Vec::new(),
+            provenance: None,
+            origin: None,
+            tests: Vec::new(),
+            next_name: None,
+            compiling: None,
+            compile_buffer: Vec::new(),
+            tokens: Tokens::default(),
+            state_address: None,
+            base: 10,
+            base_address: None,
+            code: Vec::new(),
+            dictionary: Dictionary::default(),
+            wordlists: Wordlists::default(),
+            execution_tokens: Vec::new(),
+            loops: Vec::new(),
+            overflow_mode: OverflowMode::default(),
+            division_mode: DivisionMode::default(),
+            strictness: Strictness::default(),
+            case_mode: CaseMode::default(),
+            warnings: Vec::new(),
+        }
+    }
+
+    pub fn builder() -> ForthBuilder {
+        ForthBuilder::new()
+    }
+
+    pub fn stack(&self) -> &[Value] {
+        &self.stack[..]
+    }
+
+    pub fn return_stack(&self) -> &[Value] {
+        &self.return_stack
+    }
+
+    // What the program drew with PAGE and AT-XY
+    pub fn screen(&self) -> &Screen {
+        &self.screen
+    }
+
+    pub fn screen_mut(&mut self) -> &mut Screen {
+        &mut self.screen
+    }
+
+    // What the program printed, unless the builder was given another output
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    pub fn output_mut(&mut self) -> &mut Output {
+        &mut self.output
+    }
+
+    // Seed of the numbers RANDOM returned, if it returned any or was given one
+    pub fn seed(&self) -> Option<u64> {
+        self.random.seed()
+    }
+
+    pub fn overflow_mode(&self) -> OverflowMode {
+        self.overflow_mode
+    }
+
+    pub fn set_overflow_mode(&mut self, mode: OverflowMode) {
+        self.overflow_mode = mode;
+    }
+
+    pub fn division_mode(&self) -> DivisionMode {
+        self.division_mode
+    }
+
+    pub fn set_division_mode(&mut self, mode: DivisionMode) {
+        self.division_mode = mode;
+    }
+
+    pub fn strictness(&self) -> Strictness {
+        self.strictness
+    }
+
+    pub fn set_strictness(&mut self, strictness: Strictness) {
+        self.strictness = strictness;
+    }
+
+    pub fn case_mode(&self) -> CaseMode {
+        self.case_mode
+    }
+
+    // Applies to the definitions made from then on, those made before keep
+    // their names
+    pub fn set_case_mode(&mut self, mode: CaseMode) {
+        self.case_mode = mode;
+    }
+
+    // The unknown words skipped in tolerant mode, as their errors, in order
+    pub fn warnings(&self) -> &[Error] {
+        &self.warnings
+    }
+
+    pub fn take_warnings(&mut self) -> Vec<Error> {
+        std::mem::take(&mut self.warnings)
+    }
+
+    // Provenance of each stack value, from the bottom, if tracked
+    pub fn provenance(&self) -> Option<&[Provenance]> {
+        self.provenance.as_deref()
+    }
+
+    pub fn push(&mut self, value: Value) -> ForthResult {
+        if self
+            .stack_limit
+            .is_some_and(|limit| self.stack.len() >= limit)
+        {
+            return Err(ErrorKind::StackOverflow.into());
+        }
+        self.stack.push(value);
+        if let Some(provenance) = &mut self.provenance {
+            provenance.push(self.origin.unwrap_or(Provenance::Host));
+        }
+        self.record(EvalEvent::Pushed(value));
+        Ok(())
+    }
+
+    pub fn peek(&self) -> Result<Value, Error> {
+        self.stack
+            .last()
+            .copied()
+            .ok_or(ErrorKind::StackUnderflow.into())
+    }
+
+    // Keeps the bottom `depth` values
+    pub fn truncate(&mut self, depth: usize) {
+        self.stack.truncate(depth);
+        if let Some(provenance) = &mut self.provenance {
+            provenance.truncate(depth);
+        }
+    }
+
+    pub fn replace_stack(&mut self, values: Vec<Value>) -> ForthResult {
+        if self.stack_limit.is_some_and(|limit| values.len() > limit) {
+            return Err(ErrorKind::StackOverflow.into());
+        }
+        if let Some(provenance) = &mut self.provenance {
+            // values left as they were keep their provenance
+            let kept = values
+                .iter()
+                .zip(&self.stack)
+                .take_while(|(new, old)| new == old)
+                .count();
+            provenance.truncate(kept);
+            provenance.resize(values.len(), Provenance::Host);
+        }
+        self.stack = values;
+        Ok(())
+    }
+
+    pub fn pop(&mut self) -> Result<Value, Error> {
+        match self.stack.pop() {
+            Some(value) => {
+                if let Some(provenance) = &mut self.provenance {
+                    provenance.pop();
+                }
+                self.record(EvalEvent::Popped(value));
+                Ok(value)
+            }
+            _ => Err(ErrorKind::StackUnderflow.into()),
+        }
+    }
+
+    // Instructions the body of a definition resolves to when it is called
+    pub fn compiled_definition(&self, index: usize) -> Result<Vec<Instruction>, Error> {
+        let definition = self.definitions.get(index).ok_or(ErrorKind::UnknownWord)?;
+        definition
+            .instructions
+            .iter()
+            .map(|word| self.instruction_from_word(word, index))
+            .collect()
+    }
+
+    // Errors are located at the word of the input they happened at
+    pub fn eval(&mut self, input: &str) -> ForthResult {
+        let mut words = Words::new(input);
+        while let Some(word) = words.next() {
+            let position = words.word_position();
+            let warned = self.warnings.len();
+            let result = self.eval_word(word, &mut words);
+            self.locate_warnings(warned, word, Some(&position));
+            result.map_err(|error| error.at(word, Some(&position)))?;
+        }
+        Ok(())
+    }
+
+    // Evaluates a word of the input, and those after it it takes
+    fn eval_word(&mut self, word: &str, words: &mut Words) -> ForthResult {
+        match word {
+            ":" => self.add_definition(words)?,
+            _ if word.eq_ignore_ascii_case(":NONAME") => self.add_noname(words)?,
+            _ if word.eq_ignore_ascii_case("TEST:") => self.add_test(words)?,
+            _ if word.eq_ignore_ascii_case("VARIABLE") => self.add_variable(words)?,
+            _ if word.eq_ignore_ascii_case("CONSTANT") => {
+                self.add_constant(DefinitionKind::Constant, words)?
+            }
+            _ if word.eq_ignore_ascii_case("VALUE") => {
+                self.add_constant(DefinitionKind::Value, words)?
+            }
+            _ if word.eq_ignore_ascii_case("DEFER") => self.add_deferred(words)?,
+            _ if word.eq_ignore_ascii_case("MARKER") => self.add_marker(words)?,
+            _ if word.eq_ignore_ascii_case("VOCABULARY") => self.add_vocabulary(words)?,
+            _ if is_parsing_word(word) => {
+                let literal = parsed_literal(word, words)?;
+                self.eval_instruction(&literal, self.definitions.len())?
+            }
+            _ if is_opening(word) => {
+                // a structure's words are only collected when there is one
+                let mut body = vec![word.to_string()];
+                let mut positions = vec![words.word_position()];
+                let mut depth = 1;
+                while let Some(word) = words.next() {
+                    positions.push(words.word_position());
+                    if is_parsing_word(word) {
+                        body.push(parsed_literal(word, words)?);
+                        continue;
+                    }
+                    body.push(word.to_string());
+                    if is_opening(word) {
+                        depth += 1;
+                    } else if is_closing(word) {
+                        depth -= 1;
+                    }
+                    if depth == 0 {
+                        break;
+                    }
+                }
+                self.eval_words(&body, &positions, self.definitions.len())?
+            }
+            _ if self.takes_name(word, self.definitions.len()) => {
+                self.next_name = words.next().map(str::to_string);
+                let result = self.eval_instruction(word, self.definitions.len());
+                self.next_name = None;
+                result?
+            }
+            _ => self.eval_instruction(word, self.definitions.len())?,
+        };
+        Ok(())
+    }
+
+    // Evaluates like eval, failing once it has taken `max_steps` steps: each
+    // word run takes one, and so does each round of a loop. The interpreter's
+    // own budget, if any, is left as it was.
+    pub fn eval_with_fuel(&mut self, input: &str, max_steps: u64) -> ForthResult {
+        let fuel = self.fuel.replace(max_steps);
+        let result = self.eval(input);
+        self.fuel = fuel;
+        result
+    }
+
+    // Steps left before evaluation fails, if they are counted
+    pub fn fuel(&self) -> Option<u64> {
+        self.fuel
+    }
+
+    // Evaluates like eval, failing with ErrorKind::Cancelled once another
+    // thread cancels the token, instead of the interpreter's own token if any
+    pub fn eval_cancellable(&mut self, input: &str, token: &CancelToken) -> ForthResult {
+        let own_token = self.cancel_token.replace(token.clone());
+        let result = self.eval(input);
+        self.cancel_token = own_token;
+        result
+    }
+
+    // Evaluates like eval, failing with ErrorKind::Timeout once it has run
+    // for longer than `timeout`, or past the interpreter's own deadline
+    pub fn eval_with_timeout(&mut self, input: &str, timeout: Duration) -> ForthResult {
+        let own_deadline = self.deadline;
+        if let Some(deadline) = Instant::now().checked_add(timeout) {
+            self.deadline = Some(own_deadline.map_or(deadline, |own| own.min(deadline)));
+        }
+        let result = self.eval(input);
+        self.deadline = own_deadline;
+        result
+    }
+
+    // Evaluates like eval, also reporting what the evaluation did
+    pub fn eval_with_events(&mut self, input: &str) -> Result<Vec<EvalEvent>, Error> {
+        self.events = Some(Vec::new());
+        let result = self.eval(input);
+        let events = self.events.take().unwrap_or_default();
+        result.map(|()| events)
+    }
+
+    fn record(&mut self, event: EvalEvent) {
+        if let Some(events) = &mut self.events {
+            events.push(event);
+        }
+    }
+
+    // Only the first `visible` definitions can be called: a definition's body sees
+    // the definitions made before it, not itself nor later ones
+    fn instruction_from_word(&self, word: &str, visible: usize) -> Result<Instruction, Error> {
+        if word == ";" {
+            return Err(ErrorKind::UnexpectedSemicolon.into());
+        }
+        if let Some(instruction) = control_word(word) {
+            return Ok(instruction);
+        }
+        match string_literal(word) {
+            Some(("C\"", _)) => return Ok(Instruction::CountedString),
+            Some(("S\"", _)) => return Ok(Instruction::String),
+            Some(("ABORT\"", _)) => return Ok(Instruction::AbortQuote),
+            Some(_) => return Ok(Instruction::PrintString),
+            None => {}
+        }
+        if to_target(word).is_some() {
+            return Ok(Instruction::To);
+        }
+        if tick_target(word).is_some() {
+            return Ok(Instruction::Tick);
+        }
+        if is_target(word).is_some() {
+            return Ok(Instruction::Is);
+        }
+        if postpone_target(word).is_some() {
+            return Ok(Instruction::Postpone);
+        }
+        if forget_target(word).is_some() {
+            return Ok(Instruction::Forget);
+        }
+        if include_target(word).is_some() {
+            return Ok(Instruction::Include);
+        }
+        if let Some(c) = char_literal(word) {
+            return Ok(Instruction::Number(c as Value));
+        }
+        let instruction = match self.find_definition(word, visible) {
+            Some(index) => Instruction::CallDefinition(index),
+            None => match built_in(word) {
+                Some(instruction) => instruction,
+                None => {
+                    let base = self.number_base(visible);
+                    match number_literal(word, base) {
+                        _ if double_literal(word, base).is_some() => Instruction::DoubleNumber,
+                        Some(number) => Instruction::Number(number?),
+                        None if float_literal(word, base).is_some() => Instruction::FloatNumber,
+                        None => return Err(ErrorKind::UnknownWord.into()),
+                    }
+                }
+            },
+        };
+        if self.visibility.allows(word, &instruction, visible) {
+            Ok(instruction)
+        } else {
+            Err(ErrorKind::UnknownWord.into())
+        }
+    }
+
+    fn add_definition(&mut self, words: &mut Words) -> ForthResult {
+        self.visibility.check_can_define()?;
+        // cannot redefine numbers, nor control words !
+        let definition_name = definition_name(words.next())?;
+        let stack_effect = words.stack_effect();
+        let definition_instructions = self.compile_body(words)?;
+        self.define(
+            definition_name,
+            DefinitionKind::Colon,
+            definition_instructions,
+        );
+        if let Some(definition) = self.definitions.last_mut() {
+            definition.stack_effect = stack_effect;
+        }
+        self.definition_code(self.definitions.len() - 1);
+        Ok(())
+    }
+
+    // Runs words, looking each of them up first. Errors are located at the
+    // words with a position, if any.
+    fn eval_words<S: AsRef<str>>(
+        &mut self,
+        words: &[S],
+        positions: &[Position],
+        visible: usize,
+    ) -> ForthResult {
+        let code: Vec<Step> = self.compile_code(words, visible);
+        let body = Body {
+            words,
+            code: &code,
+            positions,
+        };
+        self.run(body, visible)
+    }
+
+    // Runs a body in order, repeating the words of a BEGIN loop until it ends,
+    // or until an error, a timeout or a cancellation stops it, running the
+    // words between IF and THEN only when the flag on top of the stack is
+    // true, counting DO loops and picking the clause of a CASE
+    fn run<S: AsRef<str>>(&mut self, body: Body<S>, visible: usize) -> ForthResult {
+        let mut index = 0;
+        while let Some(&step) = body.code.get(index) {
+            let word = body.words[index].as_ref();
+            if let Some(
+                opening @ (Instruction::Begin
+                | Instruction::If
+                | Instruction::Do
+                | Instruction::Case),
+            ) = step
+            {
+                match self.run_structure(opening, body.from(index + 1), visible) {
+                    Ok(length) => index += length + 1,
+                    Err(error) => return Err(error.at(word, body.positions.get(index))),
+                }
+                continue;
+            }
+            if let Err(error) = self.run_step(word, step, visible) {
+                return Err(error.at(word, body.positions.get(index)));
+            }
+            // an unknown word run without an error was skipped with a warning
+            if step.is_none() {
+                let warned = self.warnings.len().saturating_sub(1);
+                self.locate_warnings(warned, word, body.positions.get(index));
+            }
+            index += 1;
+        }
+        Ok(())
+    }
+
+    // Runs the structure an opening word starts, given the body following
+    // it, and returns how many words it took. Kept out of run, whose stack
+    // frame is paid once per nested call.
+    fn run_structure<S: AsRef<str>>(
+        &mut self,
+        opening: Instruction,
+        body: Body<S>,
+        visible: usize,
+    ) -> Result<usize, Error> {
+        self.enter()?;
+        let result = match opening {
+            Instruction::Begin => self.run_indefinite_loop(body, visible),
+            Instruction::If => self.run_conditional(body, visible),
+            Instruction::Do => self.run_counted_loop(body, visible),
+            Instruction::Case => self.run_case(body, visible),
+            _ => Err(ErrorKind::Internal.into()),
+        };
+        self.nesting -= 1;
+        result
+    }
+
+    // Runs the branch the flag selects, given the body following an IF, and
+    // returns how many words the conditional took up to its THEN included
+    fn run_conditional<S: AsRef<str>>(
+        &mut self,
+        body: Body<S>,
+        visible: usize,
+    ) -> Result<usize, Error> {
+        let (else_index, then_index) = conditional_parts(body.code)?;
+        self.check_interrupted()?;
+        let branch = match (self.pop()? != 0, else_index) {
+            (true, Some(else_index)) => body.part(0, else_index),
+            (true, None) => body.part(0, then_index),
+            (false, Some(else_index)) => body.part(else_index + 1, then_index),
+            (false, None) => body.part(0, 0),
+        };
+        self.run(branch, visible)?;
+        Ok(then_index + 1)
+    }
+
+    // Runs the clause whose OF value equals the selector on top of the stack,
+    // or else the words before ENDCASE, given the body following a CASE, and
+    // returns how many words it took up to its ENDCASE included. The selector
+    // is dropped by the OF matching it, or by ENDCASE.
+    fn run_case<S: AsRef<str>>(&mut self, body: Body<S>, visible: usize) -> Result<usize, Error> {
+        let (clauses, end) = case_parts(body.code)?;
+        let mut start = 0;
+        for (of_index, endof_index) in clauses {
+            self.check_interrupted()?;
+            self.run(body.part(start, of_index), visible)?;
+            let value = self.pop()?;
+            if self.peek()? == value {
+                self.pop()?;
+                self.run(body.part(of_index + 1, endof_index), visible)?;
+                return Ok(end + 1);
+            }
+            start = endof_index + 1;
+        }
+        self.run(body.part(start, end), visible)?;
+        self.pop()?;
+        Ok(end + 1)
+    }
+
+    // Runs a BEGIN loop, given the body following its BEGIN, and returns how
+    // many words it took up to its AGAIN, UNTIL or REPEAT included. UNTIL ends
+    // the loop when the flag it pops is true, WHILE when the flag is false.
+    fn run_indefinite_loop<S: AsRef<str>>(
+        &mut self,
+        body: Body<S>,
+        visible: usize,
+    ) -> Result<usize, Error> {
+        let (while_index, end) = loop_parts(body.code)?;
+        let (repeated, rest) = match while_index {
+            Some(while_index) => (body.part(0, while_index), body.part(while_index + 1, end)),
+            None => (body.part(0, end), body.part(0, 0)),
+        };
+        let until = matches!(body.code[end], Some(Instruction::Until));
+        loop {
+            self.check_interrupted()?;
+            self.run(repeated, visible)?;
+            if until && self.pop()? != 0 || while_index.is_some() && self.pop()? == 0 {
+                return Ok(end + 1);
+            }
+            self.run(rest, visible)?;
+        }
+    }
+
+    // Runs a DO loop ( limit start -- ), given the body following its DO,
+    // and returns how many words it took up to its LOOP or +LOOP included
+    fn run_counted_loop<S: AsRef<str>>(
+        &mut self,
+        body: Body<S>,
+        visible: usize,
+    ) -> Result<usize, Error> {
+        let end = counted_loop_length(body.code)?;
+        let step_from_stack = matches!(body.code[end], Some(Instruction::PlusLoop));
+        let start = self.pop()?;
+        let limit = self.pop()?;
+        self.loops.push((start, limit));
+        let result = self.repeat_counted_loop(body.part(0, end), step_from_stack, visible);
+        self.loops.pop();
+        result.map(|()| end + 1)
+    }
+
+    fn repeat_counted_loop<S: AsRef<str>>(
+        &mut self,
+        body: Body<S>,
+        step_from_stack: bool,
+        visible: usize,
+    ) -> ForthResult {
+        loop {
+            self.check_interrupted()?;
+            self.run(body, visible)?;
+            let step = if step_from_stack { self.pop()? } else { 1 };
+            let Some((index, limit)) = self.loops.last_mut() else {
+                debug_assert!(false, "DO loop without its index");
+                return Err(ErrorKind::Internal.into());
+            };
+            let before = index.wrapping_sub(*limit);
+            *index = index.wrapping_add(step);
+            let after = index.wrapping_sub(*limit);
+            // the loop ends once the index crosses from limit - 1 to limit, one
+            // way or the other, which is when the index minus the limit changes
+            // sign while moving in the direction of the step
+            if (before ^ after) < 0 && (before ^ step) < 0 {
+                return Ok(());
+            }
+        }
+    }
+
+    // Index of the DO loop `depth` loops out from the innermost one
+    fn loop_index(&mut self, depth: usize) -> ForthResult {
+        let position = self.loops.len().checked_sub(depth + 1);
+        let (index, _) = position
+            .map(|position| self.loops[position])
+            .ok_or(ErrorKind::UnbalancedControlFlow)?;
+        self.push(index)
+    }
+
+    fn check_interrupted(&mut self) -> ForthResult {
+        if let Some(fuel) = &mut self.fuel {
+            *fuel = fuel.checked_sub(1).ok_or(ErrorKind::FuelExhausted)?;
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| Instant::now() >= deadline)
+        {
+            return Err(ErrorKind::Timeout.into());
+        }
+        if self
+            .cancel_token
+            .as_ref()
+            .is_some_and(CancelToken::is_cancelled)
+        {
+            return Err(ErrorKind::Cancelled.into());
+        }
+        Ok(())
+    }
+
+    fn eval_instruction(&mut self, word: &str, visible: usize) -> ForthResult {
+        let step = self.instruction_from_word(word, visible).ok();
+        self.run_step(word, step, visible)
+    }
+
+    fn run_step(&mut self, word: &str, step: Step, visible: usize) -> ForthResult {
+        self.check_interrupted()?;
+        let instruction = match step {
+            Some(instruction) => instruction,
+            // looked up again, for why it wasn't found
+            None => {
+                let error = match self.instruction_from_word(word, visible) {
+                    Err(error) if *error.kind() == ErrorKind::UnknownWord => {
+                        self.unknown_word(word, visible)
+                    }
+                    Err(error) => error,
+                    Ok(_) => self.unknown_word(word, visible),
+                };
+                return self.unknown_word_run(error);
+            }
+        };
+        let shuffled = self.shuffled_provenance(instruction);
+        if self.provenance.is_some() {
+            self.origin = origin(instruction);
+        }
+        // calls stay out of execute's big match, whose stack frame would
+        // otherwise be paid once per nested call
+        let result = self.perform(instruction, word, visible);
+        self.origin = None;
+        if let (Ok(()), Some(shuffled), Some(provenance)) =
+            (&result, shuffled, self.provenance.as_mut())
+        {
+            let start = provenance.len().saturating_sub(shuffled.len());
+            provenance.truncate(start);
+            provenance.extend(shuffled);
+        }
+        if let (Ok(()), Some(tracer)) = (&result, self.tracer.as_mut()) {
+            tracer(&TraceEvent {
+                word,
+                depth: self.call_depth,
+                stack: &self.stack,
+            });
+        }
+        result
+    }
+
+    fn perform(&mut self, instruction: Instruction, word: &str, visible: usize) -> ForthResult {
+        match instruction {
+            Instruction::Recurse => self.recurse(visible),
+            Instruction::Execute => self.execute_token(visible),
+            Instruction::CallDefinition(instruction_index) => self.call(instruction_index),
+            _ => self.execute(instruction, word, visible),
+        }
+    }
+
+    fn execute(&mut self, instruction: Instruction, word: &str, visible: usize) -> ForthResult {
+        match instruction {
+            Instruction::Number(value) => self.push_value_onto_the_stack(value),
+            Instruction::DoubleNumber => {
+                let double = double_literal(word, self.number_base(visible));
+                self.push_double(double.ok_or(ErrorKind::Internal)?)
+            }
+            Instruction::DPlus
+            | Instruction::DMinus
+            | Instruction::DNegate
+            | Instruction::DDot
+            | Instruction::SToD
+            | Instruction::MStar
+            | Instruction::UmStar
+            | Instruction::UmSlashMod => self.double_word(instruction),
+            Instruction::FloatNumber => {
+                let float = float_literal(word, self.number_base(visible));
+                self.push_float(float.ok_or(ErrorKind::Internal)?)
+            }
+            Instruction::FPlus
+            | Instruction::FMinus
+            | Instruction::FStar
+            | Instruction::FSlash
+            | Instruction::FDot
+            | Instruction::FDup
+            | Instruction::FSwap
+            | Instruction::FDrop
+            | Instruction::FLess => self.float_word(instruction),
+            Instruction::Add => self.perform_maths_operation(Instruction::Add),
+            Instruction::Subtract => self.perform_maths_operation(Instruction::Subtract),
+            Instruction::Multiply => self.perform_maths_operation(Instruction::Multiply),
+            Instruction::Divide => self.perform_maths_operation(Instruction::Divide),
+            Instruction::Min | Instruction::Max => self.perform_maths_operation(instruction),
+            Instruction::Abs | Instruction::Negate => self.negate(instruction),
+            Instruction::Within => self.within(),
+            Instruction::Mod | Instruction::DivMod => self.divide_with_remainder(instruction),
+            Instruction::StarSlash | Instruction::StarSlashMod => self.scale(instruction),
+            Instruction::Dup => self.dup(),
+            Instruction::Drop => self.drop(),
+            Instruction::Swap => self.swap(),
+            Instruction::Over => self.over(),
+            Instruction::Rot
+            | Instruction::MinusRot
+            | Instruction::Nip
+            | Instruction::Tuck
+            | Instruction::TwoDup
+            | Instruction::TwoDrop
+            | Instruction::TwoSwap
+            | Instruction::TwoOver => self.shuffle(instruction),
+            Instruction::ToR | Instruction::TwoToR => self.move_to_return_stack(instruction),
+            Instruction::FromR | Instruction::TwoFromR => self.move_from_return_stack(instruction),
+            Instruction::RFetch => {
+                let value = self
+                    .return_stack
+                    .last()
+                    .ok_or(ErrorKind::ReturnStackUnderflow)?;
+                self.push(*value)
+            }
+            Instruction::Pick => self.pick(),
+            Instruction::Roll => self.roll(),
+            Instruction::QuestionDup => {
+                if self.peek()? != 0 {
+                    self.dup()?;
+                }
+                Ok(())
+            }
+            Instruction::Cells
+            | Instruction::CellPlus
+            | Instruction::Chars
+            | Instruction::CharPlus
+            | Instruction::Aligned => self.address_arithmetic(instruction),
+            Instruction::Align => {
+                self.align();
+                Ok(())
+            }
+            Instruction::Count => self.count(),
+            Instruction::Page => {
+                self.screen.page();
+                Ok(())
+            }
+            Instruction::AtXy => self.at_xy(),
+            Instruction::Random => self.random(),
+            Instruction::Key => self.key(),
+            Instruction::Accept => self.accept(),
+            Instruction::Here => self.here(),
+            Instruction::Allot => self.allot(),
+            Instruction::Fetch | Instruction::CFetch => self.fetch(instruction),
+            Instruction::Store | Instruction::PlusStore | Instruction::CStore => {
+                self.store(instruction)
+            }
+            Instruction::Comma => self.comma(),
+            Instruction::XcSize => self.xc_size(),
+            Instruction::XcFetchPlus => self.xc_fetch_plus(),
+            Instruction::XcStorePlus => self.xc_store_plus(),
+            Instruction::XcharPlus => self.xchar_plus(),
+            Instruction::XSize => self.x_size(),
+            Instruction::Xemit => self.xemit(),
+            Instruction::Dot | Instruction::UDot => self.dot(instruction),
+            Instruction::DotS => {
+                self.dot_s();
+                Ok(())
+            }
+            Instruction::Emit => self.xemit(),
+            Instruction::Cr => {
+                self.print("\n");
+                Ok(())
+            }
+            Instruction::Space => {
+                self.print(" ");
+                Ok(())
+            }
+            Instruction::Spaces => self.spaces(),
+            Instruction::Type => self.type_string(),
+            Instruction::Move => self.move_bytes(),
+            Instruction::Fill => self.fill(),
+            Instruction::Equal
+            | Instruction::NotEqual
+            | Instruction::Less
+            | Instruction::Greater
+            | Instruction::LessOrEqual
+            | Instruction::GreaterOrEqual
+            | Instruction::ULess
+            | Instruction::UGreater => self.compare(instruction),
+            Instruction::ZeroEqual | Instruction::ZeroLess | Instruction::ZeroGreater => {
+                self.compare_with_zero(instruction)
+            }
+            Instruction::And | Instruction::Or | Instruction::Xor => self.bitwise(instruction),
+            Instruction::Invert => {
+                let value = self.pop()?;
+                self.push(!value)
+            }
+            Instruction::LShift | Instruction::RShift => self.shift(instruction),
+            Instruction::TwoStar => {
+                let value = self.pop()?;
+                self.push(value << 1)
+            }
+            // rounds towards negative infinity, keeping the sign bit
+            Instruction::TwoSlash => {
+                let value = self.pop()?;
+                self.push(value >> 1)
+            }
+            Instruction::LoopIndex => self.loop_index(0),
+            Instruction::OuterLoopIndex => self.loop_index(1),
+            Instruction::CountedString => self.counted_string(word),
+            Instruction::String => self.string(word),
+            Instruction::PrintString => {
+                let (_, text) = string_literal(word).ok_or(ErrorKind::Internal)?;
+                self.print(text);
+                Ok(())
+            }
+            Instruction::AbortQuote => {
+                let (_, message) = string_literal(word).ok_or(ErrorKind::Internal)?;
+                self.abort_with_message(message)
+            }
+            Instruction::Catch => self.catch(visible),
+            Instruction::Throw => self.throw(),
+            Instruction::Abort => Err(ErrorKind::Thrown(-1).into()),
+            Instruction::To => self.store_value(word, visible),
+            Instruction::Tick => self.tick(word, visible),
+            Instruction::Create => self.create(),
+            // calling a definition runs the words before its DOES> on their own
+            Instruction::Does => Err(ErrorKind::InvalidWord.into()),
+            Instruction::CComma => self.c_comma(),
+            Instruction::Immediate => self.make_immediate(),
+            Instruction::State => self.state(),
+            Instruction::Base => self.base_word(),
+            Instruction::Hex => {
+                self.set_base(16);
+                Ok(())
+            }
+            Instruction::Decimal => {
+                self.set_base(10);
+                Ok(())
+            }
+            // only mean something while compiling, where compile_body sees them
+            Instruction::LeftBracket | Instruction::RightBracket => {
+                Err(ErrorKind::InvalidWord.into())
+            }
+            Instruction::Literal => self.literal(),
+            Instruction::Postpone => self.postpone(word),
+            Instruction::Forget => self.forget(word, visible),
+            Instruction::Include => match include_target(word) {
+                Some(name) => self.include(name),
+                None => Err(ErrorKind::Internal.into()),
+            },
+            Instruction::Included => self.included(),
+            Instruction::ForthWordlist
+            | Instruction::Wordlist
+            | Instruction::GetOrder
+            | Instruction::SetOrder
+            | Instruction::GetCurrent
+            | Instruction::SetCurrent
+            | Instruction::Definitions
+            | Instruction::Also
+            | Instruction::Only
+            | Instruction::Previous
+            | Instruction::Forth => self.search_order_word(instruction),
+            Instruction::Is => self.defer_to(word, visible),
+            // eval_words runs loops and conditionals, so these are out of place here
+            Instruction::Begin
+            | Instruction::Again
+            | Instruction::Until
+            | Instruction::While
+            | Instruction::Repeat => Err(ErrorKind::InvalidWord.into()),
+            Instruction::If
+            | Instruction::Else
+            | Instruction::Then
+            | Instruction::Do
+            | Instruction::Loop
+            | Instruction::PlusLoop
+            | Instruction::Case
+            | Instruction::Of
+            | Instruction::Endof
+            | Instruction::Endcase => Err(ErrorKind::UnbalancedControlFlow.into()),
+            Instruction::Recurse | Instruction::Execute | Instruction::CallDefinition(_) => {
+                Err(ErrorKind::Internal.into())
+            }
+        }
+    }
+
+    // Provenance of the top values once a stack word has moved them around,
+    // for words that move values rather than compute them
+    fn shuffled_provenance(&self, instruction: Instruction) -> Option<Vec<Provenance>> {
+        let provenance = self.provenance.as_ref()?;
+        let top = |n: usize| provenance.len().checked_sub(n).map(|i| provenance[i]);
+        match instruction {
+            Instruction::Dup => Some(vec![top(1)?, top(1)?]),
+            Instruction::Swap => Some(vec![top(1)?, top(2)?]),
+            Instruction::Over => Some(vec![top(2)?, top(1)?, top(2)?]),
+            Instruction::QuestionDup if self.peek().ok()? != 0 => Some(vec![top(1)?, top(1)?]),
+            Instruction::Pick => {
+                let depth = usize::try_from(self.peek().ok()?).ok()?;
+                Some(vec![top(depth + 2)?])
+            }
+            Instruction::Roll => {
+                let depth = usize::try_from(self.peek().ok()?).ok()?;
+                let mut rolled: Vec<_> = (2..=depth + 1).rev().map(top).collect::<Option<_>>()?;
+                rolled.push(top(depth + 2)?);
+                Some(rolled)
+            }
+            _ => {
+                let (depth, order) = shuffle_order(instruction)?;
+                let start = provenance.len().checked_sub(depth)?;
+                Some(order.iter().map(|&i| provenance[start + i]).collect())
+            }
+        }
+    }
+
+    fn push_value_onto_the_stack(&mut self, value: Value) -> ForthResult {
+        self.push(value)?;
+        Ok(())
+    }
+
+    // Goes a level deeper, to be left by decrementing `nesting`
+    fn enter(&mut self) -> ForthResult {
+        if self.nesting >= MAX_NESTING {
+            return Err(ErrorKind::ReturnStackOverflow.into());
+        }
+        self.nesting += 1;
+        Ok(())
+    }
+
+    fn call(&mut self, instruction_index: usize) -> ForthResult {
+        self.enter()?;
+        self.call_depth += 1;
+        let result = self.call_user_defined_instruction(instruction_index);
+        self.call_depth -= 1;
+        self.nesting -= 1;
+        result
+    }
+
+    // Calls the running definition, the one whose body sees the first
+    // `visible` definitions; there is none outside of a definition
+    fn recurse(&mut self, visible: usize) -> ForthResult {
+        if self.call_depth == 0 || visible >= self.definitions.len() {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        if self.recursion_depth >= self.recursion_limit {
+            return Err(ErrorKind::RecursionLimit.into());
+        }
+        self.recursion_depth += 1;
+        let result = self.call(visible);
+        self.recursion_depth -= 1;
+        result
+    }
+
+    fn call_user_defined_instruction(&mut self, instruction_index: usize) -> ForthResult {
+        let Some(def) = self.definitions.get(instruction_index) else {
+            debug_assert!(false, "call to missing definition #{}", instruction_index);
+            return Err(ErrorKind::Internal.into());
+        };
+        match def.kind {
+            DefinitionKind::Deferred => return self.call_deferred(instruction_index),
+            DefinitionKind::Created => return self.call_created(instruction_index),
+            DefinitionKind::Marker => return self.call_marker(instruction_index),
+            DefinitionKind::Vocabulary => return self.call_vocabulary(instruction_index),
+            _ => {}
+        }
+        if let Some(does) = does_index(&def.instructions) {
+            return self.call_defining_word(instruction_index, does);
+        }
+        let words = def.instructions.clone();
+        let code = self.definition_code(instruction_index);
+        self.run(
+            Body {
+                words: &words,
+                code: &code,
+                positions: &[],
+            },
+            instruction_index,
+        )
+    }
+
+    // ( n1 n2 -- n3 )
+    fn perform_maths_operation(&mut self, instruction: Instruction) -> ForthResult {
+        let [left, right] = self.operands()?;
+        if let (Instruction::Divide, 0) = (instruction, right) {
+            return Err(ErrorKind::DivisionByZero.into());
+        }
+        // operands stay on the stack when the result overflows
+        let mode = self.overflow_mode;
+        let result = match instruction {
+            Instruction::Add => mode.add(left, right),
+            Instruction::Subtract => mode.subtract(left, right),
+            Instruction::Multiply => mode.multiply(left, right),
+            Instruction::Min => Ok(left.min(right)),
+            Instruction::Max => Ok(left.max(right)),
+            _ => mode.narrow(self.division_mode.divide(left.into(), right.into()).0),
+        }?;
+        self.pop()?;
+        self.pop()?;
+        self.push(result)
+    }
+
+    // NEGATE ( n1 -- n2 ) and ABS ( n -- u ), the most negative value
+    // overflowing
+    fn negate(&mut self, instruction: Instruction) -> ForthResult {
+        let [value] = self.operands()?;
+        let result = match instruction {
+            Instruction::Abs if value >= 0 => value,
+            _ => self.overflow_mode.subtract(0, value)?,
+        };
+        self.pop()?;
+        self.push(result)
+    }
+
+    // WITHIN ( n lo hi -- flag ), true if lo <= n < hi, going round past the
+    // largest value when hi is below lo
+    fn within(&mut self) -> ForthResult {
+        let high = self.pop()?;
+        let low = self.pop()?;
+        let value = self.pop()?;
+        let within = (value.wrapping_sub(low) as Unsigned) < high.wrapping_sub(low) as Unsigned;
+        self.push(flag(within))
+    }
+
+    // MOD ( n1 n2 -- rem ) and /MOD ( n1 n2 -- rem quot ), rounding like /
+    // in the division mode: symmetric by default, so -7 2 /MOD gives -1 -3
+    fn divide_with_remainder(&mut self, instruction: Instruction) -> ForthResult {
+        let [dividend, divisor] = self.operands()?;
+        if divisor == 0 {
+            return Err(ErrorKind::DivisionByZero.into());
+        }
+        let (quotient, remainder) = self.division_mode.divide(dividend.into(), divisor.into());
+        // the most negative value divided by -1 overflows, like /, though its
+        // remainder, 0, doesn't
+        let quotient = match instruction {
+            Instruction::DivMod => Some(self.overflow_mode.narrow(quotient)?),
+            _ => None,
+        };
+        self.pop()?;
+        self.pop()?;
+        self.push(remainder as Value)?;
+        match quotient {
+            Some(quotient) => self.push(quotient),
+            None => Ok(()),
+        }
+    }
+
+    // */ ( n1 n2 n3 -- quot ) and */MOD ( n1 n2 n3 -- rem quot ), dividing
+    // n1 * n2 by n3 in the division mode. The product is kept in 128 bits, so
+    // it can't overflow; a quotient too big for a cell overflows like /.
+    fn scale(&mut self, instruction: Instruction) -> ForthResult {
+        let [n1, n2, divisor] = self.operands()?.map(i128::from);
+        if divisor == 0 {
+            return Err(ErrorKind::DivisionByZero.into());
+        }
+        let (quotient, remainder) = self.division_mode.divide(n1 * n2, divisor);
+        let quotient = self.overflow_mode.narrow(quotient)?;
+        for _ in 0..3 {
+            self.pop()?;
+        }
+        if let Instruction::StarSlashMod = instruction {
+            self.push(remainder as Value)?;
+        }
+        self.push(quotient)
+    }
+
+    // The top N values of the stack, the topmost last, left on it
+    fn operands<const N: usize>(&self) -> Result<[Value; N], Error> {
+        let start = self
+            .stack
+            .len()
+            .checked_sub(N)
+            .ok_or(ErrorKind::StackUnderflow)?;
+        let mut operands = [0; N];
+        operands.copy_from_slice(&self.stack[start..]);
+        Ok(operands)
+    }
+
+    fn address_arithmetic(&mut self, instruction: Instruction) -> ForthResult {
+        let value = self.pop()?;
+        let result = match instruction {
+            Instruction::Cells => value.wrapping_mul(CELL_SIZE),
+            Instruction::CellPlus => value.wrapping_add(CELL_SIZE),
+            Instruction::CharPlus => value.wrapping_add(1),
+            Instruction::Aligned => value.wrapping_add(CELL_SIZE - 1) & !(CELL_SIZE - 1),
+            // CHARS: characters are a single address unit
+            _ => value,
+        };
+        self.push(result)
+    }
+
+    // ( n1 n2 -- flag )
+    fn compare(&mut self, instruction: Instruction) -> ForthResult {
+        let right = self.pop()?;
+        let left = self.pop()?;
+        let result = match instruction {
+            Instruction::Equal => left == right,
+            Instruction::NotEqual => left != right,
+            Instruction::Less => left < right,
+            Instruction::Greater => left > right,
+            Instruction::LessOrEqual => left <= right,
+            Instruction::ULess => (left as Unsigned) < right as Unsigned,
+            Instruction::UGreater => left as Unsigned > right as Unsigned,
+            _ => left >= right,
+        };
+        self.push(flag(result))
+    }
+
+    // ( n -- flag )
+    fn compare_with_zero(&mut self, instruction: Instruction) -> ForthResult {
+        let value = self.pop()?;
+        let result = match instruction {
+            Instruction::ZeroEqual => value == 0,
+            Instruction::ZeroLess => value < 0,
+            _ => value > 0,
+        };
+        self.push(flag(result))
+    }
+
+    // ( x1 x2 -- x3 )
+    fn bitwise(&mut self, instruction: Instruction) -> ForthResult {
+        let right = self.pop()?;
+        let left = self.pop()?;
+        let result = match instruction {
+            Instruction::And => left & right,
+            Instruction::Or => left | right,
+            _ => left ^ right,
+        };
+        self.push(result)
+    }
+
+    // LSHIFT ( x1 u -- x2 ) and RSHIFT ( x1 u -- x2 ), shifting in zeros:
+    // shifting by the width of a cell or more, or by a negative count read
+    // as unsigned, gives 0
+    fn shift(&mut self, instruction: Instruction) -> ForthResult {
+        let count = u32::try_from(self.pop()? as Unsigned).unwrap_or(u32::MAX);
+        let value = self.pop()? as Unsigned;
+        let result = match instruction {
+            Instruction::LShift => value.checked_shl(count),
+            _ => value.checked_shr(count),
+        };
+        self.push(result.unwrap_or(0) as Value)
+    }
+
+    fn align(&mut self) {
+        let aligned = self.data.len().next_multiple_of(CELL_SIZE as usize);
+        self.data.resize(aligned, 0);
+    }
+
+    fn data_byte(&self, address: Value) -> Result<u8, Error> {
+        usize::try_from(address)
+            .ok()
+            .and_then(|address| self.data.get(address))
+            .copied()
+            .ok_or(ErrorKind::InvalidAddress.into())
+    }
+
+    // ( c-addr -- addr len )
+    fn count(&mut self) -> ForthResult {
+        let address = self.pop()?;
+        let length = self.data_byte(address)?;
+        self.push(address.wrapping_add(1))?;
+        self.push(Value::from(length))
+    }
+
+    // Stores the literal's text the first time it runs, then pushes its address
+    fn counted_string(&mut self, word: &str) -> ForthResult {
+        let text = string_literal_text(word).ok_or(ErrorKind::Internal)?;
+        let address = match self.counted_strings.get(text) {
+            Some(&address) => address,
+            None => {
+                let length = u8::try_from(text.len()).map_err(|_| ErrorKind::InvalidWord)?;
+                self.check_data_room(text.len() + 1)?;
+                let address =
+                    Value::try_from(self.data.len()).map_err(|_| ErrorKind::InvalidAddress)?;
+                self.data.push(length);
+                self.data.extend_from_slice(text.as_bytes());
+                self.counted_strings.insert(text.to_string(), address);
+                address
+            }
+        };
+        self.push(address)
+    }
+
+    // Stores the literal's text the first time it runs, then pushes its
+    // address and length
+    fn string(&mut self, word: &str) -> ForthResult {
+        let (_, text) = string_literal(word).ok_or(ErrorKind::Internal)?;
+        let address = match self.strings.get(text) {
+            Some(&address) => address,
+            None => {
+                let address =
+                    Value::try_from(self.data.len()).map_err(|_| ErrorKind::InvalidAddress)?;
+                let length = Value::try_from(text.len()).map_err(|_| ErrorKind::InvalidWord)?;
+                if address.checked_add(length).is_none() {
+                    return Err(ErrorKind::InvalidAddress.into());
+                }
+                self.check_data_room(text.len())?;
+                self.data.extend_from_slice(text.as_bytes());
+                self.strings.insert(text.to_string(), address);
+                address
+            }
+        };
+        self.push(address)?;
+        self.push(text.len() as Value)
+    }
+
+    // ( column row -- ), negative positions are the first column or row
+    fn at_xy(&mut self) -> ForthResult {
+        let row = self.pop()?;
+        let column = self.pop()?;
+        self.screen.at_xy(
+            usize::try_from(column).unwrap_or(0),
+            usize::try_from(row).unwrap_or(0),
+        );
+        Ok(())
+    }
+
+    // ( n -- u ), u from 0 to n - 1, or 0 when n isn't positive
+    fn random(&mut self) -> ForthResult {
+        let bound = self.pop()?;
+        let value = match u64::try_from(bound) {
+            Ok(bound) if bound > 0 => (self.random.next() % bound) as Value,
+            _ => 0,
+        };
+        self.push(value)
+    }
+
+    // ( -- char ), -1 once the input has ended
+    fn key(&mut self) -> ForthResult {
+        let value = self.input.key().map_or(-1, |c| c as Value);
+        self.push(value)
+    }
+
+    // ( c-addr +n1 -- +n2 ) reads a line, without its end, storing up to n1
+    // bytes of it at c-addr; n2 is 0 once the input has ended
+    fn accept(&mut self) -> ForthResult {
+        let max = self.pop()?;
+        let address = self.pop()?;
+        let max = usize::try_from(max).unwrap_or(0);
+        let mut line = String::new();
+        while let Some(c) = self.input.key() {
+            if c == '\n' {
+                break;
+            }
+            if line.len() + c.len_utf8() <= max {
+                line.push(c);
+            }
+        }
+        let line = line.trim_end_matches('\r');
+        let start = usize::try_from(address).map_err(|_| ErrorKind::InvalidAddress)?;
+        let target = self
+            .data
+            .get_mut(start..start + line.len())
+            .ok_or(ErrorKind::InvalidAddress)?;
+        target.copy_from_slice(line.as_bytes());
+        self.push(line.len() as Value)
+    }
+
+    // ( -- addr ) the next free address of data space
+    fn here(&mut self) -> ForthResult {
+        let here = Value::try_from(self.data.len()).map_err(|_| ErrorKind::InvalidAddress)?;
+        self.push(here)
+    }
+
+    // ( n -- ) reserves n bytes of data space, or gives back -n of them
+    fn allot(&mut self) -> ForthResult {
+        let size = self.pop()?;
+        let here = self.data.len() as i128 + i128::from(size);
+        if here < 0 || here > self.data_limit as i128 {
+            return Err(ErrorKind::InvalidAddress.into());
+        }
+        self.data.resize(here as usize, 0);
+        Ok(())
+    }
+
+    fn print(&mut self, text: &str) {
+        self.sink().write_str(text);
+        self.screen.write(text);
+        self.record_output(text);
+    }
+
+    fn emit(&mut self, c: char) {
+        self.sink().emit_char(c);
+        self.screen.write(c.encode_utf8(&mut [0; 4]));
+        self.record_output(c.encode_utf8(&mut [0; 4]));
+    }
+
+    fn record_output(&mut self, text: &str) {
+        match self.events.as_mut().and_then(|events| events.last_mut()) {
+            Some(EvalEvent::Output(printed)) => printed.push_str(text),
+            _ if !text.is_empty() => self.record(EvalEvent::Output(text.to_string())),
+            _ => {}
+        }
+    }
+
+    fn sink(&mut self) -> &mut dyn ForthOutput {
+        match &mut self.sink {
+            Some(sink) => sink.as_mut(),
+            None => &mut *self.output,
+        }
+    }
+
+    // The character at an address of data space and its size in bytes;
+    // bytes that aren't UTF-8 read as a replacement character each
+    fn xchar_at(&self, address: Value) -> Result<(char, usize), Error> {
+        let start = usize::try_from(address).map_err(|_| ErrorKind::InvalidAddress)?;
+        let size = match self.data_byte(address)? {
+            0x00..=0x7F => 1,
+            0xC0..=0xDF => 2,
+            0xE0..=0xEF => 3,
+            _ => 4,
+        };
+        let bytes = self.data.get(start..start + size).unwrap_or_default();
+        match std::str::from_utf8(bytes)
+            .ok()
+            .and_then(|s| s.chars().next())
+        {
+            Some(c) => Ok((c, size)),
+            None => Ok((char::REPLACEMENT_CHARACTER, 1)),
+        }
+    }
+
+    // ( xchar -- u ) bytes of the UTF-8 encoding
+    fn xc_size(&mut self) -> ForthResult {
+        let xchar = xchar(self.pop()?);
+        self.push(xchar.len_utf8() as Value)
+    }
+
+    // ( xc-addr1 -- xc-addr2 xchar )
+    fn xc_fetch_plus(&mut self) -> ForthResult {
+        let address = self.pop()?;
+        let (c, size) = self.xchar_at(address)?;
+        self.push(address.wrapping_add(size as Value))?;
+        self.push(c as Value)
+    }
+
+    // ( xchar xc-addr1 -- xc-addr2 )
+    fn xc_store_plus(&mut self) -> ForthResult {
+        let address = self.pop()?;
+        let xchar = xchar(self.pop()?);
+        let start = usize::try_from(address).map_err(|_| ErrorKind::InvalidAddress)?;
+        let size = xchar.len_utf8();
+        let target = self
+            .data
+            .get_mut(start..start + size)
+            .ok_or(ErrorKind::InvalidAddress)?;
+        xchar.encode_utf8(target);
+        self.push(address.wrapping_add(size as Value))
+    }
+
+    // ( xc-addr1 -- xc-addr2 )
+    fn xchar_plus(&mut self) -> ForthResult {
+        let address = self.pop()?;
+        let (_, size) = self.xchar_at(address)?;
+        self.push(address.wrapping_add(size as Value))
+    }
+
+    // ( xc-addr u1 -- u2 ) bytes of the first character of a string
+    fn x_size(&mut self) -> ForthResult {
+        let length = self.pop()?;
+        let address = self.pop()?;
+        let size = if length > 0 {
+            self.xchar_at(address)?.1.min(length as usize)
+        } else {
+            0
+        };
+        self.push(size as Value)
+    }
+
+    // ( xchar -- )
+    fn xemit(&mut self) -> ForthResult {
+        let xchar = xchar(self.pop()?);
+        self.emit(xchar);
+        Ok(())
+    }
+
+    fn dup(&mut self) -> ForthResult {
+        let last = self.pop()?;
+        self.push(last)?;
+        self.push(last)?;
+        Ok(())
+    }
+
+    fn drop(&mut self) -> ForthResult {
+        self.pop()?;
+        Ok(())
+    }
+
+    fn swap(&mut self) -> ForthResult {
+        let last = self.pop()?;
+        let previous = self.pop()?;
+        self.push(last)?;
+        self.push(previous)?;
+        Ok(())
+    }
+
+    fn over(&mut self) -> ForthResult {
+        let last = self.pop()?;
+        let previous = self.pop()?;
+        self.push(previous)?;
+        self.push(last)?;
+        self.push(previous)?;
+        Ok(())
+    }
+
+    // Rearranges the top values the way shuffle_order says, leaving the
+    // stack as it was when there are not enough of them
+    fn shuffle(&mut self, instruction: Instruction) -> ForthResult {
+        let Some((depth, order)) = shuffle_order(instruction) else {
+            return Err(ErrorKind::Internal.into());
+        };
+        if self.stack.len() < depth {
+            return Err(ErrorKind::StackUnderflow.into());
+        }
+        let mut values = Vec::with_capacity(depth);
+        for _ in 0..depth {
+            values.push(self.pop()?);
+        }
+        values.reverse();
+        for &index in order {
+            self.push(values[index])?;
+        }
+        Ok(())
+    }
+
+    // >R ( x -- ) ( R: -- x ) and 2>R ( x1 x2 -- ) ( R: -- x1 x2 )
+    fn move_to_return_stack(&mut self, instruction: Instruction) -> ForthResult {
+        let count = if let Instruction::TwoToR = instruction {
+            2
+        } else {
+            1
+        };
+        if self.stack.len() < count {
+            return Err(ErrorKind::StackUnderflow.into());
+        }
+        let mut values = Vec::with_capacity(count);
+        for _ in 0..count {
+            values.push(self.pop()?);
+        }
+        self.return_stack.extend(values.into_iter().rev());
+        Ok(())
+    }
+
+    // R> ( -- x ) ( R: x -- ) and 2R> ( -- x1 x2 ) ( R: x1 x2 -- )
+    fn move_from_return_stack(&mut self, instruction: Instruction) -> ForthResult {
+        let count = if let Instruction::TwoFromR = instruction {
+            2
+        } else {
+            1
+        };
+        let start = self
+            .return_stack
+            .len()
+            .checked_sub(count)
+            .ok_or(ErrorKind::ReturnStackUnderflow)?;
+        for value in self.return_stack.split_off(start) {
+            self.push(value)?;
+        }
+        Ok(())
+    }
+
+    // Index of the value `u` values below the top, once u is popped
+    fn depth_index(&self) -> Result<usize, Error> {
+        let depth = usize::try_from(self.peek()?).map_err(|_| ErrorKind::StackUnderflow)?;
+        (self.stack.len() - 1)
+            .checked_sub(depth + 1)
+            .ok_or(ErrorKind::StackUnderflow.into())
+    }
+
+    // ( xu ... x0 u -- xu ... x0 xu )
+    fn pick(&mut self) -> ForthResult {
+        let index = self.depth_index()?;
+        self.pop()?;
+        self.push(self.stack[index])
+    }
+
+    // ( xu xu-1 ... x0 u -- xu-1 ... x0 xu )
+    fn roll(&mut self) -> ForthResult {
+        let index = self.depth_index()?;
+        self.pop()?;
+        let mut values = Vec::new();
+        while self.stack.len() > index {
+            values.push(self.pop()?);
+        }
+        let rolled = values.pop().ok_or(ErrorKind::StackUnderflow)?;
+        for value in values.into_iter().rev() {
+            self.push(value)?;
+        }
+        self.push(rolled)
+    }
+}
+
+const BUILT_INS: [(&str, Instruction); 137] = [
+    ("+", Instruction::Add),
+    ("-", Instruction::Subtract),
+    ("*", Instruction::Multiply),
+    ("/", Instruction::Divide),
+    ("MIN", Instruction::Min),
+    ("MAX", Instruction::Max),
+    ("ABS", Instruction::Abs),
+    ("NEGATE", Instruction::Negate),
+    ("WITHIN", Instruction::Within),
+    ("MOD", Instruction::Mod),
+    ("/MOD", Instruction::DivMod),
+    ("*/", Instruction::StarSlash),
+    ("*/MOD", Instruction::StarSlashMod),
+    ("DUP", Instruction::Dup),
+    ("DROP", Instruction::Drop),
+    ("SWAP", Instruction::Swap),
+    ("OVER", Instruction::Over),
+    ("ROT", Instruction::Rot),
+    ("-ROT", Instruction::MinusRot),
+    ("NIP", Instruction::Nip),
+    ("TUCK", Instruction::Tuck),
+    ("2DUP", Instruction::TwoDup),
+    ("2DROP", Instruction::TwoDrop),
+    ("2SWAP", Instruction::TwoSwap),
+    ("2OVER", Instruction::TwoOver),
+    (">R", Instruction::ToR),
+    ("R>", Instruction::FromR),
+    ("R@", Instruction::RFetch),
+    ("2>R", Instruction::TwoToR),
+    ("2R>", Instruction::TwoFromR),
+    ("PICK", Instruction::Pick),
+    ("ROLL", Instruction::Roll),
+    ("?DUP", Instruction::QuestionDup),
+    ("CELLS", Instruction::Cells),
+    ("CELL+", Instruction::CellPlus),
+    ("CHARS", Instruction::Chars),
+    ("CHAR+", Instruction::CharPlus),
+    ("ALIGN", Instruction::Align),
+    ("ALIGNED", Instruction::Aligned),
+    ("COUNT", Instruction::Count),
+    ("PAGE", Instruction::Page),
+    ("AT-XY", Instruction::AtXy),
+    ("RANDOM", Instruction::Random),
+    ("KEY", Instruction::Key),
+    ("ACCEPT", Instruction::Accept),
+    ("HERE", Instruction::Here),
+    ("ALLOT", Instruction::Allot),
+    ("@", Instruction::Fetch),
+    ("!", Instruction::Store),
+    ("+!", Instruction::PlusStore),
+    ("C@", Instruction::CFetch),
+    ("C!", Instruction::CStore),
+    (",", Instruction::Comma),
+    ("XC-SIZE", Instruction::XcSize),
+    ("XC@+", Instruction::XcFetchPlus),
+    ("XC!+", Instruction::XcStorePlus),
+    ("XCHAR+", Instruction::XcharPlus),
+    ("X-SIZE", Instruction::XSize),
+    ("XEMIT", Instruction::Xemit),
+    (".", Instruction::Dot),
+    ("U.", Instruction::UDot),
+    (".S", Instruction::DotS),
+    ("EMIT", Instruction::Emit),
+    ("CR", Instruction::Cr),
+    ("SPACE", Instruction::Space),
+    ("SPACES", Instruction::Spaces),
+    ("RECURSE", Instruction::Recurse),
+    ("EXECUTE", Instruction::Execute),
+    ("CREATE", Instruction::Create),
+    ("DOES>", Instruction::Does),
+    ("C,", Instruction::CComma),
+    ("IMMEDIATE", Instruction::Immediate),
+    ("STATE", Instruction::State),
+    ("D+", Instruction::DPlus),
+    ("D-", Instruction::DMinus),
+    ("DNEGATE", Instruction::DNegate),
+    ("D.", Instruction::DDot),
+    ("S>D", Instruction::SToD),
+    ("M*", Instruction::MStar),
+    ("UM*", Instruction::UmStar),
+    ("UM/MOD", Instruction::UmSlashMod),
+    ("F+", Instruction::FPlus),
+    ("F-", Instruction::FMinus),
+    ("F*", Instruction::FStar),
+    ("F/", Instruction::FSlash),
+    ("F.", Instruction::FDot),
+    ("FDUP", Instruction::FDup),
+    ("FSWAP", Instruction::FSwap),
+    ("FDROP", Instruction::FDrop),
+    ("F<", Instruction::FLess),
+    ("CATCH", Instruction::Catch),
+    ("THROW", Instruction::Throw),
+    ("ABORT", Instruction::Abort),
+    ("BASE", Instruction::Base),
+    ("HEX", Instruction::Hex),
+    ("DECIMAL", Instruction::Decimal),
+    ("[", Instruction::LeftBracket),
+    ("]", Instruction::RightBracket),
+    ("LITERAL", Instruction::Literal),
+    ("FORTH-WORDLIST", Instruction::ForthWordlist),
+    ("WORDLIST", Instruction::Wordlist),
+    ("GET-ORDER", Instruction::GetOrder),
+    ("SET-ORDER", Instruction::SetOrder),
+    ("GET-CURRENT", Instruction::GetCurrent),
+    ("SET-CURRENT", Instruction::SetCurrent),
+    ("DEFINITIONS", Instruction::Definitions),
+    ("ALSO", Instruction::Also),
+    ("ONLY", Instruction::Only),
+    ("PREVIOUS", Instruction::Previous),
+    ("FORTH", Instruction::Forth),
+    ("TYPE", Instruction::Type),
+    ("INCLUDED", Instruction::Included),
+    ("MOVE", Instruction::Move),
+    ("FILL", Instruction::Fill),
+    ("=", Instruction::Equal),
+    ("<>", Instruction::NotEqual),
+    ("<", Instruction::Less),
+    (">", Instruction::Greater),
+    ("<=", Instruction::LessOrEqual),
+    (">=", Instruction::GreaterOrEqual),
+    ("U<", Instruction::ULess),
+    ("U>", Instruction::UGreater),
+    ("0=", Instruction::ZeroEqual),
+    ("0<", Instruction::ZeroLess),
+    ("0>", Instruction::ZeroGreater),
+    ("AND", Instruction::And),
+    ("OR", Instruction::Or),
+    ("XOR", Instruction::Xor),
+    ("INVERT", Instruction::Invert),
+    ("LSHIFT", Instruction::LShift),
+    ("RSHIFT", Instruction::RShift),
+    ("2*", Instruction::TwoStar),
+    ("2/", Instruction::TwoSlash),
+    ("TRUE", Instruction::Number(TRUE)),
+    ("FALSE", Instruction::Number(FALSE)),
+    ("I", Instruction::LoopIndex),
+    ("J", Instruction::OuterLoopIndex),
+];
+
+// Compares without case, rather than allocating an upper case copy of every word
+fn built_in(word: &str) -> Option<Instruction> {
+    BUILT_INS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(word))
+        .map(|&(_, instruction)| instruction)
+}
+
+// How many values a stack word takes, and which of them it puts back, by
+// index from the deepest one
+fn shuffle_order(instruction: Instruction) -> Option<(usize, &'static [usize])> {
+    match instruction {
+        Instruction::Rot => Some((3, &[1, 2, 0])),
+        Instruction::MinusRot => Some((3, &[2, 0, 1])),
+        Instruction::Nip => Some((2, &[1])),
+        Instruction::Tuck => Some((2, &[1, 0, 1])),
+        Instruction::TwoDup => Some((2, &[0, 1, 0, 1])),
+        Instruction::TwoDrop => Some((2, &[])),
+        Instruction::TwoSwap => Some((4, &[2, 3, 0, 1])),
+        Instruction::TwoOver => Some((4, &[0, 1, 2, 3, 0, 1])),
+        _ => None,
+    }
+}
+
+fn flag(condition: bool) -> Value {
+    if condition {
+        TRUE
+    } else {
+        FALSE
+    }
+}
+
+// Control words cannot be redefined, like ":"
+const CONTROL_WORDS: [(&str, Instruction); 15] = [
+    ("BEGIN", Instruction::Begin),
+    ("AGAIN", Instruction::Again),
+    ("UNTIL", Instruction::Until),
+    ("WHILE", Instruction::While),
+    ("REPEAT", Instruction::Repeat),
+    ("IF", Instruction::If),
+    ("ELSE", Instruction::Else),
+    ("THEN", Instruction::Then),
+    ("DO", Instruction::Do),
+    ("LOOP", Instruction::Loop),
+    ("+LOOP", Instruction::PlusLoop),
+    ("CASE", Instruction::Case),
+    ("OF", Instruction::Of),
+    ("ENDOF", Instruction::Endof),
+    ("ENDCASE", Instruction::Endcase),
+];
+
+fn control_word(word: &str) -> Option<Instruction> {
+    CONTROL_WORDS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(word))
+        .map(|&(_, instruction)| instruction)
+}
+
+// Words taking the text up to the next quote as their argument
+const STRING_OPENINGS: [&str; 4] = ["C\"", "S\"", ".\"", "ABORT\""];
+
+// Words taking the words after them as their argument, like C" text"
+fn is_parsing_word(word: &str) -> bool {
+    [
+        "CHAR", "[CHAR]", "TO", "IS", "'", "[']", "POSTPONE", "FORGET", "INCLUDE",
+    ]
+    .iter()
+    .chain(STRING_OPENINGS.iter())
+    .any(|parsing| parsing.eq_ignore_ascii_case(word))
+}
+
+fn is_string_opening(word: &str) -> bool {
+    STRING_OPENINGS
+        .iter()
+        .any(|opening| opening.eq_ignore_ascii_case(word))
+}
+
+// Gathers a parsing word and its argument into a single word: `CHAR a`, or
+// `C" hello world"`, its text ending with the first word ending with a quote
+fn parsed_literal<'a, I>(opening: &str, words: &mut I) -> Result<String, Error>
+where
+    I: Iterator<Item = &'a str>,
+{
+    let is_string = is_string_opening(opening);
+    let mut literal = String::from(opening);
+    for word in words {
+        literal.push(' ');
+        literal.push_str(word);
+        if !is_string || word.ends_with('"') {
+            return Ok(literal);
+        }
+    }
+    Err(ErrorKind::InvalidWord.into())
+}
+
+// Opening word, in upper case, and text of a string literal gathered by
+// parsed_literal
+fn string_literal(word: &str) -> Option<(&'static str, &str)> {
+    let (opening, text) = word.split_once(' ')?;
+    let opening = STRING_OPENINGS
+        .iter()
+        .find(|string| string.eq_ignore_ascii_case(opening))?;
+    Some((opening, text.strip_suffix('"')?))
+}
+
+// Text of a `C" text"` literal
+fn string_literal_text(word: &str) -> Option<&str> {
+    match string_literal(word)? {
+        ("C\"", text) => Some(text),
+        _ => None,
+    }
+}
+
+// Name of the VALUE a `TO name` word stores into
+fn to_target(word: &str) -> Option<&str> {
+    let (opening, name) = word.split_once(' ')?;
+    opening.eq_ignore_ascii_case("TO").then_some(name)
+}
+
+// Character of a `CHAR a` or `[CHAR] a` literal
+fn char_literal(word: &str) -> Option<char> {
+    let (opening, argument) = word.split_once(' ')?;
+    if !opening.eq_ignore_ascii_case("CHAR") && !opening.eq_ignore_ascii_case("[CHAR]") {
+        return None;
+    }
+    argument.chars().next()
+}
+
+// The character with a code point, or a replacement character if none has it
+fn xchar(value: Value) -> char {
+    u32::try_from(value)
+        .ok()
+        .and_then(char::from_u32)
+        .unwrap_or(char::REPLACEMENT_CHARACTER)
+}
+
+// Words starting a structure, which eval collects up to its end
+fn is_opening(word: &str) -> bool {
+    matches!(
+        control_word(word),
+        Some(Instruction::Begin | Instruction::If | Instruction::Do | Instruction::Case)
+    )
+}
+
+// Words ending a structure
+fn is_closing(word: &str) -> bool {
+    matches!(
+        control_word(word),
+        Some(
+            Instruction::Again
+                | Instruction::Until
+                | Instruction::Repeat
+                | Instruction::Then
+                | Instruction::Loop
+                | Instruction::PlusLoop
+                | Instruction::Endcase
+        )
+    )
+}
+
+// Words of a body with their code, step for step
+pub(crate) struct Body<'a, S> {
+    words: &'a [S],
+    code: &'a [Step],
+    // where the words are in the evaluated code, when they come from it
+    positions: &'a [Position],
+}
+
+impl<S> Clone for Body<'_, S> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<S> Copy for Body<'_, S> {}
+
+impl<'a, S> Body<'a, S> {
+    fn from(self, start: usize) -> Body<'a, S> {
+        self.part(start, self.code.len())
+    }
+
+    fn part(self, start: usize, end: usize) -> Body<'a, S> {
+        Body {
+            words: &self.words[start..end],
+            code: &self.code[start..end],
+            positions: self.positions.get(start..end).unwrap_or(&[]),
+        }
+    }
+}
+
+// Indexes of the WHILE, if any, and of the AGAIN, UNTIL or REPEAT closing a
+// loop, given the code following its BEGIN. Only REPEAT goes with WHILE.
+fn loop_parts(code: &[Step]) -> Result<(Option<usize>, usize), Error> {
+    let mut depth = 0;
+    let mut while_index = None;
+    for (index, step) in code.iter().enumerate() {
+        match step {
+            Some(Instruction::Begin) => depth += 1,
+            Some(Instruction::While) if depth == 0 && while_index.is_some() => {
+                return Err(ErrorKind::InvalidWord.into())
+            }
+            Some(Instruction::While) if depth == 0 => while_index = Some(index),
+            Some(Instruction::Again | Instruction::Until) if depth == 0 => {
+                return match while_index {
+                    Some(_) => Err(ErrorKind::InvalidWord.into()),
+                    None => Ok((None, index)),
+                }
+            }
+            Some(Instruction::Repeat) if depth == 0 => {
+                return match while_index {
+                    Some(_) => Ok((while_index, index)),
+                    None => Err(ErrorKind::InvalidWord.into()),
+                }
+            }
+            Some(Instruction::Again | Instruction::Until | Instruction::Repeat) => depth -= 1,
+            _ => {}
+        }
+    }
+    Err(ErrorKind::InvalidWord.into())
+}
+
+// Indexes of the ELSE, if any, and of the THEN of a conditional, given the
+// code following its IF
+fn conditional_parts(code: &[Step]) -> Result<(Option<usize>, usize), Error> {
+    let mut depth = 0;
+    let mut else_index = None;
+    for (index, step) in code.iter().enumerate() {
+        match step {
+            Some(Instruction::If) => depth += 1,
+            Some(Instruction::Else) if depth == 0 && else_index.is_some() => {
+                return Err(ErrorKind::UnbalancedControlFlow.into())
+            }
+            Some(Instruction::Else) if depth == 0 => else_index = Some(index),
+            Some(Instruction::Then) if depth == 0 => return Ok((else_index, index)),
+            Some(Instruction::Then) => depth -= 1,
+            _ => {}
+        }
+    }
+    Err(ErrorKind::UnbalancedControlFlow.into())
+}
+
+// Indexes of the OF and ENDOF of each clause of a CASE, and of its ENDCASE,
+// given the code following the CASE
+type CaseParts = (Vec<(usize, usize)>, usize);
+
+fn case_parts(code: &[Step]) -> Result<CaseParts, Error> {
+    let mut depth = 0;
+    let mut clauses = Vec::new();
+    let mut of_index = None;
+    for (index, step) in code.iter().enumerate() {
+        match step {
+            Some(Instruction::Case) => depth += 1,
+            Some(Instruction::Of) if depth == 0 && of_index.is_none() => of_index = Some(index),
+            Some(Instruction::Endof) if depth == 0 => {
+                let of_index = of_index.take().ok_or(ErrorKind::UnbalancedControlFlow)?;
+                clauses.push((of_index, index));
+            }
+            Some(Instruction::Of) if depth == 0 => {
+                return Err(ErrorKind::UnbalancedControlFlow.into())
+            }
+            Some(Instruction::Endcase) if depth == 0 => {
+                return match of_index {
+                    Some(_) => Err(ErrorKind::UnbalancedControlFlow.into()),
+                    None => Ok((clauses, index)),
+                }
+            }
+            Some(Instruction::Endcase) => depth -= 1,
+            _ => {}
+        }
+    }
+    Err(ErrorKind::UnbalancedControlFlow.into())
+}
+
+// Number of words before the LOOP or +LOOP closing a DO loop, given the code
+// following its DO
+fn counted_loop_length(code: &[Step]) -> Result<usize, Error> {
+    let mut depth = 0;
+    for (index, step) in code.iter().enumerate() {
+        match step {
+            Some(Instruction::Do) => depth += 1,
+            Some(Instruction::Loop | Instruction::PlusLoop) if depth == 0 => return Ok(index),
+            Some(Instruction::Loop | Instruction::PlusLoop) => depth -= 1,
+            _ => {}
+        }
+    }
+    Err(ErrorKind::UnbalancedControlFlow.into())
+}
+
+// Checks that every IF of a definition's words has its THEN, with at most one
+// ELSE in between, every DO its LOOP or +LOOP, and every CASE its ENDCASE,
+// with OF ... ENDOF clauses in between, each closing the structure opened
+// last, so that a definition can't be made broken
+fn check_structures<S: AsRef<str>>(words: &[S]) -> ForthResult {
+    // the words opening the structures left open, an IF becoming its ELSE
+    let mut open = Vec::new();
+    for word in words {
+        let closed = match control_word(word.as_ref()) {
+            Some(Instruction::If) => {
+                open.push(Instruction::If);
+                continue;
+            }
+            Some(Instruction::Do) => {
+                open.push(Instruction::Do);
+                continue;
+            }
+            Some(Instruction::Case) => {
+                open.push(Instruction::Case);
+                continue;
+            }
+            Some(Instruction::Of) => {
+                if !matches!(open.last(), Some(Instruction::Case)) {
+                    return Err(ErrorKind::UnbalancedControlFlow.into());
+                }
+                open.push(Instruction::Of);
+                continue;
+            }
+            Some(Instruction::Endof) => matches!(open.pop(), Some(Instruction::Of)),
+            Some(Instruction::Endcase) => matches!(open.pop(), Some(Instruction::Case)),
+            Some(Instruction::Else) => {
+                if !matches!(open.pop(), Some(Instruction::If)) {
+                    return Err(ErrorKind::UnbalancedControlFlow.into());
+                }
+                open.push(Instruction::Else);
+                continue;
+            }
+            Some(Instruction::Then) => {
+                matches!(open.pop(), Some(Instruction::If | Instruction::Else))
+            }
+            Some(Instruction::Loop | Instruction::PlusLoop) => {
+                matches!(open.pop(), Some(Instruction::Do))
+            }
+            _ => true,
+        };
+        if !closed {
+            return Err(ErrorKind::UnbalancedControlFlow.into());
+        }
+    }
+    if open.is_empty() {
+        Ok(())
+    } else {
+        Err(ErrorKind::UnbalancedControlFlow.into())
+    }
+}
+
+// Provenance of the values an instruction pushes; definitions leave it to
+// the instructions they run
+fn origin(instruction: Instruction) -> Option<Provenance> {
+    match instruction {
+        Instruction::Number(_) | Instruction::DoubleNumber | Instruction::CountedString => {
+            Some(Provenance::Literal)
+        }
+        // pushes onto the float stack, whose values have no provenance
+        Instruction::FloatNumber => None,
+        Instruction::CallDefinition(_) | Instruction::Recurse | Instruction::Execute => None,
+        _ => BUILT_INS
+            .iter()
+            .find(|(_, built_in)| {
+                std::mem::discriminant(built_in) == std::mem::discriminant(&instruction)
+            })
+            .map(|&(name, _)| Provenance::Result(name)),
+    }
+}
@@ -0,0 +1,128 @@
+use crate::snapshot::ForthSnapshot;
+use crate::{Diagnostic, Forth, Position, Words};
+
+// What evaluating code with eval_all_cached left behind: the interpreter's
+// state before the first word of each line, so that evaluating the code
+// again, once edited, starts over from the line the edit is on rather than
+// from the first. States taken after the first change are dropped.
+//
+// The interpreter the code is evaluated with must be the same each time, up
+// to the code: a cache is cleared when what was evaluated before the code,
+// or how, changes.
+//
+// States share what didn't change since the one before, like data space
+// when the line didn't write to it. Lines whose state would take the cache
+// past CHECKPOINTS_SIZE are left without one, evaluated again from an
+// earlier line instead.
+#[derive(Default)]
+pub struct LineCache {
+    // the code evaluated last, and every error it had, in order
+    input: String,
+    diagnostics: Vec<Diagnostic>,
+    checkpoints: Vec<Checkpoint>,
+    // line the last evaluation started from
+    resumed_from: usize,
+}
+
+// Bytes the states of a cache take at most, roughly
+pub const CHECKPOINTS_SIZE: usize = 128 * 1024 * 1024;
+
+struct Checkpoint {
+    // where the first word of the line is, in bytes and as line and column
+    offset: usize,
+    position: Position,
+    // errors found before it
+    diagnostics: usize,
+    snapshot: ForthSnapshot,
+    // bytes the snapshot takes beyond what it shares with the one before
+    size: usize,
+}
+
+impl LineCache {
+    pub fn new() -> LineCache {
+        LineCache::default()
+    }
+
+    pub fn clear(&mut self) {
+        *self = LineCache::default();
+    }
+
+    // The line the last evaluation started from, 1 when it started from
+    // scratch
+    pub fn resumed_from(&self) -> usize {
+        self.resumed_from.max(1)
+    }
+
+    // Roughly the bytes the states kept take, at most CHECKPOINTS_SIZE
+    pub fn size(&self) -> usize {
+        self.checkpoints
+            .iter()
+            .map(|checkpoint| checkpoint.size)
+            .sum()
+    }
+}
+
+impl Forth {
+    // Evaluates like eval_all, starting from the last line the cache has the
+    // state before, among the lines before the first change since the code
+    // evaluated last. Evaluating code the cache has nothing for yet takes a
+    // state per line, which is kept until the line or one before changes.
+    pub fn eval_all_cached(&mut self, input: &str, cache: &mut LineCache) -> Vec<Diagnostic> {
+        let unchanged = cache
+            .input
+            .bytes()
+            .zip(input.bytes())
+            .take_while(|(old, new)| old == new)
+            .count();
+        let valid = cache
+            .checkpoints
+            .iter()
+            .take_while(|checkpoint| checkpoint.offset <= unchanged)
+            .count();
+        cache.checkpoints.truncate(valid);
+        let mut diagnostics = std::mem::take(&mut cache.diagnostics);
+        let mut words = match cache.checkpoints.last() {
+            Some(checkpoint) => {
+                self.restore(&checkpoint.snapshot);
+                diagnostics.truncate(checkpoint.diagnostics);
+                cache.resumed_from = checkpoint.position.0;
+                Words::resuming(input, checkpoint.offset, checkpoint.position)
+            }
+            None => {
+                diagnostics.clear();
+                cache.resumed_from = 1;
+                Words::new(input)
+            }
+        };
+        cache.input = input.to_string();
+        let mut size = cache.size();
+        let mut last_line = cache.checkpoints.last().map_or(1, |last| last.position.0);
+        loop {
+            let argument = words.is_argument_next();
+            let Some(word) = words.next() else { break };
+            let position = words.word_position();
+            // the first word of a line, not taken by the word before it
+            if position.0 > last_line && !argument {
+                last_line = position.0;
+                let snapshot = self.snapshot();
+                let earlier = cache.checkpoints.last().map(|last| &last.snapshot);
+                let snapshot_size = snapshot.size_after(earlier);
+                if size + snapshot_size <= CHECKPOINTS_SIZE {
+                    size += snapshot_size;
+                    cache.checkpoints.push(Checkpoint {
+                        offset: words.word_offset(word),
+                        position,
+                        diagnostics: diagnostics.len(),
+                        snapshot,
+                        size: snapshot_size,
+                    });
+                }
+            }
+            if self.eval_word_diagnosed(word, &mut words, position, &mut diagnostics) {
+                break;
+            }
+        }
+        cache.diagnostics.clone_from(&diagnostics);
+        diagnostics
+    }
+}
@@ -0,0 +1,140 @@
+use crate::{Error, ErrorKind, Forth, ForthResult, Instruction, Unsigned, Value, CELL_SIZE};
+use std::ops::Range;
+
+// Reading and writing data space, a cell at a time or a byte at a time.
+// Cells are stored little-endian, at any address: data space doesn't have to
+// be aligned for them.
+
+impl Forth {
+    // Bytes of data space from an address on, if they are all in it
+    pub(crate) fn data_range(&self, address: Value, size: usize) -> Result<Range<usize>, Error> {
+        let start = usize::try_from(address).map_err(|_| ErrorKind::InvalidAddress)?;
+        let end = start.checked_add(size).ok_or(ErrorKind::InvalidAddress)?;
+        if end > self.data.len() {
+            return Err(ErrorKind::InvalidAddress.into());
+        }
+        Ok(start..end)
+    }
+
+    pub(crate) fn cell_at(&self, address: Value) -> Result<Value, Error> {
+        let range = self.data_range(address, CELL_SIZE as usize)?;
+        let mut bytes = [0; CELL_SIZE as usize];
+        bytes.copy_from_slice(&self.data[range]);
+        Ok(Value::from_le_bytes(bytes))
+    }
+
+    pub(crate) fn set_cell(&mut self, address: Value, value: Value) -> ForthResult {
+        let range = self.data_range(address, CELL_SIZE as usize)?;
+        self.data[range].copy_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    // @ ( a-addr -- x ) and C@ ( c-addr -- char )
+    pub(crate) fn fetch(&mut self, instruction: Instruction) -> ForthResult {
+        let address = self.pop()?;
+        let value = match instruction {
+            Instruction::CFetch => Value::from(self.data_byte(address)?),
+            _ => self.cell_at(address)?,
+        };
+        self.push(value)
+    }
+
+    // ! ( x a-addr -- ), +! ( n a-addr -- ) and C! ( char c-addr -- ), the
+    // latter keeping the lowest byte of char
+    pub(crate) fn store(&mut self, instruction: Instruction) -> ForthResult {
+        let address = self.pop()?;
+        let value = self.pop()?;
+        match instruction {
+            Instruction::CStore => {
+                let range = self.data_range(address, 1)?;
+                self.data[range.start] = value as u8;
+                Ok(())
+            }
+            Instruction::PlusStore => {
+                let sum = self.overflow_mode.add(self.cell_at(address)?, value)?;
+                self.set_cell(address, sum)
+            }
+            _ => self.set_cell(address, value),
+        }
+    }
+
+    // Gives back the data space from an address on, with the strings and the
+    // STATE and BASE cells stored there
+    pub(crate) fn truncate_data(&mut self, here: Value) {
+        let Ok(here) = usize::try_from(here) else {
+            return;
+        };
+        self.keep_base();
+        self.data.truncate(here);
+        let kept = |address: &Value| usize::try_from(*address).is_ok_and(|a| a < here);
+        self.counted_strings.retain(|_, address| kept(address));
+        self.strings.retain(|_, address| kept(address));
+        if !self.state_address.as_ref().is_some_and(kept) {
+            self.state_address = None;
+        }
+        if !self.base_address.as_ref().is_some_and(kept) {
+            self.base_address = None;
+        }
+    }
+
+    // Reserves an aligned cell of data space, returning its address
+    pub(crate) fn reserve_cell(&mut self) -> Result<Value, Error> {
+        self.align();
+        self.check_data_room(CELL_SIZE as usize)?;
+        let address = self.data.len();
+        self.data.resize(address + CELL_SIZE as usize, 0);
+        Ok(address as Value)
+    }
+
+    // , ( x -- ) reserves a cell of data space and stores x in it
+    pub(crate) fn comma(&mut self) -> ForthResult {
+        let value = self.pop()?;
+        self.check_data_room(CELL_SIZE as usize)?;
+        self.data.extend_from_slice(&value.to_le_bytes());
+        Ok(())
+    }
+
+    // C, ( char -- ) reserves a byte of data space and stores the lowest byte
+    // of char in it
+    pub(crate) fn c_comma(&mut self) -> ForthResult {
+        let value = self.pop()?;
+        self.check_data_room(1)?;
+        self.data.push(value as u8);
+        Ok(())
+    }
+
+    // Fails unless data space can grow by `bytes` within its limit
+    pub(crate) fn check_data_room(&self, bytes: usize) -> ForthResult {
+        match self.data.len().checked_add(bytes) {
+            Some(end) if end <= self.data_limit => Ok(()),
+            _ => Err(ErrorKind::InvalidAddress.into()),
+        }
+    }
+
+    // MOVE ( addr1 addr2 u -- ) copies u bytes from addr1 to addr2, even when
+    // the two ranges overlap
+    pub(crate) fn move_bytes(&mut self) -> ForthResult {
+        let size = byte_count(self.pop()?);
+        let destination = self.pop()?;
+        let source = self.pop()?;
+        let source = self.data_range(source, size)?;
+        let destination = self.data_range(destination, size)?;
+        self.data.copy_within(source, destination.start);
+        Ok(())
+    }
+
+    // FILL ( c-addr u char -- ) sets u bytes to the lowest byte of char
+    pub(crate) fn fill(&mut self) -> ForthResult {
+        let byte = self.pop()? as u8;
+        let size = byte_count(self.pop()?);
+        let address = self.pop()?;
+        let range = self.data_range(address, size)?;
+        self.data[range].fill(byte);
+        Ok(())
+    }
+}
+
+// A count of bytes, read as unsigned: a negative one is out of data space
+pub(crate) fn byte_count(count: Value) -> usize {
+    count as Unsigned as usize
+}
@@ -0,0 +1,65 @@
+use crate::{Error, ErrorKind};
+use std::fmt;
+
+// What errors read like to the user, for the app, the CLI and any other
+// host to print the same
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let message = match self {
+            ErrorKind::DivisionByZero => "Cannot divide by 0",
+            ErrorKind::InvalidWord => "Invalid word definition",
+            ErrorKind::StackUnderflow => "Stack underflow",
+            ErrorKind::StackOverflow => "Stack overflow",
+            ErrorKind::UnknownWord => "Unknown word, type on :)",
+            ErrorKind::Timeout => "Timed out",
+            ErrorKind::Cancelled => "Stopped",
+            ErrorKind::FuelExhausted => "Ran too many steps, stopped",
+            ErrorKind::ReturnStackOverflow => "Definitions nested too deep",
+            ErrorKind::ReturnStackUnderflow => "Return stack underflow",
+            ErrorKind::DictionaryFrozen => "No new definitions allowed",
+            ErrorKind::InvalidAddress => "Address out of data space",
+            ErrorKind::Internal => "Internal error, please report it",
+            ErrorKind::UnbalancedControlFlow => "IF, ELSE and THEN do not match",
+            ErrorKind::RecursionLimit => "RECURSE nested too deep",
+            ErrorKind::InvalidNumber => "Invalid number",
+            ErrorKind::Overflow => "Number too big for a cell",
+            ErrorKind::UnexpectedSemicolon => "; without a definition to end",
+            ErrorKind::NestedDefinition => ": inside a definition, missing a ; before",
+            ErrorKind::Thrown(-1) => "Aborted",
+            ErrorKind::Thrown(code) => return write!(f, "THROW {}", code),
+            ErrorKind::Aborted(message) => message,
+            ErrorKind::CannotInclude(file, reason) => {
+                return write!(f, "Cannot include {}: {}", file, reason)
+            }
+            // the line in the file, the error being at the INCLUDE in the code
+            ErrorKind::Included(file, error) => {
+                return match error.location() {
+                    Some(location) => write!(f, "{}:{}: {}", file, location.line, error),
+                    None => write!(f, "{}: {}", file, error),
+                }
+            }
+        };
+        f.write_str(message)
+    }
+}
+
+// An unknown word is named, with the words suggested instead
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.suggestions() {
+            Some(suggestions) if suggestions.words.is_empty() => {
+                write!(f, "Unknown word {}", suggestions.word)
+            }
+            Some(suggestions) => write!(
+                f,
+                "Unknown word {} — did you mean {} ?",
+                suggestions.word,
+                suggestions.words.join(", ")
+            ),
+            None => self.kind().fmt(f),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
@@ -0,0 +1,156 @@
+use crate::base::format_in_base;
+use crate::memory::byte_count;
+use crate::{Forth, ForthResult, Instruction, Unsigned};
+use std::io::Write;
+
+// Where printed text goes. The default sink is the interpreter's `Output`
+// buffer; a host can install its own to print to stdout, a file or a channel.
+// Like inputs, outputs are Send.
+pub trait ForthOutput: Send {
+    fn write_str(&mut self, text: &str);
+
+    // Called by EMIT and XEMIT
+    fn emit_char(&mut self, c: char) {
+        self.write_str(c.encode_utf8(&mut [0; 4]));
+    }
+}
+
+// Output written to a writer, such as stdout, as it is printed. Writing
+// errors are ignored, like a closed pipe.
+pub struct WriterOutput<W> {
+    writer: W,
+}
+
+impl<W: Write> WriterOutput<W> {
+    pub fn new(writer: W) -> WriterOutput<W> {
+        WriterOutput { writer }
+    }
+}
+
+impl<W: Write + Send> ForthOutput for WriterOutput<W> {
+    fn write_str(&mut self, text: &str) {
+        let _ = self.writer.write_all(text.as_bytes());
+        let _ = self.writer.flush();
+    }
+}
+
+// Text the program printed. Only the latest `limit` bytes are kept, so that a
+// program printing in a long loop can't use up the memory: older text is
+// dropped first, and remembered as dropped.
+
+pub const DEFAULT_OUTPUT_LIMIT: usize = 64 * 1024;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Output {
+    text: String,
+    limit: usize,
+    // bytes dropped from the start so far
+    dropped: usize,
+}
+
+impl Default for Output {
+    fn default() -> Self {
+        Output::new(DEFAULT_OUTPUT_LIMIT)
+    }
+}
+
+impl Output {
+    pub fn new(limit: usize) -> Output {
+        Output {
+            text: String::new(),
+            limit,
+            dropped: 0,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+
+    pub fn has_overflowed(&self) -> bool {
+        self.dropped > 0
+    }
+
+    pub fn limit(&self) -> usize {
+        self.limit
+    }
+
+    pub fn write(&mut self, text: &str) {
+        self.text.push_str(text);
+        if self.text.len() > self.limit {
+            let mut excess = self.text.len() - self.limit;
+            while !self.text.is_char_boundary(excess) {
+                excess += 1;
+            }
+            self.text.drain(..excess);
+            self.dropped += excess;
+        }
+    }
+
+    // Counts text printed before what was kept of it as dropped, without
+    // having written it
+    pub(crate) fn drop_unwritten(&mut self, bytes: usize) {
+        self.dropped = self.dropped.saturating_add(bytes);
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.dropped = 0;
+    }
+}
+
+impl ForthOutput for Output {
+    fn write_str(&mut self, text: &str) {
+        self.write(text);
+    }
+}
+
+impl Forth {
+    // . ( n -- ) prints n and a space, U. ( u -- ) n read as unsigned
+    pub(crate) fn dot(&mut self, instruction: Instruction) -> ForthResult {
+        let value = self.pop()?;
+        let text = match instruction {
+            Instruction::UDot => format_in_base((value as Unsigned).into(), self.base()),
+            _ => self.format_number(value),
+        };
+        self.print(&format!("{} ", text));
+        Ok(())
+    }
+
+    // .S ( -- ) prints the depth of the stack, then its values from the bottom
+    pub(crate) fn dot_s(&mut self) {
+        let mut text = format!("<{}> ", self.stack.len());
+        for &value in &self.stack {
+            text.push_str(&format!("{} ", self.format_number(value)));
+        }
+        self.print(&text);
+    }
+
+    // TYPE ( c-addr u -- ) prints u bytes of data space, bytes that aren't
+    // UTF-8 printing as replacement characters
+    pub(crate) fn type_string(&mut self) -> ForthResult {
+        let size = byte_count(self.pop()?);
+        let address = self.pop()?;
+        let range = self.data_range(address, size)?;
+        let text = String::from_utf8_lossy(&self.data[range]).into_owned();
+        self.print(&text);
+        Ok(())
+    }
+
+    // SPACES ( n -- ) prints n spaces, none when n isn't positive. Only as
+    // many as the output keeps are made, the others counting as dropped.
+    pub(crate) fn spaces(&mut self) -> ForthResult {
+        let count = usize::try_from(self.pop()?).unwrap_or(0);
+        let kept = count.min(self.output.limit());
+        if self.sink.is_none() {
+            self.output.drop_unwritten(count - kept);
+        }
+        self.print(&" ".repeat(kept));
+        Ok(())
+    }
+}
@@ -0,0 +1,67 @@
+use crate::{Error, ErrorKind, Unsigned, Value};
+
+// What arithmetic does with a result too big for a cell: + - * / */ +! and
+// UM/MOD wrap around by default, like most Forths, or fail, or stop at the
+// largest or smallest value. Address arithmetic and loop indexes always wrap
+// around.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum OverflowMode {
+    #[default]
+    Wrapping,
+    // failing with ErrorKind::Overflow
+    Checked,
+    Saturating,
+}
+
+impl OverflowMode {
+    pub(crate) fn add(self, left: Value, right: Value) -> Result<Value, Error> {
+        self.pick(
+            left.checked_add(right),
+            left.wrapping_add(right),
+            left.saturating_add(right),
+        )
+    }
+
+    pub(crate) fn subtract(self, left: Value, right: Value) -> Result<Value, Error> {
+        self.pick(
+            left.checked_sub(right),
+            left.wrapping_sub(right),
+            left.saturating_sub(right),
+        )
+    }
+
+    pub(crate) fn multiply(self, left: Value, right: Value) -> Result<Value, Error> {
+        self.pick(
+            left.checked_mul(right),
+            left.wrapping_mul(right),
+            left.saturating_mul(right),
+        )
+    }
+
+    // A double-width result back into a cell
+    pub(crate) fn narrow(self, value: i128) -> Result<Value, Error> {
+        let saturated = value.clamp(Value::MIN.into(), Value::MAX.into()) as Value;
+        self.pick(Value::try_from(value).ok(), value as Value, saturated)
+    }
+
+    // An unsigned double-width result back into a cell
+    pub(crate) fn narrow_unsigned(self, value: u128) -> Result<Value, Error> {
+        let saturated = value.min(Unsigned::MAX.into()) as Value;
+        let checked = Unsigned::try_from(value).ok().map(|value| value as Value);
+        self.pick(checked, value as Value, saturated)
+    }
+
+    fn pick(
+        self,
+        checked: Option<Value>,
+        wrapped: Value,
+        saturated: Value,
+    ) -> Result<Value, Error> {
+        match self {
+            OverflowMode::Wrapping => Ok(wrapped),
+            OverflowMode::Checked => checked.ok_or(ErrorKind::Overflow.into()),
+            OverflowMode::Saturating => Ok(saturated),
+        }
+    }
+}
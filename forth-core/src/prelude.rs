@@ -0,0 +1,19 @@
+use crate::{Definition, Forth, ForthResult};
+
+// Forth source defining the words of the standard that aren't built in
+pub(crate) const PRELUDE: &str = include_str!("prelude.fth");
+
+impl Forth {
+    // Evaluates source whose definitions then count as the prelude's, like
+    // the words of a library loaded before the code
+    pub fn eval_prelude(&mut self, source: &str) -> ForthResult {
+        let result = self.eval(source);
+        self.prelude = self.definitions.len();
+        result
+    }
+
+    // The definitions made after the prelude's, by the code
+    pub fn user_definitions(&self) -> &[Definition] {
+        &self.definitions[self.prelude.min(self.definitions.len())..]
+    }
+}
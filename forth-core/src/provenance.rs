@@ -0,0 +1,51 @@
+use std::fmt;
+
+// Where a value on the stack came from, kept alongside the stack when the
+// interpreter is built with provenance on. Values moved around by stack words
+// like SWAP keep theirs.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Provenance {
+    // a number or a string literal in the code
+    Literal,
+    // computed by a built-in word
+    Result(&'static str),
+    // pushed or edited by the host rather than by the program
+    Host,
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Provenance::Literal => write!(f, "literal"),
+            Provenance::Result(word) => write!(f, "result of {}", word),
+            Provenance::Host => write!(f, "edited"),
+        }
+    }
+}
+
+// Read back with the word owned, until it is found among the built-in words
+#[cfg(feature = "serde")]
+#[derive(serde::Deserialize)]
+#[serde(rename = "Provenance")]
+enum SavedProvenance {
+    Literal,
+    Result(String),
+    Host,
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Provenance {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Provenance, D::Error> {
+        use serde::de::Error;
+        Ok(match SavedProvenance::deserialize(deserializer)? {
+            SavedProvenance::Literal => Provenance::Literal,
+            SavedProvenance::Result(word) => crate::BUILT_INS
+                .iter()
+                .find(|&&(name, _)| name == word)
+                .map(|&(name, _)| Provenance::Result(name))
+                .ok_or_else(|| D::Error::custom(format!("not a built-in word: {}", word)))?,
+            SavedProvenance::Host => Provenance::Host,
+        })
+    }
+}
@@ -0,0 +1,38 @@
+use std::time::{SystemTime, UNIX_EPOCH};
+
+// SplitMix64: small and fast, good enough for programs that want dice rolls
+// and reproducible when the seed is known. The seed is only picked on first
+// use, unless set beforehand, so that it can tell whether randomness was used.
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Random {
+    seed: Option<u64>,
+    state: u64,
+}
+
+impl Random {
+    pub(crate) fn seeded(seed: u64) -> Random {
+        Random {
+            seed: Some(seed),
+            state: seed,
+        }
+    }
+
+    pub(crate) fn seed(&self) -> Option<u64> {
+        self.seed
+    }
+
+    pub(crate) fn next(&mut self) -> u64 {
+        if self.seed.is_none() {
+            let seed = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |elapsed| elapsed.as_nanos() as u64);
+            *self = Random::seeded(seed);
+        }
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+}
@@ -0,0 +1,107 @@
+// A virtual text screen that programs draw on: PAGE clears it and AT-XY moves
+// its cursor, so cursor-addressed programs display the same wherever they are
+// shown, without any terminal escape codes reaching the host.
+
+pub const DEFAULT_WIDTH: usize = 80;
+pub const DEFAULT_HEIGHT: usize = 24;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Screen {
+    width: usize,
+    height: usize,
+    // rows of characters, top row first
+    cells: Vec<char>,
+    // column and row of the next character written
+    cursor: (usize, usize),
+    // whether the program cleared the screen or moved the cursor itself
+    addressed: bool,
+}
+
+impl Default for Screen {
+    fn default() -> Self {
+        Screen::new(DEFAULT_WIDTH, DEFAULT_HEIGHT)
+    }
+}
+
+impl Screen {
+    // A screen is at least one character wide and high
+    pub fn new(width: usize, height: usize) -> Screen {
+        let (width, height) = (width.max(1), height.max(1));
+        Screen {
+            width,
+            height,
+            cells: vec![' '; width * height],
+            cursor: (0, 0),
+            addressed: false,
+        }
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn height(&self) -> usize {
+        self.height
+    }
+
+    pub fn cursor(&self) -> (usize, usize) {
+        self.cursor
+    }
+
+    // Rows from the top, without their trailing spaces
+    pub fn lines(&self) -> impl Iterator<Item = String> + '_ {
+        self.cells
+            .chunks(self.width)
+            .map(|row| row.iter().collect::<String>().trim_end().to_string())
+    }
+
+    // Programs drawing with PAGE or AT-XY are better shown as a screen than
+    // as the text they printed
+    pub fn is_addressed(&self) -> bool {
+        self.addressed
+    }
+
+    pub fn is_blank(&self) -> bool {
+        self.cells.iter().all(|&c| c == ' ')
+    }
+
+    pub fn page(&mut self) {
+        self.cells.fill(' ');
+        self.cursor = (0, 0);
+        self.addressed = true;
+    }
+
+    // Positions past the edges move to the last column or row
+    pub fn at_xy(&mut self, column: usize, row: usize) {
+        self.cursor = (column.min(self.width - 1), row.min(self.height - 1));
+        self.addressed = true;
+    }
+
+    // Writes at the cursor, wrapping at the right edge and scrolling up past
+    // the bottom row; a newline moves to the start of the next row
+    pub fn write(&mut self, text: &str) {
+        for c in text.chars() {
+            if c == '\n' {
+                self.new_line();
+                continue;
+            }
+            if self.cursor.0 >= self.width {
+                self.new_line();
+            }
+            let (column, row) = self.cursor;
+            self.cells[row * self.width + column] = c;
+            self.cursor.0 += 1;
+        }
+    }
+
+    fn new_line(&mut self) {
+        self.cursor.0 = 0;
+        if self.cursor.1 + 1 < self.height {
+            self.cursor.1 += 1;
+        } else {
+            self.cells.drain(..self.width);
+            self.cells.resize(self.width * self.height, ' ');
+        }
+    }
+}
@@ -0,0 +1,185 @@
+use crate::code::CompiledBody;
+use crate::dictionary::Dictionary;
+use crate::execution_tokens::ExecutionToken;
+use crate::random::Random;
+use crate::shared::Shared;
+use crate::visibility::Visibility;
+use crate::wordlists::Wordlists;
+use crate::{
+    Definition, Error, Float, Forth, ForthResult, InlineTest, Output, Provenance, Screen, Token,
+    Value,
+};
+use std::collections::HashMap;
+
+// The state evaluation changes, copied to go back to later: the stacks, the
+// dictionary, data space, and what was printed, along with the tests and the
+// steps left. What the host set up is left out: limits, modes, the tracer,
+// the input, and an output sink, whatever was printed to it staying printed.
+// Definitions share their bodies, and compiled code its steps, and data space,
+// the output and the screen are shared until changed, so copying them is
+// cheap.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ForthSnapshot {
+    stack: Vec<Value>,
+    floats: Vec<Float>,
+    return_stack: Vec<Value>,
+    definitions: Vec<Definition>,
+    // sessions saved before the prelude have none
+    #[cfg_attr(feature = "serde", serde(default))]
+    prelude: usize,
+    fuel: Option<u64>,
+    visibility: Visibility,
+    data: Shared<Vec<u8>>,
+    counted_strings: HashMap<String, Value>,
+    strings: HashMap<String, Value>,
+    screen: Shared<Screen>,
+    output: Shared<Output>,
+    random: Random,
+    provenance: Option<Vec<Provenance>>,
+    tests: Vec<InlineTest>,
+    compiling: Option<Vec<Token>>,
+    state_address: Option<Value>,
+    base: Value,
+    base_address: Option<Value>,
+    // compiled again when needed
+    #[cfg_attr(feature = "serde", serde(skip))]
+    code: Vec<Option<CompiledBody>>,
+    dictionary: Dictionary,
+    wordlists: Wordlists,
+    execution_tokens: Vec<ExecutionToken>,
+    warnings: Vec<Error>,
+}
+
+impl ForthSnapshot {
+    pub fn stack(&self) -> &[Value] {
+        &self.stack
+    }
+
+    pub fn definitions(&self) -> &[Definition] {
+        &self.definitions
+    }
+
+    pub fn output(&self) -> &Output {
+        &self.output
+    }
+
+    // Roughly the bytes keeping the snapshot takes, leaving out data space,
+    // the output and the screen when it shares them with an earlier one
+    pub(crate) fn size_after(&self, earlier: Option<&ForthSnapshot>) -> usize {
+        let unshared = |shared: fn(&ForthSnapshot, &ForthSnapshot) -> bool| {
+            !earlier.is_some_and(|earlier| shared(self, earlier))
+        };
+        let mut size = self.stack.len() * std::mem::size_of::<Value>()
+            + self.definitions.len() * std::mem::size_of::<Definition>();
+        if unshared(|a, b| a.data.is_shared_with(&b.data)) {
+            size += self.data.len();
+        }
+        if unshared(|a, b| a.output.is_shared_with(&b.output)) {
+            size += self.output.text().len();
+        }
+        if unshared(|a, b| a.screen.is_shared_with(&b.screen)) {
+            size += self.screen.width() * self.screen.height() * std::mem::size_of::<char>();
+        }
+        size
+    }
+}
+
+impl Forth {
+    // Evaluates like eval, though an error leaves the interpreter as it was
+    // before, rather than with what the words before the error did
+    pub fn eval_atomic(&mut self, input: &str) -> ForthResult {
+        let snapshot = self.snapshot();
+        let result = self.eval(input);
+        if result.is_err() {
+            self.restore(&snapshot);
+        }
+        result
+    }
+
+    pub fn snapshot(&self) -> ForthSnapshot {
+        ForthSnapshot {
+            stack: self.stack.clone(),
+            floats: self.floats.clone(),
+            return_stack: self.return_stack.clone(),
+            definitions: self.definitions.clone(),
+            prelude: self.prelude,
+            fuel: self.fuel,
+            visibility: self.visibility.clone(),
+            data: self.data.clone(),
+            counted_strings: self.counted_strings.clone(),
+            strings: self.strings.clone(),
+            screen: self.screen.clone(),
+            output: self.output.clone(),
+            random: self.random.clone(),
+            provenance: self.provenance.clone(),
+            tests: self.tests.clone(),
+            compiling: self.compiling.clone(),
+            state_address: self.state_address,
+            base: self.base,
+            base_address: self.base_address,
+            code: self.code.clone(),
+            dictionary: self.dictionary.clone(),
+            wordlists: self.wordlists.clone(),
+            execution_tokens: self.execution_tokens.clone(),
+            warnings: self.warnings.clone(),
+        }
+    }
+
+    // Goes back to the state of the snapshot, which can be another
+    // interpreter's
+    pub fn restore(&mut self, snapshot: &ForthSnapshot) {
+        self.stack.clone_from(&snapshot.stack);
+        self.floats.clone_from(&snapshot.floats);
+        self.return_stack.clone_from(&snapshot.return_stack);
+        self.definitions.clone_from(&snapshot.definitions);
+        self.prelude = snapshot.prelude;
+        self.fuel = snapshot.fuel;
+        self.visibility = snapshot.visibility.clone();
+        self.data = snapshot.data.clone();
+        self.counted_strings.clone_from(&snapshot.counted_strings);
+        self.strings.clone_from(&snapshot.strings);
+        self.screen = snapshot.screen.clone();
+        self.output = snapshot.output.clone();
+        self.random = snapshot.random.clone();
+        self.provenance.clone_from(&snapshot.provenance);
+        self.tests.clone_from(&snapshot.tests);
+        self.compiling.clone_from(&snapshot.compiling);
+        self.state_address = snapshot.state_address;
+        self.base = snapshot.base;
+        self.base_address = snapshot.base_address;
+        self.code.clone_from(&snapshot.code);
+        self.dictionary = snapshot.dictionary.clone();
+        self.wordlists = snapshot.wordlists.clone();
+        self.execution_tokens.clone_from(&snapshot.execution_tokens);
+        self.warnings.clone_from(&snapshot.warnings);
+        // nothing runs between words
+        self.call_depth = 0;
+        self.nesting = 0;
+        self.recursion_depth = 0;
+        self.loops.clear();
+        self.next_name = None;
+        self.origin = None;
+        self.including.clear();
+    }
+}
+
+// A Forth is saved as its snapshot, and read back as a new interpreter with
+// the snapshot restored
+#[cfg(feature = "serde")]
+impl serde::Serialize for Forth {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        self.snapshot().serialize(serializer)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> serde::Deserialize<'de> for Forth {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Forth, D::Error> {
+        let snapshot = ForthSnapshot::deserialize(deserializer)?;
+        let mut forth = Forth::new_bare();
+        forth.restore(&snapshot);
+        forth.find_defining_words();
+        Ok(forth)
+    }
+}
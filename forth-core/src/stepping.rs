@@ -0,0 +1,129 @@
+use crate::{
+    is_closing, is_opening, is_parsing_word, parsed_literal, Forth, ForthResult, Position, Words,
+};
+
+// Where evaluating source one top-level word at a time is, like a debugger
+// stepping through it. A step is a word with the words it takes after it: a
+// whole `: ... ;` definition, the name after VARIABLE or CREATE, the text of
+// a string, so that stepping evaluates source the same way eval does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StepCursor {
+    offset: usize,
+    position: Position,
+}
+
+impl Default for StepCursor {
+    fn default() -> Self {
+        StepCursor::new()
+    }
+}
+
+impl StepCursor {
+    // At the start of the source
+    pub fn new() -> StepCursor {
+        StepCursor {
+            offset: 0,
+            position: (1, 1),
+        }
+    }
+
+    // The first word of the next step, with the line and column it is at;
+    // None at the end of the source
+    pub fn next_word<'a>(&self, source: &'a str) -> Option<(&'a str, usize, usize)> {
+        let mut words = Words::resuming(source, self.offset, self.position);
+        let word = words.next()?;
+        let (line, column) = words.word_position();
+        Some((word, line, column))
+    }
+
+    // Lines steps start on, as far as can be told without evaluating the
+    // source: a line within a definition or a control structure, after the
+    // one it starts on, is never stopped at
+    pub fn step_lines(source: &str) -> Vec<usize> {
+        let mut lines = Vec::new();
+        let mut words = Words::new(source);
+        while let Some(word) = words.next() {
+            let (line, _) = words.word_position();
+            if lines.last() != Some(&line) {
+                lines.push(line);
+            }
+            skip_taken_words(word, &mut words);
+        }
+        lines
+    }
+}
+
+impl Forth {
+    // Evaluates the step at the cursor, moving the cursor past it, and
+    // returns the source it took with what it did; None at the end of the
+    // source
+    pub fn eval_step<'a>(
+        &mut self,
+        source: &'a str,
+        cursor: &mut StepCursor,
+    ) -> Option<(&'a str, ForthResult)> {
+        let mut words = Words::resuming(source, cursor.offset, cursor.position);
+        let word = words.next()?;
+        let start = words.word_offset(word);
+        let position = words.word_position();
+        let warned = self.warnings.len();
+        let result = self.eval_word(word, &mut words);
+        self.locate_warnings(warned, word, Some(&position));
+        let result = result.map_err(|error| error.at(word, Some(&position)));
+        cursor.offset = words.offset();
+        cursor.position = words.position();
+        Some((&source[start..cursor.offset], result))
+    }
+}
+
+const NAMING_WORDS: [&str; 6] = [
+    "VARIABLE",
+    "CONSTANT",
+    "VALUE",
+    "DEFER",
+    "MARKER",
+    "VOCABULARY",
+];
+
+// Moves past the words the first word of a step takes after it, as
+// eval_word does
+fn skip_taken_words(word: &str, words: &mut Words) {
+    let is = |name: &str| word.eq_ignore_ascii_case(name);
+    if is_parsing_word(word) {
+        let _ = parsed_literal(word, words);
+    } else if NAMING_WORDS.iter().any(|&name| is(name)) {
+        words.next();
+    } else if word == ":" || is(":NONAME") {
+        skip_body(words, ";");
+    } else if is("TEST:") {
+        skip_body(words, ";TEST");
+    } else if is_opening(word) {
+        skip_structure(words);
+    }
+}
+
+fn skip_body(words: &mut Words, end: &str) {
+    while let Some(word) = words.next() {
+        if is_parsing_word(word) {
+            let _ = parsed_literal(word, words);
+        } else if word.eq_ignore_ascii_case(end) {
+            return;
+        }
+    }
+}
+
+fn skip_structure(words: &mut Words) {
+    let mut depth = 1;
+    while let Some(word) = words.next() {
+        if is_parsing_word(word) {
+            let _ = parsed_literal(word, words);
+        } else if is_opening(word) {
+            depth += 1;
+        } else if is_closing(word) {
+            depth -= 1;
+            if depth == 0 {
+                return;
+            }
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use crate::{Error, ErrorKind, Forth, ForthResult, Position};
+
+// What evaluation does with an unknown word: fail, by default, or skip it
+// with a warning, for a beginner to see what the rest of the code does
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Strictness {
+    #[default]
+    Strict,
+    // an unknown word is left out, its error kept as a warning
+    Tolerant,
+}
+
+impl Forth {
+    // The error of an unknown word run, or nothing once it is a warning.
+    // Kept out of run_step, which calls it, for its stack frame to stay small.
+    #[cold]
+    #[inline(never)]
+    pub(crate) fn unknown_word_run(&mut self, error: Error) -> ForthResult {
+        match (self.strictness, error.kind()) {
+            (Strictness::Tolerant, ErrorKind::UnknownWord) => {
+                self.warnings.push(error);
+                Ok(())
+            }
+            _ => Err(error),
+        }
+    }
+
+    // Warnings given from the `from`th on, as running a word of the input,
+    // are located at that word, like errors, unless they are already
+    pub(crate) fn locate_warnings(&mut self, from: usize, word: &str, position: Option<&Position>) {
+        if self.warnings.len() > from {
+            let warnings = self.warnings.split_off(from);
+            let located = warnings.into_iter().map(|w| w.at(word, position));
+            self.warnings.extend(located);
+        }
+    }
+}
@@ -0,0 +1,77 @@
+use crate::{Error, ErrorKind, Forth, BUILT_INS, CONTROL_WORDS};
+
+// An unknown word is likely a typo: the words it is closest to, by the
+// number of characters to insert, delete or replace to get one from the
+// other, are suggested with the error, for "did you mean".
+
+// Words suggested at most
+const MAX_SUGGESTIONS: usize = 3;
+// Most characters to change, fewer than the unknown word has
+const MAX_DISTANCE: usize = 2;
+
+// Words an unknown word may have been meant as
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Suggestions {
+    // the unknown word, as written
+    pub word: String,
+    // the closest first
+    pub words: Vec<String>,
+}
+
+impl Forth {
+    // UnknownWord for a word the code seeing the first `visible`
+    // definitions can't find, with the words it can that are close to it
+    pub(crate) fn unknown_word(&self, word: &str, visible: usize) -> Error {
+        let length = word.chars().count();
+        let mut close: Vec<(usize, String)> = Vec::new();
+        let definitions = self.definitions[..visible.min(self.definitions.len())]
+            .iter()
+            .rev()
+            .map(|definition| definition.name.as_str());
+        let built_ins = CONTROL_WORDS
+            .iter()
+            .map(|&(name, _)| name)
+            .chain(BUILT_INS.iter().map(|&(name, _)| name));
+        for name in definitions.chain(built_ins) {
+            if name.is_empty() || close.iter().any(|(_, n)| n.eq_ignore_ascii_case(name)) {
+                continue;
+            }
+            let distance = distance(word, name);
+            if distance <= MAX_DISTANCE
+                && distance < length
+                && self.instruction_from_word(name, visible).is_ok()
+            {
+                close.push((distance, name.to_string()));
+            }
+        }
+        // stable, so that definitions come before built-ins as close
+        close.sort_by_key(|&(distance, _)| distance);
+        let words = close
+            .into_iter()
+            .take(MAX_SUGGESTIONS)
+            .map(|(_, name)| name)
+            .collect();
+        Error::from(ErrorKind::UnknownWord).with_suggestions(Suggestions {
+            word: word.to_string(),
+            words,
+        })
+    }
+}
+
+// Characters to insert, delete or replace to get a word from another,
+// ignoring case
+fn distance(a: &str, b: &str) -> usize {
+    let a: Vec<char> = a.chars().map(|c| c.to_ascii_uppercase()).collect();
+    let b: Vec<char> = b.chars().map(|c| c.to_ascii_uppercase()).collect();
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    for (i, &ca) in a.iter().enumerate() {
+        let mut current = vec![i + 1];
+        for (j, &cb) in b.iter().enumerate() {
+            let replaced = previous[j] + usize::from(ca != cb);
+            current.push(replaced.min(previous[j + 1] + 1).min(current[j] + 1));
+        }
+        previous = current;
+    }
+    previous[b.len()]
+}
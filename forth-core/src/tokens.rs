@@ -0,0 +1,109 @@
+use std::borrow::Borrow;
+use std::collections::HashSet;
+use std::fmt::{self, Display, Write};
+use std::ops::Deref;
+use std::sync::Arc;
+
+// A word of a definition's body. Each distinct word compiled is stored once,
+// by the interpreter's `Tokens`, and shared by every body it is in: compiling
+// a word seen before allocates nothing.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(transparent)
+)]
+pub struct Token(Arc<str>);
+
+impl Token {
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for Token {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Token {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Token {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl From<&str> for Token {
+    fn from(word: &str) -> Token {
+        Token(Arc::from(word))
+    }
+}
+
+impl From<String> for Token {
+    fn from(word: String) -> Token {
+        Token(Arc::from(word))
+    }
+}
+
+impl PartialEq<str> for Token {
+    fn eq(&self, other: &str) -> bool {
+        *self.0 == *other
+    }
+}
+
+impl PartialEq<&str> for Token {
+    fn eq(&self, other: &&str) -> bool {
+        *self.0 == **other
+    }
+}
+
+// The words compiled so far. Words no body uses any longer are let go of
+// once there are twice as many as when that was last done.
+#[derive(Default)]
+pub(crate) struct Tokens {
+    tokens: HashSet<Token>,
+    kept: usize,
+    // where numbers are written to be looked up
+    scratch: String,
+}
+
+const MIN_TOKENS_KEPT: usize = 1024;
+
+impl Tokens {
+    pub(crate) fn get(&mut self, word: &str) -> Token {
+        if let Some(token) = self.tokens.get(word) {
+            return token.clone();
+        }
+        if self.tokens.len() >= (2 * self.kept).max(MIN_TOKENS_KEPT) {
+            self.tokens.retain(|token| Arc::strong_count(&token.0) > 1);
+            self.kept = self.tokens.len();
+        }
+        let token = Token::from(word);
+        self.tokens.insert(token.clone());
+        token
+    }
+
+    // The token of a value as written, a number for instance
+    pub(crate) fn written(&mut self, value: impl Display) -> Token {
+        let mut scratch = std::mem::take(&mut self.scratch);
+        scratch.clear();
+        let _ = write!(scratch, "{}", value);
+        let token = self.get(&scratch);
+        self.scratch = scratch;
+        token
+    }
+}
@@ -0,0 +1,203 @@
+use crate::{
+    built_in, control_word, to_target, Body, Definition, DefinitionKind, Error, ErrorKind,
+    EvalEvent, Forth, ForthResult, Instruction, Token, Value,
+};
+use std::sync::Arc;
+
+// Words naming data rather than code, defined from the top level:
+//
+//     VARIABLE COUNTER      a cell of data space, COUNTER pushing its address
+//     42 CONSTANT ANSWER    ANSWER pushing 42
+//     10 VALUE SPEED        SPEED pushing 10, until 20 TO SPEED changes it
+//     CREATE TABLE          TABLE pushing the address of the data space
+//                           reserved after it, by ALLOT or `,`
+//
+// Each is a definition whose body is the number it pushes, so that it shows
+// up, runs and is saved like any other definition; TO rewrites the body.
+//
+// CREATE can also run within a colon definition, making a defining word
+// taking the name after it in the source: `: ARRAY CREATE CELLS ALLOT ;`
+// then `10 ARRAY SCORES`. DOES> ends such a definition, the words after it
+// running whenever the word it created runs, after pushing its address:
+// the created word's body is then its address and the defining word's index.
+
+impl Forth {
+    pub(crate) fn add_variable<'a, I>(&mut self, words: &mut I) -> ForthResult
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        self.visibility.check_can_define()?;
+        let name = definition_name(words.next())?;
+        let address = self.reserve_cell()?;
+        let body = Arc::from([self.tokens.written(address)]);
+        self.define(name, DefinitionKind::Variable, body);
+        Ok(())
+    }
+
+    // CONSTANT and VALUE, taking their value from the stack
+    pub(crate) fn add_constant<'a, I>(&mut self, kind: DefinitionKind, words: &mut I) -> ForthResult
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        self.visibility.check_can_define()?;
+        let name = definition_name(words.next())?;
+        let value = self.pop()?;
+        let body = Arc::from([self.tokens.written(value)]);
+        self.define(name, kind, body);
+        Ok(())
+    }
+
+    // CREATE ( "name" -- ), taking the name the top-level code left for it
+    pub(crate) fn create(&mut self) -> ForthResult {
+        let name = self.next_name.take().ok_or(ErrorKind::InvalidWord)?;
+        self.visibility.check_can_define()?;
+        let name = definition_name(Some(&name))?.to_string();
+        self.align();
+        let address = Value::try_from(self.data.len()).map_err(|_| ErrorKind::InvalidAddress)?;
+        let body = Arc::from([self.tokens.written(address)]);
+        self.define(&name, DefinitionKind::Created, body);
+        Ok(())
+    }
+
+    // Whether running a word at the top level takes the name after it, which
+    // it does when it runs CREATE or calls a definition that does
+    pub(crate) fn takes_name(&self, word: &str, visible: usize) -> bool {
+        match self.instruction_from_word(word, visible) {
+            Ok(Instruction::Create) => true,
+            Ok(Instruction::CallDefinition(index)) => self.definitions[index].creates,
+            _ => false,
+        }
+    }
+
+    // Whether a colon definition's body runs CREATE, decided once as it is
+    // defined, from the definitions before it
+    pub(crate) fn body_creates<S: AsRef<str>>(&self, words: &[S]) -> bool {
+        let visible = self.definitions.len();
+        words
+            .iter()
+            .any(|word| self.takes_name(word.as_ref(), visible))
+    }
+
+    // Decides again for every definition, as read back from a session
+    // saved before definitions kept it
+    #[cfg(feature = "serde")]
+    pub(crate) fn find_defining_words(&mut self) {
+        let definitions = std::mem::take(&mut self.definitions);
+        for mut definition in definitions {
+            definition.creates = definition.kind == DefinitionKind::Colon
+                && self.body_creates(&definition.instructions);
+            self.definitions.push(definition);
+        }
+    }
+
+    // Runs the words of a defining word up to its DOES>, then has the word
+    // they created run the words after it
+    pub(crate) fn call_defining_word(&mut self, index: usize, does: usize) -> ForthResult {
+        let words = self.definitions[index].instructions.clone();
+        let code = self.definition_code(index);
+        self.run(
+            Body {
+                words: &words[..does],
+                code: &code[..does],
+                positions: &[],
+            },
+            index,
+        )?;
+        let created = self.definitions.last_mut().ok_or(ErrorKind::InvalidWord)?;
+        if created.kind != DefinitionKind::Created {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        let address = created.instructions[0].clone();
+        let defining = self.tokens.written(index);
+        let created = self.definitions.last_mut().ok_or(ErrorKind::InvalidWord)?;
+        created.instructions = Arc::from([address, defining]);
+        Ok(())
+    }
+
+    // Pushes a created word's address, then runs the words after the DOES>
+    // of the word that made it, if any
+    pub(crate) fn call_created(&mut self, index: usize) -> ForthResult {
+        let body = self.definitions[index].instructions.clone();
+        let address = body[0].parse().map_err(|_| ErrorKind::Internal)?;
+        self.push(address)?;
+        let Some(defining) = body.get(1) else {
+            return Ok(());
+        };
+        let defining: usize = defining.parse().map_err(|_| ErrorKind::Internal)?;
+        let words = self.definitions[defining].instructions.clone();
+        let does = does_index(&words).ok_or(ErrorKind::Internal)?;
+        let code = self.definition_code(defining);
+        self.run(
+            Body {
+                words: &words[does + 1..],
+                code: &code[does + 1..],
+                positions: &[],
+            },
+            defining,
+        )
+    }
+
+    // The latest definition of a name among the first `visible` ones, for
+    // words changing a definition like TO
+    pub(crate) fn target_definition(&self, name: &str, visible: usize) -> Result<usize, Error> {
+        self.find_definition(name, visible)
+            .ok_or(ErrorKind::UnknownWord.into())
+    }
+
+    // `TO name` ( x -- ), giving a VALUE a new value
+    pub(crate) fn store_value(&mut self, word: &str, visible: usize) -> ForthResult {
+        let name = to_target(word).ok_or(ErrorKind::Internal)?;
+        let index = self.target_definition(name, visible)?;
+        if self.definitions[index].kind != DefinitionKind::Value {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        let value = self.pop()?;
+        self.definitions[index].instructions = Arc::from([self.tokens.written(value)]);
+        Ok(())
+    }
+
+    pub(crate) fn define(&mut self, name: &str, kind: DefinitionKind, instructions: Arc<[Token]>) {
+        let name = self.case_mode.name(name);
+        let exists = built_in(&name).is_some() || self.is_defined(&name);
+        let creates = kind == DefinitionKind::Colon && self.body_creates(&instructions);
+        if self.events.is_some() {
+            self.record(if exists {
+                EvalEvent::Redefined(name.clone())
+            } else {
+                EvalEvent::Defined(name.clone())
+            });
+        }
+        self.definitions.push(Definition {
+            name,
+            instructions,
+            kind,
+            stack_effect: None,
+            immediate: false,
+            wordlist: self.current_wordlist(),
+            creates,
+        });
+        self.index_definitions();
+    }
+}
+
+// Name given to a new definition, which can't be a number nor a control word
+pub(crate) fn definition_name(word: Option<&str>) -> Result<&str, Error> {
+    match word {
+        Some(word) if word.parse::<Value>().is_err() && !is_reserved(word) => Ok(word),
+        _ => Err(ErrorKind::InvalidWord.into()),
+    }
+}
+
+fn is_reserved(word: &str) -> bool {
+    control_word(word).is_some()
+        || word.eq_ignore_ascii_case("TO")
+        || word.eq_ignore_ascii_case("IS")
+        || word.eq_ignore_ascii_case("FORGET")
+}
+
+// Where DOES> is in a definition's words, if it is
+pub(crate) fn does_index<S: AsRef<str>>(words: &[S]) -> Option<usize> {
+    words
+        .iter()
+        .position(|word| word.as_ref().eq_ignore_ascii_case("DOES>"))
+}
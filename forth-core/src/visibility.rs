@@ -0,0 +1,145 @@
+use crate::{ErrorKind, Forth, ForthResult, Instruction};
+use std::collections::HashSet;
+
+// Controls over which words code can call, for embedders sandboxing scripts.
+//
+// Words are looked up again once visibility changes, so hiding a word also
+// breaks the definitions calling it.
+
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Visibility {
+    // indexes of hidden definitions
+    hidden: HashSet<usize>,
+    frozen: bool,
+    restriction: Option<Restriction>,
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+struct Restriction {
+    allowed: HashSet<String>,
+    // definitions from this one on are restricted like the code evaluated
+    first_restricted: usize,
+}
+
+impl Visibility {
+    pub(crate) fn forget_from(&mut self, index: usize) {
+        self.hidden.retain(|&hidden| hidden < index);
+    }
+
+    pub(crate) fn is_hidden(&self, index: usize) -> bool {
+        self.hidden.contains(&index)
+    }
+
+    // Whether a word resolved to `instruction` may run, when looked up by code
+    // seeing the first `visible` definitions
+    pub(crate) fn allows(&self, word: &str, instruction: &Instruction, visible: usize) -> bool {
+        let Some(restriction) = &self.restriction else {
+            return true;
+        };
+        if visible < restriction.first_restricted {
+            return true;
+        }
+        match instruction {
+            Instruction::Number(_)
+            | Instruction::DoubleNumber
+            | Instruction::FloatNumber
+            | Instruction::CountedString
+            | Instruction::String
+            | Instruction::PrintString
+            | Instruction::AbortQuote
+            | Instruction::To
+            | Instruction::Tick
+            | Instruction::Is
+            | Instruction::Postpone
+            | Instruction::Begin
+            | Instruction::Again
+            | Instruction::Until
+            | Instruction::While
+            | Instruction::Repeat
+            | Instruction::If
+            | Instruction::Else
+            | Instruction::Then
+            | Instruction::Do
+            | Instruction::Loop
+            | Instruction::PlusLoop
+            | Instruction::Case
+            | Instruction::Of
+            | Instruction::Endof
+            | Instruction::Endcase => true,
+            Instruction::CallDefinition(index) if *index >= restriction.first_restricted => true,
+            _ => restriction.allowed.contains(&word.to_ascii_uppercase()),
+        }
+    }
+
+    pub(crate) fn check_can_define(&self) -> ForthResult {
+        if self.frozen {
+            Err(ErrorKind::DictionaryFrozen.into())
+        } else {
+            Ok(())
+        }
+    }
+}
+
+impl Forth {
+    // Whether code evaluated now can call the word
+    pub fn is_visible(&self, word: &str) -> bool {
+        self.instruction_from_word(word, self.definitions.len())
+            .is_ok()
+    }
+
+    // Hides the latest visible definition of a word, uncovering the one it
+    // shadowed if any. Returns false if there was none.
+    pub fn hide(&mut self, word: &str) -> bool {
+        let named = |i: usize| self.case_mode.matches(&self.definitions[i].name, word);
+        let index = (0..self.definitions.len())
+            .rev()
+            .find(|&i| named(i) && !self.visibility.is_hidden(i));
+        self.drop_code();
+        match index {
+            Some(index) => self.visibility.hidden.insert(index),
+            None => false,
+        }
+    }
+
+    // Hides every definition made so far, leaving only built-in words
+    pub fn hide_definitions(&mut self) {
+        self.drop_code();
+        self.visibility.hidden.extend(0..self.definitions.len());
+    }
+
+    pub fn reveal_definitions(&mut self) {
+        self.drop_code();
+        self.visibility.hidden.clear();
+    }
+
+    // New definitions fail while the dictionary is frozen
+    pub fn freeze(&mut self) {
+        self.visibility.frozen = true;
+    }
+
+    pub fn unfreeze(&mut self) {
+        self.visibility.frozen = false;
+    }
+
+    pub fn is_frozen(&self) -> bool {
+        self.visibility.frozen
+    }
+
+    // From now on, code can only call the given words (built-in or defined), numbers,
+    // and the definitions it makes itself. Definitions made before keep calling
+    // whatever they call.
+    pub fn restrict_to<'a>(&mut self, words: impl IntoIterator<Item = &'a str>) {
+        self.drop_code();
+        self.visibility.restriction = Some(Restriction {
+            allowed: words.into_iter().map(str::to_ascii_uppercase).collect(),
+            first_restricted: self.definitions.len(),
+        });
+    }
+
+    pub fn lift_restriction(&mut self) {
+        self.drop_code();
+        self.visibility.restriction = None;
+    }
+}
@@ -0,0 +1,208 @@
+use crate::variables::definition_name;
+use crate::{DefinitionKind, Error, ErrorKind, Forth, ForthResult, Instruction, Value};
+use std::sync::Arc;
+
+// Wordlists and the search order: definitions go to the current wordlist,
+// and words are looked up in the wordlists of the search order, the first
+// one first, then among the built-in words, whatever the order.
+//
+//     FORTH-WORDLIST ( -- wid )          the wordlist definitions go to at first
+//     WORDLIST ( -- wid )                a new, empty wordlist
+//     GET-ORDER ( -- widn ... wid1 n )   the search order, wid1 searched first
+//     SET-ORDER ( widn ... wid1 n -- )   setting it, -1 for FORTH-WORDLIST alone
+//     GET-CURRENT and SET-CURRENT        the wordlist definitions go to
+//     DEFINITIONS                        making it the first of the order
+//     VOCABULARY NAME                    NAME making a wordlist of its own
+//                                        the first of the order
+//     ALSO, ONLY, PREVIOUS and FORTH     changing the first of the order
+//
+// A definition looks words up in the search order of when it was made.
+// Wordlist ids are positions in `names` plus one, so that 0 isn't one.
+
+// Longest search order
+const MAX_ORDER: usize = 16;
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub(crate) struct Wordlists {
+    // empty for the wordlists made by WORDLIST
+    names: Vec<String>,
+    order: Arc<[usize]>,
+    current: usize,
+    // the search order each definition was made with
+    orders: Vec<Arc<[usize]>>,
+}
+
+impl Default for Wordlists {
+    fn default() -> Wordlists {
+        Wordlists {
+            names: vec![String::from("FORTH")],
+            order: Arc::from([0]),
+            current: 0,
+            orders: Vec::new(),
+        }
+    }
+}
+
+impl Forth {
+    // The wordlists searched, the first one first
+    pub fn search_order(&self) -> &[usize] {
+        &self.wordlists.order
+    }
+
+    // The wordlist definitions go to
+    pub fn current_wordlist(&self) -> usize {
+        self.wordlists.current
+    }
+
+    pub fn wordlist_name(&self, wordlist: usize) -> String {
+        match self.wordlists.names.get(wordlist) {
+            Some(name) if !name.is_empty() => name.clone(),
+            _ => format!("WORDLIST {}", wordlist + 1),
+        }
+    }
+
+    // Search order the code seeing the first `visible` definitions looks
+    // words up in: the body of definition `visible`, or code evaluated now
+    pub(crate) fn order_seen(&self, visible: usize) -> &[usize] {
+        match self.wordlists.orders.get(visible) {
+            Some(order) if visible < self.definitions.len() => order,
+            _ => &self.wordlists.order,
+        }
+    }
+
+    // Keeps the search order of the definitions made since last time
+    pub(crate) fn keep_orders(&mut self) {
+        let order = self.wordlists.order.clone();
+        self.wordlists.orders.resize(self.definitions.len(), order);
+    }
+
+    pub(crate) fn add_vocabulary<'a, I>(&mut self, words: &mut I) -> ForthResult
+    where
+        I: Iterator<Item = &'a str>,
+    {
+        self.visibility.check_can_define()?;
+        let name = self.case_mode.name(definition_name(words.next())?);
+        let wid = self.new_wordlist(name.clone())?;
+        let body = Arc::from([self.tokens.written(wid)]);
+        self.define(&name, DefinitionKind::Vocabulary, body);
+        Ok(())
+    }
+
+    // Runs a vocabulary, making its wordlist the first of the order
+    pub(crate) fn call_vocabulary(&mut self, index: usize) -> ForthResult {
+        let wid = self.definitions[index].instructions[0]
+            .parse()
+            .map_err(|_| ErrorKind::Internal)?;
+        let wordlist = self.wordlist(wid)?;
+        self.replace_first(wordlist);
+        Ok(())
+    }
+
+    pub(crate) fn search_order_word(&mut self, instruction: Instruction) -> ForthResult {
+        match instruction {
+            Instruction::ForthWordlist => self.push(1),
+            Instruction::Wordlist => {
+                let wid = self.new_wordlist(String::new())?;
+                self.push(wid)
+            }
+            Instruction::GetOrder => self.get_order(),
+            Instruction::SetOrder => self.set_order(),
+            Instruction::GetCurrent => self.push(wid(self.wordlists.current)?),
+            Instruction::SetCurrent => {
+                let wid = self.pop()?;
+                self.wordlists.current = self.wordlist(wid)?;
+                Ok(())
+            }
+            Instruction::Definitions => {
+                let first = self.wordlists.order.first().ok_or(ErrorKind::InvalidWord)?;
+                self.wordlists.current = *first;
+                Ok(())
+            }
+            Instruction::Also => {
+                let first = *self.wordlists.order.first().ok_or(ErrorKind::InvalidWord)?;
+                self.set_search_order(std::iter::once(first).chain(self.wordlists.order.to_vec()))
+            }
+            Instruction::Only => self.set_search_order([0]),
+            Instruction::Previous => {
+                if self.wordlists.order.is_empty() {
+                    return Err(ErrorKind::InvalidWord.into());
+                }
+                self.set_search_order(self.wordlists.order[1..].to_vec())
+            }
+            Instruction::Forth => {
+                self.replace_first(0);
+                Ok(())
+            }
+            _ => Err(ErrorKind::Internal.into()),
+        }
+    }
+
+    // GET-ORDER ( -- widn ... wid1 n )
+    fn get_order(&mut self) -> ForthResult {
+        for &wordlist in self.wordlists.order.clone().iter().rev() {
+            self.push(wid(wordlist)?)?;
+        }
+        let count = Value::try_from(self.wordlists.order.len()).map_err(|_| ErrorKind::Internal)?;
+        self.push(count)
+    }
+
+    // SET-ORDER ( widn ... wid1 n -- )
+    fn set_order(&mut self) -> ForthResult {
+        let count = self.pop()?;
+        if count == -1 {
+            return self.set_search_order([0]);
+        }
+        let count = usize::try_from(count).map_err(|_| ErrorKind::InvalidWord)?;
+        if count > MAX_ORDER {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        let mut order = Vec::with_capacity(count);
+        for _ in 0..count {
+            let wid = self.pop()?;
+            order.push(self.wordlist(wid)?);
+        }
+        self.set_search_order(order)
+    }
+
+    fn set_search_order(&mut self, order: impl IntoIterator<Item = usize>) -> ForthResult {
+        let order: Arc<[usize]> = order.into_iter().collect();
+        if order.len() > MAX_ORDER {
+            return Err(ErrorKind::InvalidWord.into());
+        }
+        self.wordlists.order = order;
+        Ok(())
+    }
+
+    // Replaces the first wordlist of the order, or makes it the only one
+    fn replace_first(&mut self, wordlist: usize) {
+        let mut order = self.wordlists.order.to_vec();
+        match order.first_mut() {
+            Some(first) => *first = wordlist,
+            None => order.push(wordlist),
+        }
+        self.wordlists.order = order.into();
+    }
+
+    fn new_wordlist(&mut self, name: String) -> Result<Value, Error> {
+        self.wordlists.names.push(name);
+        wid(self.wordlists.names.len() - 1)
+    }
+
+    // Wordlist with an id, if there is one
+    fn wordlist(&self, wid: Value) -> Result<usize, Error> {
+        usize::try_from(wid)
+            .ok()
+            .and_then(|wid| wid.checked_sub(1))
+            .filter(|&wordlist| wordlist < self.wordlists.names.len())
+            .ok_or(ErrorKind::InvalidWord.into())
+    }
+
+    pub(crate) fn forget_orders(&mut self, first_forgotten: usize) {
+        self.wordlists.orders.truncate(first_forgotten);
+    }
+}
+
+fn wid(wordlist: usize) -> Result<Value, Error> {
+    Value::try_from(wordlist + 1).map_err(|_| ErrorKind::Internal.into())
+}
@@ -0,0 +1,145 @@
+use crate::{is_parsing_word, is_string_opening};
+
+// Splits source code into words, leaving out comments: `( ... )` up to the
+// next closing parenthesis, and `\` up to the end of the line. The argument
+// of a parsing word is kept as it is, so `CHAR (` or `." a ( b"` still work.
+pub(crate) struct Words<'a> {
+    rest: &'a str,
+    // bytes of the whole source
+    length: usize,
+    argument: Argument,
+    // where rest starts, and where the last word taken started
+    position: Position,
+    word_position: Position,
+}
+
+// Line and column of the source, counting lines and characters from 1
+pub(crate) type Position = (usize, usize);
+
+enum Argument {
+    None,
+    // the next word, like the name after CHAR
+    Word,
+    // words up to one ending with a quote, like the text after C"
+    Text,
+}
+
+impl<'a> Words<'a> {
+    pub(crate) fn new(source: &'a str) -> Words<'a> {
+        Words::resuming(source, 0, (1, 1))
+    }
+
+    // Words from the one at `offset`, in bytes, which is at `position`
+    pub(crate) fn resuming(source: &'a str, offset: usize, position: Position) -> Words<'a> {
+        Words {
+            rest: &source[offset..],
+            length: source.len(),
+            argument: Argument::None,
+            position,
+            word_position: position,
+        }
+    }
+
+    // Where the words left start, in bytes and as line and column
+    pub(crate) fn offset(&self) -> usize {
+        self.length - self.rest.len()
+    }
+
+    pub(crate) fn position(&self) -> Position {
+        self.position
+    }
+
+    // Where the last word taken starts
+    pub(crate) fn word_position(&self) -> Position {
+        self.word_position
+    }
+
+    // And where it starts in bytes, given the word
+    pub(crate) fn word_offset(&self, word: &str) -> usize {
+        self.offset() - word.len()
+    }
+
+    // Whether the next word is the argument of the one before, rather than
+    // a word of its own
+    pub(crate) fn is_argument_next(&self) -> bool {
+        !matches!(self.argument, Argument::None)
+    }
+
+    // Moves on to the end of the source part left, counting the lines and
+    // characters passed
+    fn move_to(&mut self, rest: &'a str) {
+        let passed = &self.rest[..self.rest.len() - rest.len()];
+        for c in passed.chars() {
+            self.position = match c {
+                '\n' => (self.position.0 + 1, 1),
+                _ => (self.position.0, self.position.1 + 1),
+            };
+        }
+        self.rest = rest;
+    }
+
+    // A `( ... )` comment coming next, such as the stack effect after the
+    // name of a definition, taken with its spacing tidied up
+    pub(crate) fn stack_effect(&mut self) -> Option<String> {
+        let start = self.rest.trim_start();
+        let comment = start.strip_prefix('(')?;
+        if !comment.starts_with(char::is_whitespace) {
+            return None;
+        }
+        let (comment, rest) = comment.split_once(')')?;
+        self.move_to(rest);
+        let mut stack_effect = String::from("(");
+        for word in comment.split_whitespace() {
+            stack_effect.push(' ');
+            stack_effect.push_str(word);
+        }
+        stack_effect.push_str(" )");
+        Some(stack_effect)
+    }
+
+    // Moves past the first `end` character, or to the end of the source
+    fn skip_past(&mut self, end: char) {
+        let rest = match self.rest.split_once(end) {
+            Some((_, rest)) => rest,
+            None => &self.rest[self.rest.len()..],
+        };
+        self.move_to(rest);
+    }
+}
+
+impl<'a> Iterator for Words<'a> {
+    type Item = &'a str;
+
+    fn next(&mut self) -> Option<&'a str> {
+        loop {
+            let start = self.rest.trim_start();
+            self.move_to(start);
+            if start.is_empty() {
+                return None;
+            }
+            let length = start.find(char::is_whitespace).unwrap_or(start.len());
+            let (word, rest) = start.split_at(length);
+            self.word_position = self.position;
+            self.move_to(rest);
+            match self.argument {
+                Argument::Word => self.argument = Argument::None,
+                Argument::Text if word.ends_with('"') => self.argument = Argument::None,
+                Argument::Text => {}
+                Argument::None => match word {
+                    "(" => {
+                        self.skip_past(')');
+                        continue;
+                    }
+                    "\\" => {
+                        self.skip_past('\n');
+                        continue;
+                    }
+                    _ if is_string_opening(word) => self.argument = Argument::Text,
+                    _ if is_parsing_word(word) => self.argument = Argument::Word,
+                    _ => {}
+                },
+            }
+            return Some(word);
+        }
+    }
+}
@@ -0,0 +1,297 @@
+// Numbers and what arithmetic does with them, in each mode
+
+mod common;
+
+use common::kind;
+use forth_core::{DivisionMode, ErrorKind, Forth, ForthBuilder, OverflowMode};
+
+#[test]
+fn arithmetic_wraps_around_on_overflow() {
+    let mut forth = Forth::new();
+    assert!(forth.eval("9223372036854775807 1 +").is_ok());
+    assert_eq!(forth.stack(), [i64::MIN]);
+
+    let mut forth = Forth::new();
+    assert!(forth.eval("-9223372036854775808 1 -").is_ok());
+    assert_eq!(forth.stack(), [i64::MAX]);
+
+    let mut forth = Forth::new();
+    assert!(forth.eval("4294967296 4294967296 *").is_ok());
+    assert_eq!(forth.stack(), [0]);
+}
+
+#[test]
+fn overflow_modes() {
+    let checked = || {
+        ForthBuilder::new()
+            .overflow_mode(OverflowMode::Checked)
+            .build()
+    };
+    for code in [
+        "9223372036854775807 1 +",
+        "-9223372036854775808 1 -",
+        "4294967296 4294967296 *",
+        "-9223372036854775808 -1 /",
+        "-9223372036854775808 -1 /mod",
+        "9223372036854775807 2 1 */",
+        "variable v 9223372036854775807 v ! 1 v +!",
+    ] {
+        assert_eq!(
+            kind(checked().eval(code)),
+            Err(ErrorKind::Overflow),
+            "{}",
+            code
+        );
+    }
+    let mut forth = checked();
+    assert!(forth.eval("1 9223372036854775807").is_ok());
+    assert_eq!(kind(forth.eval("+")), Err(ErrorKind::Overflow));
+    assert_eq!(forth.stack(), [1, i64::MAX]);
+    assert!(forth.eval("-9223372036854775808 -1 mod").is_ok());
+    assert_eq!(forth.stack(), [1, i64::MAX, 0]);
+
+    let mut forth = Forth::new();
+    forth.set_overflow_mode(OverflowMode::Saturating);
+    let source = "9223372036854775807 1 + -9223372036854775808 1 - 4294967296 0 4294967296 - *\n\
+                  -9223372036854775808 -1 / 9223372036854775807 3 -2 */";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(
+        forth.stack(),
+        [i64::MAX, i64::MIN, i64::MIN, i64::MAX, i64::MIN]
+    );
+    assert_eq!(forth.overflow_mode(), OverflowMode::Saturating);
+}
+
+#[test]
+fn double_numbers() {
+    let mut forth = Forth::new();
+    let source = "123. -5. d+ 4611686018427387904 4 m* 3 s>d d- dnegate\n\
+                  -1 -1 um* hex $10. decimal";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [118, 0, 3, -1, 1, -2, 16, 0]);
+    assert!(forth.eval("2drop 2drop d. d.").is_ok());
+    assert_eq!(forth.output().text(), "-18446744073709551613 118 ");
+
+    // 2^64 divided by 3
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval("0 1 3 um/mod -1 0 -2 um/mod : d 10. ; d")
+        .is_ok());
+    assert_eq!(forth.stack(), [1, 6148914691236517205, 1, 1, 10, 0]);
+    assert_eq!(forth.user_definitions()[0].instructions[..], ["10."]);
+
+    for (code, error) in [
+        ("1 2 0 um/mod", ErrorKind::DivisionByZero),
+        ("1 2 3 d+", ErrorKind::StackUnderflow),
+        ("1 dnegate", ErrorKind::StackUnderflow),
+        ("$fg.", ErrorKind::InvalidNumber),
+        ("' 5.", ErrorKind::UnknownWord),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+    let mut forth = ForthBuilder::new()
+        .overflow_mode(OverflowMode::Checked)
+        .build();
+    assert_eq!(kind(forth.eval("0 1 1 um/mod")), Err(ErrorKind::Overflow));
+    assert_eq!(forth.stack(), [0, 1, 1]);
+}
+
+#[test]
+fn floating_point() {
+    let mut forth = Forth::new();
+    let source = "1.5e0 2E f* fdup f. 1e1 -2.5E-1 fswap f/ 3e fdrop 1e0 2e0 f< 2e 1e f<\n\
+                  : half 5e-1 f* ; 7e half";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.output().text(), "3 ");
+    assert_eq!(forth.float_stack(), [3.0, -0.025, 3.5]);
+    assert_eq!(forth.stack(), [-1, 0]);
+    assert!(forth.eval("fdrop fdrop fdrop 1e 0e f/ f. hex 1e0").is_ok());
+    assert_eq!(forth.output().text(), "3 inf ");
+    assert_eq!(forth.stack(), [-1, 0, 0x1e0]);
+
+    for (code, error) in [
+        ("f+", ErrorKind::StackUnderflow),
+        ("1e f-", ErrorKind::StackUnderflow),
+        ("fswap", ErrorKind::StackUnderflow),
+        ("f.", ErrorKind::StackUnderflow),
+        ("e5", ErrorKind::UnknownWord),
+        ("1e5x", ErrorKind::UnknownWord),
+        ("' 1e5", ErrorKind::UnknownWord),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+    let mut forth = ForthBuilder::new().stack_limit(1).build();
+    assert_eq!(kind(forth.eval("1e 2e")), Err(ErrorKind::StackOverflow));
+}
+
+#[test]
+fn unsigned_words() {
+    let mut forth = Forth::new();
+    let source = "-1 1 u< 1 -1 u< -1 1 u> 1 2 u> 5 5 u< -1 u. 255 u. hex -1 u.";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [0, -1, -1, 0, 0]);
+    assert_eq!(
+        forth.output().text(),
+        "18446744073709551615 255 FFFFFFFFFFFFFFFF "
+    );
+    assert_eq!(
+        kind(Forth::new().eval("1 u<")),
+        Err(ErrorKind::StackUnderflow)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("u.")),
+        Err(ErrorKind::StackUnderflow)
+    );
+}
+
+#[test]
+fn shifting_bits() {
+    let mut forth = Forth::new();
+    let source = "1 4 lshift 256 4 rshift -1 60 rshift 1 63 lshift 1 64 lshift -1 64 rshift\n\
+                  1 -1 lshift 3 2* -7 2/ 7 2/ -1 2/";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [16, 16, 15, i64::MIN, 0, 0, 0, 6, -4, 3, -1]);
+    assert_eq!(
+        kind(Forth::new().eval("1 lshift")),
+        Err(ErrorKind::StackUnderflow)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("2/")),
+        Err(ErrorKind::StackUnderflow)
+    );
+}
+
+#[test]
+fn clamping_words() {
+    let mut forth = Forth::new();
+    let source = "3 7 min 3 7 max -4 abs 4 abs 5 negate -9223372036854775808 abs\n\
+                  5 0 10 within 10 0 10 within -1 0 10 within 5 10 0 within 11 10 0 within";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [3, 7, 4, 4, -5, i64::MIN, -1, 0, 0, 0, -1]);
+
+    let mut forth = ForthBuilder::new()
+        .overflow_mode(OverflowMode::Checked)
+        .build();
+    assert_eq!(
+        kind(forth.eval("-9223372036854775808 negate")),
+        Err(ErrorKind::Overflow)
+    );
+    assert_eq!(forth.stack(), [i64::MIN]);
+    assert_eq!(
+        kind(Forth::new().eval("1 2 within")),
+        Err(ErrorKind::StackUnderflow)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("abs")),
+        Err(ErrorKind::StackUnderflow)
+    );
+}
+
+#[test]
+fn division_rounds_towards_zero() {
+    let mut forth = Forth::new();
+    assert!(forth.eval("-7 2 /mod 7 -2 mod -2147483648 -1 mod").is_ok());
+    assert_eq!(forth.stack(), [-1, -3, 1, 0]);
+    let mut forth = Forth::new();
+    assert!(forth.eval("100000 100000 1000 */ -7 1 2 */mod").is_ok());
+    assert_eq!(forth.stack(), [10_000_000, -1, -3]);
+    assert_eq!(
+        kind(Forth::new().eval("1 2 */")),
+        Err(ErrorKind::StackUnderflow)
+    );
+}
+
+#[test]
+fn floored_division() {
+    let mut forth = Forth::builder()
+        .division_mode(DivisionMode::Floored)
+        .build();
+    let source = "-7 2 /mod 7 -2 mod -7 -2 /mod 6 -3 /mod -7 2 / -7 1 2 */mod -7 1 2 */";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [1, -4, -1, -1, 3, 0, -2, -4, 1, -4, -4]);
+
+    // the quotient overflows like a symmetric one
+    let mut forth = Forth::builder()
+        .division_mode(DivisionMode::Floored)
+        .overflow_mode(OverflowMode::Checked)
+        .build();
+    assert_eq!(
+        kind(forth.eval("-9223372036854775808 -1 /")),
+        Err(ErrorKind::Overflow)
+    );
+    assert!(forth.eval("2drop -9223372036854775808 -1 mod").is_ok());
+    assert_eq!(forth.stack(), [0]);
+    forth.set_division_mode(DivisionMode::Symmetric);
+    assert!(forth.eval("-7 2 /").is_ok());
+    assert_eq!(forth.stack(), [0, -3]);
+}
+
+#[test]
+fn numeric_base() {
+    let mut forth = Forth::new();
+    let source = "hex ff -a : sixteen 10 ; decimal 10 sixteen\n\
+                  2 base ! 101 . decimal base @ 36 base ! zz";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [255, -10, 10, 16, 10, 1295]);
+    assert_eq!(forth.output().text(), "101 ");
+    assert_eq!(forth.format_number(-255), "-73");
+
+    let mut forth = Forth::new();
+    assert!(forth.eval("-1 0 -2147483648 hex . . . .s").is_ok());
+    assert_eq!(forth.output().text(), "-80000000 0 -1 <0> ");
+    assert_eq!(kind(forth.eval("g")), Err(ErrorKind::UnknownWord));
+    assert_eq!(forth.base(), 16);
+
+    // a base out of range reads and prints in decimal
+    let mut forth = Forth::new();
+    assert!(forth.eval("1 base ! 12 . 99 base ! 12 .").is_ok());
+    assert_eq!(forth.output().text(), "12 12 ");
+    assert_eq!(forth.base(), 10);
+
+    // the base outlives its cell being given back
+    let mut forth = Forth::new();
+    assert!(forth.eval("marker m hex base drop m 10").is_ok());
+    assert_eq!(forth.stack(), [16]);
+    forth.set_base(2);
+    assert_eq!(forth.format_number(5), "101");
+}
+
+#[test]
+fn prefixed_number_literals() {
+    let mut forth = Forth::new();
+    let source =
+        "hex $ff 0xFF #99 %1010 'A' decimal $-10 '€' ''' 0X7fffffffffffffff $FFFFFFFFFFFFFFFF\n\
+                  : bits %11 'a' ; bits ' dup drop";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(
+        forth.stack(),
+        [255, 255, 99, 10, 65, -16, 8364, 39, i64::MAX, -1, 3, 97]
+    );
+    assert_eq!(forth.user_definitions()[0].instructions[..], ["3", "97"]);
+
+    for (code, error) in [
+        ("$fg", ErrorKind::InvalidNumber),
+        ("%102", ErrorKind::InvalidNumber),
+        ("0x1ffffffffffffffff", ErrorKind::InvalidNumber),
+        ("#-", ErrorKind::InvalidNumber),
+        ("'ab'", ErrorKind::InvalidNumber),
+        ("' $10", ErrorKind::UnknownWord),
+        ("$", ErrorKind::UnknownWord),
+        ("0x", ErrorKind::UnknownWord),
+        ("''", ErrorKind::UnknownWord),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+}
+
+#[test]
+fn comparisons_leave_well_formed_flags() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval("1 2 < 2 1 < -5 0< 0 0= invert true false or 6 3 xor")
+        .is_ok());
+    assert_eq!(forth.stack(), [-1, 0, -1, 0, -1, 5]);
+    let mut forth = Forth::new();
+    assert!(forth.eval("-2147483648 2147483647 < 3 3 <>").is_ok());
+    assert_eq!(forth.stack(), [-1, 0]);
+}
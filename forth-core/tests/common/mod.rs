@@ -0,0 +1,6 @@
+use forth_core::{Error, ErrorKind};
+
+// What a result ended with, leaving out where an error happened
+pub fn kind<T>(result: Result<T, Error>) -> Result<T, ErrorKind> {
+    result.map_err(Error::into_kind)
+}
@@ -0,0 +1,106 @@
+// Conditionals and loops
+
+mod common;
+
+use common::kind;
+use forth_core::{ErrorKind, Forth};
+
+#[test]
+fn case_selection() {
+    for code in [
+        "1 case 1 of 2 endof",
+        "1 endcase",
+        "1 case 1 of 2 endcase",
+        "1 case endof endcase",
+        ": foo case 1 of 2 endcase ;",
+        ": foo case 1 of 2 of endof endof endcase ;",
+        ": foo of endof ;",
+        ": foo case if endcase then ;",
+    ] {
+        assert_eq!(
+            kind(Forth::new().eval(code)),
+            Err(ErrorKind::UnbalancedControlFlow),
+            "{}",
+            code
+        );
+    }
+    assert_eq!(
+        kind(Forth::new().eval("case endcase")),
+        Err(ErrorKind::StackUnderflow)
+    );
+
+    let mut forth = Forth::new();
+    let source = ": pick-one case 1 of 10 endof 2 of 20 endof dup 100 + swap endcase ;\n\
+                  : nested case 1 of 2 case 2 of 30 endof endcase endof endcase ;\n\
+                  1 pick-one 2 pick-one 3 pick-one 1 nested";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [10, 20, 103, 30]);
+}
+
+#[test]
+fn counted_loops() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval(": pairs 2 0 do 12 10 do j i loop loop ; pairs")
+        .is_ok());
+    assert_eq!(forth.stack(), [0, 10, 0, 11, 1, 10, 1, 11]);
+
+    // +LOOP stops once the index crosses the limit, in either direction
+    let mut forth = Forth::new();
+    assert!(forth.eval(": down 0 2 do i -1 +loop ; down").is_ok());
+    assert_eq!(forth.stack(), [2, 1, 0]);
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval(": up 2147483647 2147483646 do i loop ; up")
+        .is_ok());
+    assert_eq!(forth.stack(), [2147483646]);
+
+    for code in [
+        ": foo 3 0 do ;",
+        ": foo 3 0 do if loop then ;",
+        "i",
+        "1 loop",
+    ] {
+        assert_eq!(
+            kind(Forth::new().eval(code)),
+            Err(ErrorKind::UnbalancedControlFlow),
+            "{}",
+            code
+        );
+    }
+    // a failed loop must not leave its index behind
+    let mut forth = Forth::new();
+    assert_eq!(
+        kind(forth.eval("3 0 do drop loop")),
+        Err(ErrorKind::StackUnderflow)
+    );
+    assert_eq!(kind(forth.eval("i")), Err(ErrorKind::UnbalancedControlFlow));
+}
+
+#[test]
+fn loops_ending_on_a_flag() {
+    // each run of the loop allots a byte, counting the runs
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval(": runs begin 1 allot until here ; 1 0 0 runs")
+        .is_ok());
+    assert_eq!(forth.stack(), [3]);
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval(": runs begin while 1 allot repeat here ; 0 1 1 runs")
+        .is_ok());
+    assert_eq!(forth.stack(), [2]);
+
+    assert_eq!(
+        kind(Forth::new().eval("begin until")),
+        Err(ErrorKind::StackUnderflow)
+    );
+    for code in ["1 until", "begin 0 while again", "begin repeat", "1 while"] {
+        assert_eq!(
+            kind(Forth::new().eval(code)),
+            Err(ErrorKind::InvalidWord),
+            "{}",
+            code
+        );
+    }
+}
@@ -0,0 +1,449 @@
+// Defining words, finding them again and forgetting them
+
+mod common;
+
+use common::kind;
+use forth_core::{
+    CaseMode, Definition, DefinitionKind, ErrorKind, EvalEvent, Forth, ForthBuilder, Token,
+};
+
+#[test]
+fn case_modes() {
+    let names = |forth: &Forth| -> Vec<String> {
+        forth
+            .user_definitions()
+            .iter()
+            .map(|d| d.name.clone())
+            .collect()
+    };
+    let mut forth = Forth::new();
+    assert_eq!(forth.case_mode(), CaseMode::Insensitive);
+    assert!(forth.eval(": square dup * ; 3 SQUARE").is_ok());
+    assert_eq!(names(&forth), ["SQUARE"]);
+
+    let mut forth = Forth::builder().case_mode(CaseMode::Preserve).build();
+    assert!(forth.eval(": square dup * ; 3 SQUARE Square").is_ok());
+    assert_eq!(forth.stack(), [81]);
+    assert!(forth.eval("VARIABLE Total VOCABULARY Extras").is_ok());
+    assert_eq!(names(&forth), ["square", "Total", "Extras"]);
+
+    let mut forth = Forth::new();
+    forth.set_case_mode(CaseMode::Sensitive);
+    assert!(forth.eval(": sq dup * ; : SQ 1 + ; 3 sq SQ").is_ok());
+    assert_eq!(forth.stack(), [10]);
+    // built-in words are found whatever their case
+    assert!(forth.eval("drop 2 Dup").is_ok());
+    assert_eq!(kind(forth.eval("Sq")), Err(ErrorKind::UnknownWord));
+    assert!(forth.hide("sq"));
+    assert_eq!(kind(forth.eval("sq")), Err(ErrorKind::UnknownWord));
+    assert!(forth.eval("SQ").is_ok());
+    assert_eq!(forth.stack(), [2, 3]);
+}
+
+#[test]
+fn a_redefinition_calls_the_previous_definition() {
+    let mut forth = Forth::new();
+    assert!(forth.eval(": foo 1 ; : foo foo 1 + ; foo").is_ok());
+    assert_eq!(forth.stack(), [2]);
+}
+
+#[test]
+fn recursive_definitions() {
+    let mut forth = Forth::new();
+    let source = ": fact ( n -- n! ) dup 1 > if dup 1 - recurse * then ;\n\
+                  : down dup 0> if 1 - recurse then ;\n\
+                  5 fact 300 down";
+    assert_eq!(kind(forth.eval(source)), Err(ErrorKind::RecursionLimit));
+    assert_eq!(forth.stack()[0], 120);
+    // a failed recursion must not leave its depth behind
+    assert!(forth.eval("drop 10 down").is_ok());
+
+    let mut forth = ForthBuilder::new().recursion_limit(2000).build();
+    assert!(forth.eval(": down dup 0> if 1 - recurse then ;").is_ok());
+    assert_eq!(
+        kind(forth.eval("1000 down")),
+        Err(ErrorKind::ReturnStackOverflow)
+    );
+    let nested = ": deep dup 0> if 1 - 1 0 do 0 case 0 of recurse endof endcase loop then ;";
+    assert!(forth.eval(nested).is_ok());
+    assert_eq!(
+        kind(forth.eval("1000 deep")),
+        Err(ErrorKind::ReturnStackOverflow)
+    );
+
+    assert_eq!(
+        kind(Forth::new().eval("recurse")),
+        Err(ErrorKind::InvalidWord)
+    );
+}
+
+#[test]
+fn execution_tokens() {
+    let mut forth = Forth::new();
+    let source = ": square dup * ; ' square 3 over execute swap\n\
+                  ' dup ' dup = ' true execute\n\
+                  :noname ( n -- n' ) 1 + ; dup 5 swap execute\n\
+                  : apply ['] square execute ; 4 apply";
+    assert!(forth.eval(source).is_ok());
+    let stack = forth.stack().to_vec();
+    assert_eq!(stack[..4], [9, 1, -1, -1]);
+    assert_eq!(stack[5..], [6, 16]);
+
+    for (code, error) in [
+        ("' nothing", ErrorKind::UnknownWord),
+        ("' 5", ErrorKind::UnknownWord),
+        ("' if", ErrorKind::InvalidWord),
+        ("' char", ErrorKind::InvalidWord),
+        ("'", ErrorKind::InvalidWord),
+        ("0 execute", ErrorKind::InvalidWord),
+        ("-1 execute", ErrorKind::InvalidWord),
+        ("execute", ErrorKind::StackUnderflow),
+        (":noname 1", ErrorKind::InvalidWord),
+        (":noname 1 if ;", ErrorKind::UnbalancedControlFlow),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+}
+
+#[test]
+fn deferred_words() {
+    let mut forth = Forth::new();
+    let source = "defer draw : circle 1 ; : square 2 ; : scene draw draw ;\n\
+                  ' circle is draw scene ' square is draw scene";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [1, 1, 2, 2]);
+    let draw = forth.definitions.iter().find(|d| d.name == "DRAW").unwrap();
+    let xt = draw.instructions[0].parse().unwrap();
+    assert_eq!(forth.execution_token_name(xt), Some("SQUARE"));
+    assert!(forth
+        .eval(": set-draw ['] dup is draw ; set-draw 7 draw")
+        .is_ok());
+    assert_eq!(forth.stack(), [1, 1, 2, 2, 7, 7]);
+
+    for (code, error) in [
+        ("defer draw draw", ErrorKind::InvalidWord),
+        ("defer draw 0 is draw", ErrorKind::InvalidWord),
+        ("defer draw is draw", ErrorKind::StackUnderflow),
+        ("' dup is draw", ErrorKind::UnknownWord),
+        ("1 constant one ' dup is one", ErrorKind::InvalidWord),
+        ("defer is", ErrorKind::InvalidWord),
+        ("defer", ErrorKind::InvalidWord),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+}
+
+#[test]
+fn defining_words() {
+    let mut forth = Forth::new();
+    let source = ": array create cells allot does> swap cells + ;\n\
+                  3 array a 10 0 a ! 20 2 a ! 0 a @ 2 a @\n\
+                  create bytes 1 c, 2 c, bytes 1 + c@";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [10, 20, 2]);
+    assert!(forth
+        .eval(": point create , , ; 5 6 point p p @ p cell+ @")
+        .is_ok());
+    assert_eq!(forth.stack(), [10, 20, 2, 6, 5]);
+
+    for (code, error) in [
+        ("create", ErrorKind::InvalidWord),
+        ("create 5", ErrorKind::InvalidWord),
+        ("does>", ErrorKind::InvalidWord),
+        (": bad 1 does> 2 ; bad", ErrorKind::InvalidWord),
+        (": maker create ; maker", ErrorKind::InvalidWord),
+        ("c,", ErrorKind::StackUnderflow),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+}
+
+#[test]
+fn compile_state() {
+    let mut forth = Forth::new();
+    let source = ": squared postpone dup postpone * ; immediate\n\
+                  : cube dup squared * ; 3 cube\n\
+                  : answer [ 6 7 * ] literal ; answer\n\
+                  : compiling? state @ postpone literal ; immediate\n\
+                  : flag compiling? ; flag state @";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [27, 42, -1, 0]);
+    let cube = forth.definitions.iter().find(|d| d.name == "CUBE").unwrap();
+    assert_eq!(cube.instructions.join(" "), "dup dup * *");
+    assert!(forth
+        .eval(": ?neg postpone dup postpone 0< postpone if postpone 0 postpone swap postpone - postpone then ; immediate")
+        .is_ok());
+    assert!(forth.eval(": abs' ?neg ; -5 abs' 5 abs'").is_ok());
+    assert_eq!(forth.stack(), [27, 42, -1, 0, 5, 5]);
+
+    for (code, error) in [
+        ("1 literal", ErrorKind::InvalidWord),
+        ("[", ErrorKind::InvalidWord),
+        ("]", ErrorKind::InvalidWord),
+        ("immediate", ErrorKind::InvalidWord),
+        ("variable v immediate", ErrorKind::InvalidWord),
+        ("postpone dup", ErrorKind::InvalidWord),
+        (": f postpone ; ;", ErrorKind::InvalidWord),
+        (": f postpone nothing ;", ErrorKind::UnknownWord),
+        (": f [ 1 2 ;", ErrorKind::InvalidWord),
+        (": f literal ;", ErrorKind::StackUnderflow),
+        (
+            ": bad postpone if ; immediate : f bad ;",
+            ErrorKind::UnbalancedControlFlow,
+        ),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+}
+
+#[test]
+fn compiled_definitions_follow_changes() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval(": two 2 ; : four two two + ; four 5 value v : v+ v 1 + ; v+")
+        .is_ok());
+    assert_eq!(forth.stack(), [4, 6]);
+    assert!(forth.eval("7 to v v+").is_ok());
+    assert_eq!(forth.stack(), [4, 6, 8]);
+    assert!(forth.hide("two"));
+    assert_eq!(kind(forth.eval("four")), Err(ErrorKind::UnknownWord));
+    forth.reveal_definitions();
+    assert!(forth.eval(": two 3 ; four").is_ok());
+    assert_eq!(forth.stack(), [4, 6, 8, 4]);
+    assert!(forth.eval(": later missing ; : missing 1 ;").is_ok());
+    assert_eq!(kind(forth.eval("later")), Err(ErrorKind::UnknownWord));
+}
+
+#[test]
+fn dictionary_lookups() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval(": n 1 ; : m n ; : n 2 ; : dup 3 ; m N dup")
+        .is_ok());
+    assert_eq!(forth.stack(), [1, 2, 3]);
+    assert!(forth.hide("N"));
+    assert!(forth.eval("n").is_ok());
+    assert_eq!(forth.stack(), [1, 2, 3, 1]);
+    assert!(forth.hide("dup"));
+    assert!(forth.eval("dup").is_ok());
+    assert_eq!(forth.stack(), [1, 2, 3, 1, 1]);
+    // as a daemon's response is read, straight onto the definitions
+    forth.definitions.push(Definition {
+        name: String::from("PUSHED"),
+        instructions: ["1"].map(Token::from).into(),
+        kind: DefinitionKind::Colon,
+        stack_effect: None,
+        immediate: false,
+        wordlist: 0,
+        creates: false,
+    });
+    assert!(forth.eval("pushed : after pushed ; after").is_ok());
+    assert_eq!(forth.stack(), [1, 2, 3, 1, 1, 1, 1]);
+}
+
+#[test]
+fn forgetting_definitions() {
+    let mut forth = Forth::new();
+    let source = ": a 1 ; : b a a ; : a 2 ; : c 3 ; forget a a ' b";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [1, 1]);
+    let names: Vec<&str> = forth
+        .user_definitions()
+        .iter()
+        .map(|d| d.name.as_str())
+        .collect();
+    assert_eq!(names, ["A", "B"]);
+    assert_eq!(kind(forth.eval("c")), Err(ErrorKind::UnknownWord));
+    assert!(forth.eval("forget b").is_ok());
+    assert_eq!(kind(forth.eval("execute")), Err(ErrorKind::InvalidWord));
+
+    let mut forth = Forth::new();
+    let source = "variable v marker checkpoint variable w : d 4 ; s\" text\" 2drop here";
+    assert!(forth.eval(source).is_ok());
+    let events = forth.eval_with_events("checkpoint here").unwrap();
+    assert!(events.contains(&EvalEvent::Forgotten(String::from("D"))));
+    assert_eq!(forth.stack()[1], 8);
+    assert_eq!(forth.user_definitions().len(), 1);
+    assert!(forth.eval(": d 5 ; d s\" text\" type").is_ok());
+    assert_eq!(kind(forth.eval("checkpoint")), Err(ErrorKind::UnknownWord));
+
+    for (code, error) in [
+        ("forget", ErrorKind::InvalidWord),
+        ("forget dup", ErrorKind::UnknownWord),
+        (": g ; : f forget g recurse ; f", ErrorKind::InvalidWord),
+        ("marker", ErrorKind::InvalidWord),
+        ("marker forget", ErrorKind::InvalidWord),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+    let mut forth = Forth::new();
+    assert!(forth.eval(": a ; marker m").is_ok());
+    forth.freeze();
+    assert_eq!(
+        kind(forth.eval("forget a")),
+        Err(ErrorKind::DictionaryFrozen)
+    );
+    assert_eq!(kind(forth.eval("m")), Err(ErrorKind::DictionaryFrozen));
+}
+
+#[test]
+fn search_order() {
+    let mut forth = Forth::new();
+    let source = ": shape 1 ; vocabulary graphics also graphics definitions\n\
+                  : shape 2 ; : draw shape ; previous definitions\n\
+                  shape draw";
+    assert_eq!(kind(forth.eval(source)), Err(ErrorKind::UnknownWord));
+    assert_eq!(forth.stack(), [1]);
+    assert!(forth
+        .eval("also graphics draw shape previous shape")
+        .is_ok());
+    assert_eq!(forth.stack(), [1, 2, 2, 1]);
+    assert_eq!(forth.search_order(), [0]);
+    assert_eq!(forth.wordlist_name(forth.current_wordlist()), "FORTH");
+
+    let mut forth = Forth::new();
+    let source = "wordlist dup set-current : hidden 7 ; forth-wordlist set-current\n\
+                  get-order swap 1 + set-order hidden get-order get-current";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [7, 2, 1, 2, 1]);
+    assert!(forth
+        .eval("0 set-order dup 1 1 set-order only forth-wordlist")
+        .is_ok());
+    assert_eq!(forth.search_order(), [0]);
+
+    for (code, error) in [
+        ("0 set-order definitions", ErrorKind::InvalidWord),
+        ("0 set-order previous", ErrorKind::InvalidWord),
+        ("5 1 set-order", ErrorKind::InvalidWord),
+        ("17 set-order", ErrorKind::InvalidWord),
+        ("-2 set-order", ErrorKind::InvalidWord),
+        ("0 set-current", ErrorKind::InvalidWord),
+        ("2 set-order", ErrorKind::StackUnderflow),
+        ("vocabulary", ErrorKind::InvalidWord),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+}
+
+#[test]
+fn variables_constants_and_values() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval("1 allot variable x 42 constant answer 10 value speed")
+        .is_ok());
+    assert!(forth
+        .eval(": faster 20 to speed ; x answer speed faster speed here")
+        .is_ok());
+    assert_eq!(forth.stack(), [8, 42, 10, 20, 16]);
+
+    assert_eq!(kind(forth.eval("1 to answer")), Err(ErrorKind::InvalidWord));
+    assert_eq!(
+        kind(forth.eval("1 to nothing")),
+        Err(ErrorKind::UnknownWord)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("10 value speed to speed")),
+        Err(ErrorKind::StackUnderflow)
+    );
+    for code in [
+        "variable",
+        "constant",
+        "1 value 2",
+        "1 constant if",
+        "variable to",
+    ] {
+        assert_eq!(
+            kind(Forth::new().eval(code)),
+            Err(ErrorKind::InvalidWord),
+            "{}",
+            code
+        );
+    }
+    assert_eq!(
+        kind(Forth::new().eval("constant x")),
+        Err(ErrorKind::StackUnderflow)
+    );
+}
+
+#[test]
+fn comments() {
+    let mut forth = Forth::new();
+    let source = "1 ( a comment ) 2 \\ up to the end of the line 3\n\
+                  : add ( n1 n2 -- n3 ) + ; add\n\
+                  char ( .\" ( \\ \" ( unterminated";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [3, 40]);
+    assert_eq!(forth.output().text(), "( \\ ");
+}
+
+#[test]
+fn stack_effects_of_definitions() {
+    let mut forth = Forth::new();
+    let source = ": square (  n\t-- n*n ) dup * ;\n\
+                  : nothing ( ) ;\n\
+                  : twice 2 * ( n -- 2n ) ;";
+    assert!(forth.eval(source).is_ok());
+    let stack_effects: Vec<_> = forth
+        .user_definitions()
+        .iter()
+        .map(|d| d.stack_effect.as_deref())
+        .collect();
+    assert_eq!(stack_effects, [Some("( n -- n*n )"), Some("( )"), None]);
+    assert_eq!(
+        kind(Forth::new().eval(": open ( n -- n")),
+        Err(ErrorKind::InvalidWord)
+    );
+}
+
+#[test]
+fn prelude_words() {
+    let mut forth = Forth::new();
+    assert!(forth.user_definitions().is_empty());
+    let source = "variable v 1 1+ 1- 5 0<> v on v @ v off v @ \
+                  create pair 2 cells allot 3 4 pair 2! pair 2@ s\" hello\" 2 /string nip bl";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [1, -1, -1, 0, 3, 4, 3, 32]);
+    assert!(forth.eval("pair 2 cells erase pair ?").is_ok());
+    assert_eq!(forth.output().text(), "0 ");
+    let names: Vec<&str> = forth
+        .user_definitions()
+        .iter()
+        .map(|d| d.name.as_str())
+        .collect();
+    assert_eq!(names, ["V", "PAIR"]);
+
+    // the prelude's words work whatever the case mode, called by their names
+    for mode in [
+        CaseMode::Insensitive,
+        CaseMode::Preserve,
+        CaseMode::Sensitive,
+    ] {
+        let mut forth = Forth::builder().case_mode(mode).build();
+        let source = "VARIABLE V 1 1+ 1- 5 0<> V ON V @ V OFF V @ \
+                      CREATE PAIR 2 CELLS ALLOT 3 4 PAIR 2! PAIR 2@ S\" hello\" 2 /STRING NIP BL \
+                      PAIR 2 CELLS BLANK PAIR C@ PAIR 2 CELLS ERASE PAIR ?";
+        assert!(forth.eval(source).is_ok(), "{:?}", mode);
+        assert_eq!(forth.stack(), [1, -1, -1, 0, 3, 4, 3, 32, 32], "{:?}", mode);
+        assert_eq!(forth.output().text(), "0 ", "{:?}", mode);
+    }
+
+    // IMMEDIATE is for the code's own definitions
+    assert_eq!(
+        kind(Forth::new().eval("immediate")),
+        Err(ErrorKind::InvalidWord)
+    );
+    let mut forth = Forth::new();
+    assert!(forth.eval(": a ; forget 1- 1 1+").is_ok());
+    assert!(forth.user_definitions().is_empty());
+    assert_eq!(forth.stack(), [2]);
+    assert_eq!(kind(forth.eval("1-")), Err(ErrorKind::UnknownWord));
+
+    for mut forth in [Forth::new_bare(), Forth::builder().bare().build()] {
+        assert_eq!(kind(forth.eval("1+")), Err(ErrorKind::UnknownWord));
+        assert!(forth.definitions.is_empty());
+        assert!(forth.eval_prelude(": inc 1 + ; : twice inc inc ;").is_ok());
+        assert!(forth.eval(": four 2 twice ; four").is_ok());
+        assert_eq!(forth.stack(), [4]);
+        assert_eq!(forth.user_definitions().len(), 1);
+    }
+}
@@ -0,0 +1,215 @@
+// How errors are caught, located, explained and gone past
+
+mod common;
+
+use common::kind;
+use forth_core::{ErrorKind, Forth, ForthBuilder, Strictness};
+use std::time::Duration;
+
+#[test]
+fn catching_errors() {
+    let mut forth = Forth::new();
+    let source = ": pushes 1 2 3 -13 throw ; 6 2 ' / catch 6 0 ' / catch ' pushes catch";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [3, 0, 6, 0, -10, -13]);
+    // values taken before the error are put back as zeros
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval(": drops drop drop drop ; 1 2 ' drops catch")
+        .is_ok());
+    assert_eq!(forth.stack(), [0, 0, -4]);
+
+    let mut forth = Forth::new();
+    let source = ": floats 1e 2e 1 >r -1 throw ; : checked 0 abort\" fine\" 1 abort\" bad\" ;\n\
+                  ' floats catch ' checked catch 0 throw ' abort catch";
+    assert!(forth.eval(source).is_ok());
+    assert_eq!(forth.stack(), [-1, -2, -1]);
+    assert!(forth.float_stack().is_empty());
+    assert!(forth.return_stack().is_empty());
+
+    for (code, error) in [
+        ("-4 throw", ErrorKind::StackUnderflow),
+        ("-10 throw", ErrorKind::DivisionByZero),
+        ("42 throw", ErrorKind::Thrown(42)),
+        ("abort", ErrorKind::Thrown(-1)),
+        ("1 abort\" oops\"", ErrorKind::Aborted(String::from("oops"))),
+        ("throw", ErrorKind::StackUnderflow),
+        ("catch", ErrorKind::StackUnderflow),
+        ("' x catch", ErrorKind::UnknownWord),
+    ] {
+        assert_eq!(kind(Forth::new().eval(code)), Err(error), "{}", code);
+    }
+    assert_eq!(ErrorKind::Thrown(-1).throw_code(), Some(-1));
+    assert_eq!(ErrorKind::InvalidNumber.throw_code(), Some(-24));
+
+    let mut forth = ForthBuilder::new()
+        .timeout(Duration::from_millis(50))
+        .build();
+    assert_eq!(
+        kind(forth.eval(": spin begin again ; ' spin catch")),
+        Err(ErrorKind::Timeout)
+    );
+}
+
+#[test]
+fn errors_are_located() {
+    let located = |code: &str| {
+        let error = Forth::new().eval(code).unwrap_err();
+        let location = error.location().cloned().unwrap();
+        (
+            error.into_kind(),
+            location.word,
+            location.line,
+            location.column,
+        )
+    };
+    let underflow =
+        |word: &str, line, column| (ErrorKind::StackUnderflow, word.to_string(), line, column);
+    assert_eq!(located("1 2 +\n  drop drop drop"), underflow("drop", 2, 8));
+    assert_eq!(located("( a\n b ) \\ c\n\t.\" é\" +"), underflow("+", 3, 8));
+    assert_eq!(
+        located("1 if\n 0 0 / then"),
+        (ErrorKind::DivisionByZero, String::from("/"), 2, 6)
+    );
+    assert_eq!(located(": boom 0 0 / ;\n  boom").1, "boom");
+    assert_eq!(
+        located(": 1 2 ;"),
+        (ErrorKind::InvalidWord, String::from(":"), 1, 1)
+    );
+    assert_eq!(Forth::new().pop().unwrap_err().location(), None);
+}
+
+#[test]
+fn unknown_words_come_with_suggestions() {
+    let suggested = |code: &str| {
+        let error = Forth::new().eval(code).unwrap_err();
+        assert_eq!(error.kind(), &ErrorKind::UnknownWord, "{}", code);
+        error.suggestions().cloned().unwrap()
+    };
+    let suggestions = suggested("1 dupp");
+    assert_eq!(suggestions.word, "dupp");
+    assert_eq!(suggestions.words[0], "DUP");
+    assert!(suggestions.words.len() <= 3);
+    assert_eq!(suggested(": square dup * ; 3 squar").words[0], "SQUARE");
+    assert_eq!(suggested("' swapp").words[0], "SWAP");
+    assert!(suggested("xyzzy").words.is_empty());
+    // a body only sees the definitions made before it
+    let suggestions = suggested(": foo barr ; : bar 1 ; foo");
+    assert_eq!(suggestions.word, "barr");
+    assert!(!suggestions.words.contains(&String::from("BAR")));
+}
+
+#[test]
+fn errors_read_as_messages() {
+    let mut forth = Forth::new();
+    let error = forth.eval("DROP").unwrap_err();
+    assert_eq!(error.to_string(), "Stack underflow");
+    let error = forth.eval("1 0 /").unwrap_err();
+    assert_eq!(error.to_string(), "Cannot divide by 0");
+    let error = forth.eval("ABORT").unwrap_err();
+    assert_eq!(error.to_string(), "Aborted");
+    let error = forth.eval("-300 THROW").unwrap_err();
+    assert_eq!(error.to_string(), "THROW -300");
+    let error = forth.eval("-1 ABORT\" out of cheese\"").unwrap_err();
+    assert_eq!(error.to_string(), "out of cheese");
+    let error = forth.eval("1 DUPP").unwrap_err();
+    assert_eq!(
+        error.to_string(),
+        "Unknown word DUPP — did you mean DUP, DROP, 2DUP ?"
+    );
+    let error: Box<dyn std::error::Error> = Box::new(Forth::new().eval("SWAP").unwrap_err());
+    assert_eq!(error.to_string(), "Stack underflow");
+}
+
+#[test]
+fn eval_all_goes_on_after_errors() {
+    let mut forth = Forth::new();
+    let diagnostics = forth.eval_all("1 FOO 2\n: SQUARE DUP * ;\nBAR 3 SQUARE");
+    let found: Vec<_> = diagnostics
+        .iter()
+        .map(|d| (d.location.word.as_str(), d.location.line, d.location.column))
+        .collect();
+    assert_eq!(found, vec![("FOO", 1, 3), ("BAR", 3, 1)]);
+    assert_eq!(*diagnostics[0].error.kind(), ErrorKind::UnknownWord);
+    assert_eq!(forth.stack(), [1, 2, 9]);
+    assert!(Forth::new().eval_all("1 2 +").is_empty());
+    let diagnostics = Forth::new().eval_all("DROP 1 0 / 2DROP SWAP");
+    let kinds: Vec<_> = diagnostics.iter().map(|d| d.error.kind().clone()).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            ErrorKind::StackUnderflow,
+            ErrorKind::DivisionByZero,
+            ErrorKind::StackUnderflow
+        ]
+    );
+}
+
+#[test]
+fn tolerant_mode_skips_unknown_words() {
+    let mut forth = Forth::new();
+    assert_eq!(forth.strictness(), Strictness::Strict);
+    forth.set_strictness(Strictness::Tolerant);
+    assert!(forth
+        .eval("1 QUUXX 2\n: TWICE 2 * ZORKLE ;\n3 TWICE")
+        .is_ok());
+    assert_eq!(forth.stack(), [1, 2, 6]);
+    let skipped: Vec<_> = forth
+        .warnings()
+        .iter()
+        .map(|w| (w.to_string(), w.location().map(|l| (l.line, l.column))))
+        .collect();
+    assert_eq!(
+        skipped,
+        vec![
+            (String::from("Unknown word QUUXX"), Some((1, 3))),
+            (String::from("Unknown word ZORKLE"), Some((3, 3)))
+        ]
+    );
+    assert_eq!(forth.take_warnings().len(), 2);
+    assert!(forth.warnings().is_empty());
+    // other errors still fail
+    assert_eq!(
+        kind(forth.eval("DUPP 1 0 /")),
+        Err(ErrorKind::DivisionByZero)
+    );
+    assert_eq!(forth.warnings().len(), 1);
+    let mut forth = Forth::builder().strictness(Strictness::Tolerant).build();
+    assert!(forth.eval("BOGUS 1 IF 2 BOGUS THEN").is_ok());
+    let columns: Vec<_> = forth
+        .warnings()
+        .iter()
+        .map(|w| w.location().map(|l| l.column))
+        .collect();
+    assert_eq!(columns, vec![Some(1), Some(14)]);
+    assert_eq!(
+        kind(Forth::new().eval("BOGUS")),
+        Err(ErrorKind::UnknownWord)
+    );
+}
+
+#[test]
+fn stray_semicolons_and_nested_colons() {
+    let mut forth = Forth::new();
+    let error = forth.eval("1 2 ;").unwrap_err();
+    assert_eq!(*error.kind(), ErrorKind::UnexpectedSemicolon);
+    assert_eq!(error.location().map(|l| l.column), Some(5));
+    assert_eq!(forth.stack(), [1, 2]);
+    let error = forth.eval("1 IF ; THEN").unwrap_err();
+    assert_eq!(*error.kind(), ErrorKind::UnexpectedSemicolon);
+    assert_eq!(error.location().map(|l| l.column), Some(6));
+    let error = forth.eval(": A 1\n: B 2 ;").unwrap_err();
+    assert_eq!(*error.kind(), ErrorKind::NestedDefinition);
+    let location = error
+        .location()
+        .map(|l| (l.word.as_str(), l.line, l.column));
+    assert_eq!(location, Some((":", 2, 1)));
+    assert!(forth.user_definitions().is_empty());
+    // they are not unknown words, failing in tolerant mode too
+    forth.set_strictness(Strictness::Tolerant);
+    assert_eq!(kind(forth.eval(";")), Err(ErrorKind::UnexpectedSemicolon));
+    assert_eq!(
+        kind(forth.eval("-14 THROW")),
+        Err(ErrorKind::UnexpectedSemicolon)
+    );
+}
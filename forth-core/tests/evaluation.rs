@@ -0,0 +1,250 @@
+// Evaluating under limits, from cached states, a word at a time, and going back
+
+mod common;
+
+use common::kind;
+use forth_core::{ErrorKind, Forth, LineCache, StepCursor, CHECKPOINTS_SIZE};
+
+#[test]
+fn fuel_stops_runaway_programs() {
+    let mut forth = Forth::new();
+    assert_eq!(
+        kind(forth.eval_with_fuel("BEGIN AGAIN", 1000)),
+        Err(ErrorKind::FuelExhausted)
+    );
+    assert_eq!(
+        kind(forth.eval_with_fuel(": F RECURSE ; 0 IF F THEN 1 BEGIN 1 + AGAIN", 1000)),
+        Err(ErrorKind::FuelExhausted)
+    );
+    // the budget was for that evaluation only
+    assert_eq!(forth.fuel(), None);
+    assert!(forth.eval_with_fuel("1 2 + DROP", 4).is_ok());
+    assert_eq!(
+        kind(forth.eval_with_fuel("1 2 + DROP", 3)),
+        Err(ErrorKind::FuelExhausted)
+    );
+    // nor can it be caught
+    assert_eq!(
+        kind(forth.eval_with_fuel(": RUN BEGIN AGAIN ; ' RUN CATCH", 100)),
+        Err(ErrorKind::FuelExhausted)
+    );
+
+    let mut forth = Forth::builder().fuel(10).build();
+    assert!(forth.eval("1 2 3 4 5").is_ok());
+    assert_eq!(forth.fuel(), Some(5));
+    assert_eq!(
+        kind(forth.eval("10 0 DO LOOP")),
+        Err(ErrorKind::FuelExhausted)
+    );
+    assert_eq!(forth.fuel(), Some(0));
+}
+
+#[test]
+fn cached_evaluation_starts_from_the_edited_line() {
+    let code = ": SQUARE DUP * ;\n3 SQUARE\nFOO .\n: CUBE DUP SQUARE *\n  ;\n2 CUBE .";
+    let mut cache = LineCache::new();
+    let mut forth = Forth::new();
+    let diagnostics = forth.eval_all_cached(code, &mut cache);
+    assert_eq!(diagnostics, Forth::new().eval_all(code));
+    assert_eq!(cache.resumed_from(), 1);
+    assert_eq!(forth.output().text(), "9 8 ");
+
+    // editing the last line starts over from it, with what the lines before
+    // defined and printed
+    let edited = code.replace("2 CUBE .", "3 CUBE .");
+    let mut forth = Forth::new();
+    let diagnostics = forth.eval_all_cached(&edited, &mut cache);
+    assert_eq!(cache.resumed_from(), 6);
+    assert_eq!(diagnostics.len(), 1);
+    assert_eq!(diagnostics[0].location.line, 3);
+    assert_eq!(forth.output().text(), "9 27 ");
+    assert!(forth.stack().is_empty());
+
+    // a line in the middle of a definition isn't one to start from
+    let edited = edited.replace("  ;", "  1 + ;");
+    let mut forth = Forth::new();
+    forth.eval_all_cached(&edited, &mut cache);
+    assert_eq!(cache.resumed_from(), 4);
+    assert_eq!(forth.output().text(), "9 28 ");
+
+    // and a change to the first line starts from scratch
+    let edited = edited.replace("DUP *", "DUP DUP * *");
+    let mut forth = Forth::new();
+    let diagnostics = forth.eval_all_cached(&edited, &mut cache);
+    assert_eq!(cache.resumed_from(), 1);
+    assert_eq!(diagnostics, Forth::new().eval_all(&edited));
+    assert_eq!(forth.output().text(), "27 82 ");
+}
+
+#[test]
+fn cached_evaluation_keeps_its_states_small() {
+    // lines leaving data space as it was share it with the lines before
+    let mut code = String::from("50000000 ALLOT VARIABLE V");
+    for i in 0..40 {
+        code.push_str(&format!("\n{} DROP V @ DROP", i));
+    }
+    let mut cache = LineCache::new();
+    let mut forth = Forth::builder().data_limit(64 << 20).build();
+    assert!(forth.eval_all_cached(&code, &mut cache).is_empty());
+    assert!(cache.size() < 51_000_000, "{}", cache.size());
+    let edited = code.replace("39 DROP", "40 DROP");
+    assert!(Forth::builder()
+        .data_limit(64 << 20)
+        .build()
+        .eval_all_cached(&edited, &mut cache)
+        .is_empty());
+    assert_eq!(cache.resumed_from(), 41);
+
+    // lines writing to it can't all keep a copy
+    let code = code.replace(" DROP V @ DROP", " V !");
+    let mut cache = LineCache::new();
+    let mut forth = Forth::builder().data_limit(64 << 20).build();
+    assert!(forth.eval_all_cached(&code, &mut cache).is_empty());
+    assert!(cache.size() <= CHECKPOINTS_SIZE, "{}", cache.size());
+    let edited = code.replace("39 V !", "40 V ! V @");
+    let mut forth = Forth::builder().data_limit(64 << 20).build();
+    assert!(forth.eval_all_cached(&edited, &mut cache).is_empty());
+    assert!(cache.resumed_from() > 1);
+    assert_eq!(forth.stack(), [40]);
+}
+
+#[test]
+fn cached_evaluation_counts_steps_from_where_it_starts() {
+    let code = "1\n2\nBEGIN AGAIN";
+    let mut cache = LineCache::new();
+    let mut forth = Forth::builder().fuel(100).build();
+    let diagnostics = forth.eval_all_cached(code, &mut cache);
+    assert_eq!(diagnostics[0].error.kind(), &ErrorKind::FuelExhausted);
+
+    let mut forth = Forth::builder().fuel(100).build();
+    let diagnostics = forth.eval_all_cached("1\n2\n3", &mut cache);
+    assert!(diagnostics.is_empty());
+    assert_eq!(cache.resumed_from(), 3);
+    assert_eq!(forth.stack(), [1, 2, 3]);
+    assert_eq!(forth.fuel(), Some(97));
+}
+
+#[test]
+fn stepping_evaluates_like_eval() {
+    let source = ": MAKE CREATE , ;\n7 MAKE SEVEN ( n ) SEVEN @ .\" done\" CHAR A\nFOO";
+    let mut forth = Forth::new();
+    let mut cursor = StepCursor::new();
+    assert_eq!(cursor.next_word(source), Some((":", 1, 1)));
+    let mut steps = Vec::new();
+    while let Some((step, result)) = forth.eval_step(source, &mut cursor) {
+        steps.push(step);
+        if let Err(error) = result {
+            assert_eq!(error.location().map(|l| (l.line, l.column)), Some((3, 1)));
+        }
+    }
+    assert_eq!(
+        steps,
+        [
+            ": MAKE CREATE , ;",
+            "7",
+            "MAKE SEVEN",
+            "SEVEN",
+            "@",
+            ".\" done\"",
+            "CHAR A",
+            "FOO"
+        ]
+    );
+    assert_eq!(cursor.next_word(source), None);
+    let mut evaluated = Forth::new();
+    assert!(evaluated.eval(source).is_err());
+    assert_eq!(forth.stack(), evaluated.stack());
+    assert_eq!(forth.output().text(), "done");
+}
+
+#[test]
+fn steps_start_on_lines_outside_definitions_and_structures() {
+    let source = ": SQUARE\n  DUP * ;\n3 SQUARE .\" a\n b\"\n( skipped )\n\
+                  BEGIN\n  1 -\nDUP 0= UNTIL\nVARIABLE\nX";
+    assert_eq!(StepCursor::step_lines(source), [1, 3, 6, 9]);
+}
+
+#[test]
+fn restoring_a_snapshot_undoes_what_came_after() {
+    let mut forth = Forth::new();
+    forth
+        .eval(": SQUARE DUP * ;\nVARIABLE X 5 X !\n3 SQUARE . 1.5e0 7")
+        .unwrap();
+    let snapshot = forth.snapshot();
+    forth
+        .eval(": SQUARE 0 ;\n: CUBE DUP SQUARE * ;\n9 X ! 2 CUBE . F+ DROP 100 ALLOT")
+        .unwrap_err();
+    forth.eval("DROP FDROP").unwrap();
+
+    forth.restore(&snapshot);
+    assert_eq!(forth.stack(), [7]);
+    assert_eq!(snapshot.stack(), [7]);
+    assert_eq!(forth.output().text(), "9 ");
+    assert_eq!(snapshot.definitions().len(), forth.definitions.len());
+    assert_eq!(kind(forth.eval("CUBE")), Err(ErrorKind::UnknownWord));
+    forth.eval("X @ 4 SQUARE F.").unwrap();
+    assert_eq!(forth.stack(), [7, 5, 16]);
+    assert_eq!(forth.output().text(), "9 1.5 ");
+
+    // and a snapshot can be restored in another interpreter
+    let mut other = Forth::new();
+    other.restore(&snapshot);
+    other.eval("DROP 2 SQUARE .").unwrap();
+    assert_eq!(other.output().text(), "9 4 ");
+}
+
+#[test]
+fn atomic_evaluation_undoes_a_failing_line() {
+    let mut forth = Forth::new();
+    forth.eval_atomic(": SQUARE DUP * ; 3").unwrap();
+    let error = forth
+        .eval_atomic("SQUARE . : CUBE DUP SQUARE * ; 4 VARIABLE X SQAURE")
+        .unwrap_err();
+    assert_eq!(error.kind(), &ErrorKind::UnknownWord);
+    assert_eq!(forth.stack(), [3]);
+    assert_eq!(forth.output().text(), "");
+    assert_eq!(kind(forth.eval("CUBE")), Err(ErrorKind::UnknownWord));
+    assert_eq!(kind(forth.eval("X")), Err(ErrorKind::UnknownWord));
+
+    forth.eval_atomic("SQUARE .").unwrap();
+    assert_eq!(forth.output().text(), "9 ");
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn state_reads_back_from_json() {
+    let mut forth = Forth::builder().provenance(true).build();
+    forth
+        .eval(": SQUARE DUP * ;\nVARIABLE X 5 X !\n3 SQUARE . 2 3 + 1.5e0 S\" hi\" 2DROP")
+        .unwrap();
+    let json = serde_json::to_string(&forth).unwrap();
+    let mut read: Forth = serde_json::from_str(&json).unwrap();
+    assert_eq!(read.stack(), forth.stack());
+    assert_eq!(read.provenance(), forth.provenance());
+    assert_eq!(read.output().text(), "9 ");
+    read.eval("X @ 4 SQUARE F.").unwrap();
+    assert_eq!(read.stack(), [5, 5, 16]);
+    assert_eq!(read.output().text(), "9 1.5 ");
+
+    let definition: forth_core::Definition =
+        serde_json::from_str(&serde_json::to_string(&forth.user_definitions()[0]).unwrap())
+            .unwrap();
+    assert_eq!(definition.name, "SQUARE");
+    assert_eq!(&*definition.instructions, ["DUP", "*"]);
+
+    let json = json.replace("\"Result\":\"+\"", "\"Result\":\"NOT-A-WORD\"");
+    assert!(serde_json::from_str::<Forth>(&json).is_err());
+
+    // defining words still take a name from sessions saved without knowing it
+    let mut forth = Forth::new();
+    forth
+        .eval(": MAKE CREATE , ; : ARRAY DUP MAKE CELLS ALLOT ;")
+        .unwrap();
+    let json = serde_json::to_string(&forth)
+        .unwrap()
+        .replace(",\"creates\":true", "");
+    assert!(!json.contains("\"creates\":true"));
+    let mut read: Forth = serde_json::from_str(&json).unwrap();
+    read.eval("2 ARRAY PAIR PAIR @").unwrap();
+    assert_eq!(read.stack(), [2]);
+}
@@ -0,0 +1,94 @@
+// Including source files
+
+mod common;
+
+use common::kind;
+use forth_core::{DirectoryFiles, ErrorKind, Forth, ForthBuilder, ForthFiles};
+use std::collections::HashMap;
+use std::io;
+
+#[test]
+fn including_files() {
+    struct Files(HashMap<&'static str, &'static str>);
+    impl ForthFiles for Files {
+        fn read(&mut self, name: &str) -> io::Result<String> {
+            match self.0.get(name) {
+                Some(text) => Ok(text.to_string()),
+                None => Err(io::ErrorKind::NotFound.into()),
+            }
+        }
+    }
+    let files = || {
+        Box::new(Files(HashMap::from([
+            ("lib.fs", ": sq dup * ;"),
+            ("bad.fs", "1 2\n  foo"),
+            ("self.fs", "include self.fs"),
+            ("nested.fs", "1\ninclude bad.fs"),
+        ])))
+    };
+    let mut forth = ForthBuilder::new().files(files()).build();
+    assert!(forth
+        .eval("include lib.fs 3 sq s\" lib.fs\" included 2 sq : x include lib.fs ; x")
+        .is_ok());
+    assert_eq!(forth.stack(), [9, 4]);
+
+    let error = ForthBuilder::new()
+        .files(files())
+        .build()
+        .eval("\n include nested.fs")
+        .unwrap_err();
+    assert_eq!(error.location().map(|location| location.line), Some(2));
+    assert!(matches!(error.kind(), ErrorKind::Included(file, _) if file == "nested.fs"));
+    assert_eq!(
+        error.to_string(),
+        "nested.fs:2: bad.fs:2: Unknown word foo — did you mean ON, DO, LOOP ?"
+    );
+
+    for (source, file) in [
+        ("include missing.fs", "missing.fs"),
+        ("include self.fs", "self.fs"),
+    ] {
+        let error = ForthBuilder::new()
+            .files(files())
+            .build()
+            .eval(source)
+            .unwrap_err();
+        let file = file.to_string();
+        match error.into_kind() {
+            ErrorKind::CannotInclude(name, _) => assert_eq!(name, file),
+            ErrorKind::Included(name, error) => {
+                assert_eq!(name, file);
+                assert!(matches!(error.kind(), ErrorKind::CannotInclude(..)));
+            }
+            kind => panic!("{:?}", kind),
+        }
+    }
+    let mut forth = ForthBuilder::new().files(files()).build();
+    assert!(forth
+        .eval("s\" missing.fs\" ' included catch s\" bad.fs\" ' included catch")
+        .is_ok());
+    // the depth at CATCH being restored, with the name's address and length
+    assert_eq!(forth.stack().len(), 6);
+    assert_eq!((forth.stack()[2], forth.stack()[5]), (-38, -13));
+
+    // no files by default, and none out of a confined directory
+    assert!(matches!(
+        kind(Forth::new().eval("include lib.fs")),
+        Err(ErrorKind::CannotInclude(..))
+    ));
+    assert_eq!(
+        kind(Forth::new().eval("include")),
+        Err(ErrorKind::InvalidWord)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("0 -1 included")),
+        Err(ErrorKind::InvalidAddress)
+    );
+    let mut confined = DirectoryFiles::confined(".");
+    for name in ["../lib.fs", "/etc/passwd", "a/../../lib.fs"] {
+        assert_eq!(
+            confined.read(name).unwrap_err().kind(),
+            io::ErrorKind::PermissionDenied
+        );
+    }
+}
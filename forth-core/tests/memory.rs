@@ -0,0 +1,101 @@
+// Data space, and the strings kept in it
+
+mod common;
+
+use common::kind;
+use forth_core::{ErrorKind, Forth, ForthBuilder};
+
+#[test]
+fn reading_and_writing_data_space() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval("variable x 7 x ! 5 x +! x @ 300 x c! x c@ x @ here 1 , 2 , cell+ @")
+        .is_ok());
+    assert_eq!(forth.stack(), [12, 44, 44, 2]);
+
+    for code in [
+        "-1 @",
+        "here @",
+        "1 allot 0 @",
+        "1 here !",
+        "1 -4 +!",
+        "here c@",
+        "1 here c!",
+    ] {
+        assert_eq!(
+            kind(Forth::new().eval(code)),
+            Err(ErrorKind::InvalidAddress),
+            "{}",
+            code
+        );
+    }
+}
+
+#[test]
+fn counted_strings() {
+    assert_eq!(
+        kind(Forth::new().eval("c\" abc")),
+        Err(ErrorKind::InvalidWord)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("-1 count")),
+        Err(ErrorKind::InvalidAddress)
+    );
+    let long = format!("c\" {}\"", "x".repeat(256));
+    assert_eq!(kind(Forth::new().eval(&long)), Err(ErrorKind::InvalidWord));
+}
+
+#[test]
+fn strings_in_data_space() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval("s\" hello\" 2dup type here 5 allot swap move here 5 - 5 type")
+        .is_ok());
+    assert_eq!(forth.output().text(), "hellohello");
+    assert!(forth.eval("here 3 allot 3 2dup 42 fill type").is_ok());
+    assert_eq!(forth.output().text(), "hellohello***");
+    // overlapping ranges
+    assert!(forth
+        .eval("s\" abcd\" drop dup dup 1 + 3 move 4 type")
+        .is_ok());
+    assert_eq!(forth.output().text(), "hellohello***aabc");
+    assert_eq!(
+        kind(Forth::new().eval("0 -1 type")),
+        Err(ErrorKind::InvalidAddress)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("0 0 -1 move")),
+        Err(ErrorKind::InvalidAddress)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("-1 1 0 fill")),
+        Err(ErrorKind::InvalidAddress)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("s\" open")),
+        Err(ErrorKind::InvalidWord)
+    );
+}
+
+#[test]
+fn data_space_is_limited() {
+    let mut forth = Forth::new();
+    assert_eq!(
+        kind(forth.eval("2147483647 ALLOT")),
+        Err(ErrorKind::InvalidAddress)
+    );
+    assert!(forth.eval("here").is_ok());
+
+    let mut forth = ForthBuilder::new().data_limit(4096).build();
+    let here = forth.eval("here").map(|_| forth.stack()[0]).unwrap();
+    let room = 4096 - here;
+    assert!(forth.eval(&format!("{} allot", room)).is_ok());
+    for code in ["1 c,", "1 ,", "variable x", "s\" hi\" 2drop"] {
+        assert_eq!(
+            kind(forth.eval(code)),
+            Err(ErrorKind::InvalidAddress),
+            "{}",
+            code
+        );
+    }
+}
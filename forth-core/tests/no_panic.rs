@@ -0,0 +1,389 @@
+// Paths that used to panic, or could, must end in an error instead
+
+mod common;
+
+use common::kind;
+use forth_core::{CancelToken, ErrorKind, Forth, ForthBuilder, ReaderInput, TestFailure};
+use std::time::Duration;
+
+fn chain(depth: usize) -> String {
+    let mut source = String::from(": w0 1 ;\n");
+    for i in 1..=depth {
+        source.push_str(&format!(": w{} w{} ;\n", i, i - 1));
+    }
+    source.push_str(&format!("w{}", depth));
+    source
+}
+
+#[test]
+fn empty_definitions() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval(": NOP ;\n: BLANK\n\t\n;\n: QUIET ( -- ) ; IMMEDIATE")
+        .is_ok());
+    assert!(forth.eval("1 NOP BLANK QUIET 2 NOP").is_ok());
+    assert_eq!(forth.stack(), [1, 2]);
+    for name in ["NOP", "BLANK", "QUIET"] {
+        let index = forth
+            .definitions
+            .iter()
+            .rposition(|d| d.name == name)
+            .unwrap();
+        assert!(forth.definitions[index].instructions.is_empty());
+        assert_eq!(forth.compiled_definition(index), Ok(vec![]));
+    }
+    assert!(forth
+        .eval(": USES NOP QUIET ; USES ' NOP EXECUTE ' NOP CATCH")
+        .is_ok());
+    assert_eq!(forth.stack(), [1, 2, 0]);
+    assert!(forth.eval(":NONAME ; EXECUTE").is_ok());
+    assert_eq!(forth.stack(), [1, 2, 0]);
+}
+
+#[test]
+fn arithmetic_takes_two_operands() {
+    let mut forth = Forth::new();
+    assert!(forth.eval("1 2 3 + 10 4 - 6 *").is_ok());
+    assert_eq!(forth.stack(), [1, 5, 36]);
+    let mut forth = Forth::new();
+    assert_eq!(kind(forth.eval("7 +")), Err(ErrorKind::StackUnderflow));
+    assert_eq!(forth.stack(), [7]);
+}
+
+#[test]
+fn dividing_the_most_negative_value_by_minus_one() {
+    let mut forth = Forth::new();
+    assert!(forth.eval("-9223372036854775808 -1 /").is_ok());
+    assert_eq!(forth.stack(), [i64::MIN]);
+}
+
+#[test]
+fn dividing_by_zero() {
+    assert_eq!(
+        kind(Forth::new().eval("1 0 /")),
+        Err(ErrorKind::DivisionByZero)
+    );
+
+    // only the divisor matters
+    let mut forth = Forth::new();
+    assert!(forth.eval("0 5 2 / 0 3 /").is_ok());
+    assert_eq!(forth.stack(), [0, 2, 0]);
+    assert_eq!(kind(forth.eval("0 /")), Err(ErrorKind::DivisionByZero));
+    assert_eq!(forth.stack(), [0, 2, 0, 0]);
+    for word in ["mod", "/mod", "*/", "*/mod"] {
+        assert_eq!(
+            kind(forth.eval(word)),
+            Err(ErrorKind::DivisionByZero),
+            "{}",
+            word
+        );
+    }
+}
+
+#[test]
+fn a_first_definition_cannot_call_itself() {
+    assert_eq!(
+        kind(Forth::new().eval(": foo foo ; foo")),
+        Err(ErrorKind::UnknownWord)
+    );
+}
+
+#[test]
+fn deeply_nested_definitions() {
+    let mut forth = Forth::new();
+    assert_eq!(
+        kind(forth.eval(&chain(5000))),
+        Err(ErrorKind::ReturnStackOverflow)
+    );
+
+    // the failed call must not leave the call depth behind
+    assert!(forth.eval(&chain(500)).is_ok());
+    assert_eq!(forth.stack(), [1]);
+}
+
+#[test]
+fn built_in_words_on_an_empty_stack() {
+    for word in [
+        "dup", "drop", "swap", "over", "+", "-", "*", "/", "count", "at-xy", "=", "0=", "and",
+        "invert", ".", "emit", "spaces", "type", "move", "fill",
+    ] {
+        assert_eq!(
+            kind(Forth::new().eval(word)),
+            Err(ErrorKind::StackUnderflow),
+            "{}",
+            word
+        );
+    }
+    let mut forth = Forth::new();
+    assert_eq!(kind(forth.pop()), Err(ErrorKind::StackUnderflow));
+    assert_eq!(kind(forth.peek()), Err(ErrorKind::StackUnderflow));
+}
+
+#[test]
+fn unterminated_definitions() {
+    assert_eq!(kind(Forth::new().eval(":")), Err(ErrorKind::InvalidWord));
+    assert_eq!(
+        kind(Forth::new().eval(": foo")),
+        Err(ErrorKind::InvalidWord)
+    );
+    assert_eq!(
+        kind(Forth::new().eval(": foo 1")),
+        Err(ErrorKind::InvalidWord)
+    );
+    assert_eq!(
+        kind(Forth::new().eval(": 1 2 ;")),
+        Err(ErrorKind::InvalidWord)
+    );
+}
+
+#[test]
+fn compiling_a_missing_definition() {
+    assert!(matches!(
+        kind(Forth::new_bare().compiled_definition(3)),
+        Err(ErrorKind::UnknownWord)
+    ));
+}
+
+#[test]
+fn a_timeout_too_long_to_represent() {
+    let mut forth = ForthBuilder::new().timeout(Duration::MAX).build();
+    assert!(forth.eval("1 2 +").is_ok());
+}
+
+#[test]
+fn a_stack_limit_of_zero() {
+    let mut forth = ForthBuilder::new().stack_limit(0).build();
+    assert_eq!(kind(forth.eval("1")), Err(ErrorKind::StackOverflow));
+    assert_eq!(
+        kind(forth.replace_stack(vec![1])),
+        Err(ErrorKind::StackOverflow)
+    );
+}
+
+#[test]
+fn endless_loops() {
+    let mut forth = ForthBuilder::new()
+        .timeout(Duration::from_millis(50))
+        .build();
+    assert_eq!(kind(forth.eval("begin again")), Err(ErrorKind::Timeout));
+
+    let token = CancelToken::new();
+    let mut forth = ForthBuilder::new().cancel_token(token.clone()).build();
+    assert!(forth.eval(": forever begin 1 drop again ;").is_ok());
+    token.cancel();
+    assert_eq!(kind(forth.eval("forever")), Err(ErrorKind::Cancelled));
+}
+
+#[test]
+fn endless_loops_stopped_per_evaluation() {
+    let mut forth = Forth::new();
+    assert!(forth.eval(": forever begin 1 drop again ;").is_ok());
+    let token = CancelToken::new();
+    let canceller = token.clone();
+    let thread = std::thread::spawn(move || {
+        std::thread::sleep(Duration::from_millis(20));
+        canceller.cancel();
+    });
+    let result = forth.eval_cancellable("forever", &token);
+    assert_eq!(kind(result), Err(ErrorKind::Cancelled));
+    thread.join().unwrap();
+    // the token only applied to that evaluation
+    assert!(forth.eval("1 2 +").is_ok());
+
+    let timeout = Duration::from_millis(20);
+    let result = forth.eval_with_timeout("forever", timeout);
+    assert_eq!(kind(result), Err(ErrorKind::Timeout));
+    assert!(forth.eval_with_timeout("1 2 +", timeout).is_ok());
+    assert!(forth.eval_with_timeout("1 2 +", Duration::MAX).is_ok());
+}
+
+#[test]
+fn unbalanced_loops() {
+    assert_eq!(
+        kind(Forth::new().eval("begin 1")),
+        Err(ErrorKind::InvalidWord)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("1 again")),
+        Err(ErrorKind::InvalidWord)
+    );
+    assert_eq!(
+        kind(Forth::new().eval(": foo begin begin again ; foo")),
+        Err(ErrorKind::InvalidWord)
+    );
+}
+
+#[test]
+fn unbalanced_conditionals() {
+    for code in [
+        "1 if 2",
+        "1 then",
+        "0 else 1 then",
+        ": foo if 1 ;",
+        ": foo 1 then ;",
+        ": foo if 1 else 2 else 3 then ;",
+        ": foo if if then ;",
+    ] {
+        assert_eq!(
+            kind(Forth::new().eval(code)),
+            Err(ErrorKind::UnbalancedControlFlow),
+            "{}",
+            code
+        );
+    }
+    assert_eq!(
+        kind(Forth::new().eval("if 1 then")),
+        Err(ErrorKind::StackUnderflow)
+    );
+
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval(": sign dup if 0 swap if 1 else 2 then else drop 3 then ; 5 sign 0 sign")
+        .is_ok());
+    assert_eq!(forth.stack(), [0, 1, 3]);
+}
+
+#[test]
+fn stack_words_short_of_values() {
+    for (code, stack) in [
+        ("1 2 rot", &[1, 2][..]),
+        ("1 2 3 2swap", &[1, 2, 3]),
+        ("1 2over", &[1]),
+        ("1 2 pick", &[1, 2]),
+        ("1 -1 pick", &[1, -1]),
+        ("1 2 roll", &[1, 2]),
+    ] {
+        let mut forth = Forth::new();
+        assert_eq!(
+            kind(forth.eval(code)),
+            Err(ErrorKind::StackUnderflow),
+            "{}",
+            code
+        );
+        assert_eq!(forth.stack(), stack, "{}", code);
+    }
+
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval("1 2 3 rot -rot nip 4 tuck 0 ?dup 5 ?dup")
+        .is_ok());
+    assert_eq!(forth.stack(), [1, 4, 3, 4, 0, 5, 5]);
+    let mut forth = Forth::new();
+    assert!(forth.eval("10 20 30 40 2 pick 4 roll 0 roll").is_ok());
+    assert_eq!(forth.stack(), [20, 30, 40, 20, 10]);
+}
+
+#[test]
+fn return_stack_short_of_values() {
+    for word in ["r>", "r@", "2r>"] {
+        assert_eq!(
+            kind(Forth::new().eval(word)),
+            Err(ErrorKind::ReturnStackUnderflow),
+            "{}",
+            word
+        );
+    }
+    let mut forth = Forth::new();
+    assert_eq!(
+        kind(forth.eval("1 >r 2 2>r")),
+        Err(ErrorKind::StackUnderflow)
+    );
+    assert_eq!(forth.stack(), [2]);
+    assert_eq!(forth.return_stack(), [1]);
+    assert_eq!(
+        kind(forth.eval("2r>")),
+        Err(ErrorKind::ReturnStackUnderflow)
+    );
+    assert_eq!(forth.return_stack(), [1]);
+    assert!(forth.eval("3 2>r").is_ok());
+    assert_eq!(forth.return_stack(), [1, 2, 3]);
+    assert!(forth.eval("r> r@ 2r>").is_ok());
+    assert_eq!(forth.stack(), [3, 2, 1, 2]);
+    assert!(forth.return_stack().is_empty());
+}
+
+#[test]
+fn reading_past_the_end_of_input() {
+    let input = ReaderInput::new("ab\n".as_bytes());
+    let mut forth = ForthBuilder::new().input(Box::new(input)).build();
+    assert!(forth.eval("key key key key here 4 allot 4 accept").is_ok());
+    assert_eq!(forth.stack(), [97, 98, 10, -1, 0]);
+
+    let input = ReaderInput::new("abc\n".as_bytes());
+    let mut forth = ForthBuilder::new().input(Box::new(input)).build();
+    assert_eq!(
+        kind(forth.eval("here 2 accept")),
+        Err(ErrorKind::InvalidAddress)
+    );
+    assert_eq!(kind(forth.eval("-1 allot")), Err(ErrorKind::InvalidAddress));
+}
+
+#[test]
+fn output_past_its_limit() {
+    let mut forth = ForthBuilder::new().output_limit(4).build();
+    forth.output_mut().write("abc");
+    assert!(!forth.output().has_overflowed());
+    forth.output_mut().write("dé");
+    assert_eq!(forth.output().text(), "cdé");
+    assert_eq!(forth.output().dropped(), 2);
+
+    let mut forth = ForthBuilder::new().output_limit(0).build();
+    forth.output_mut().write("é");
+    assert_eq!(forth.output().text(), "");
+}
+
+#[test]
+fn characters_that_are_not_utf8() {
+    let mut forth = Forth::new();
+    // the second byte of é, on its own
+    assert!(forth
+        .eval("c\" é\" char+ char+ xc@+ -1 xemit 55296 xc-size")
+        .is_ok());
+    assert_eq!(forth.stack(), [3, 0xFFFD, 3]);
+    assert_eq!(forth.output().text(), "\u{FFFD}");
+    assert_eq!(
+        kind(Forth::new().eval("char é here xc!+")),
+        Err(ErrorKind::InvalidAddress)
+    );
+    assert_eq!(kind(Forth::new().eval("char")), Err(ErrorKind::InvalidWord));
+}
+
+#[test]
+fn failing_and_malformed_inline_tests() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval("1 : twice dup dup ; TEST: twice 2 twice -> 2 2 2 ;TEST")
+        .is_ok());
+    assert!(forth
+        .eval("TEST: wrong 2 twice -> 2 ;TEST test: broken drop -> ;test")
+        .is_ok());
+    assert_eq!(forth.stack(), [1]);
+    let outcomes = forth.run_tests();
+    assert_eq!(outcomes.len(), 3);
+    assert_eq!(outcomes[0].result, Ok(()));
+    assert_eq!(
+        outcomes[1].result,
+        Err(TestFailure::Mismatch {
+            actual: vec![2, 2, 2],
+            expected: vec![2]
+        })
+    );
+    assert_eq!(
+        outcomes[2].result,
+        Err(TestFailure::Error(ErrorKind::StackUnderflow.into()))
+    );
+    assert_eq!(forth.stack(), [1]);
+
+    assert_eq!(
+        kind(Forth::new().eval("TEST: no-arrow 1 ;TEST")),
+        Err(ErrorKind::InvalidWord)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("TEST: unfinished 1 -> 1")),
+        Err(ErrorKind::InvalidWord)
+    );
+    assert_eq!(
+        kind(Forth::new().eval("TEST:")),
+        Err(ErrorKind::InvalidWord)
+    );
+}
@@ -0,0 +1,102 @@
+// What programs print, where it goes, and where stack values come from
+
+mod common;
+
+use common::kind;
+use forth_core::{ErrorKind, EvalEvent, Forth, ForthBuilder, ForthOutput, WriterOutput};
+use std::sync::{Arc, Mutex};
+
+#[test]
+fn provenance_stays_in_step_with_the_stack() {
+    use forth_core::Provenance::{Host, Literal, Result};
+    let mut forth = ForthBuilder::new().provenance(true).build();
+    assert!(forth.eval(": two 2 ; 1 two over swap dup").is_ok());
+    assert_eq!(forth.stack(), [1, 1, 2, 2]);
+    assert_eq!(forth.provenance(), Some(&[Literal; 4][..]));
+
+    let mut forth = ForthBuilder::new().provenance(true).build();
+    assert!(forth.eval("3 4 + dup").is_ok());
+    assert_eq!(forth.provenance(), Some(&[Result("+"), Result("+")][..]));
+    assert_eq!(
+        kind(forth.eval("drop drop swap")),
+        Err(ErrorKind::StackUnderflow)
+    );
+    assert_eq!(forth.provenance(), Some(&[][..]));
+
+    assert!(forth.replace_stack(vec![1, 5]).is_ok());
+    assert!(forth.eval("drop 5").is_ok());
+    assert_eq!(forth.provenance(), Some(&[Host, Literal][..]));
+
+    let mut forth = ForthBuilder::new().provenance(true).build();
+    assert!(forth.eval("1 2 + 3 4 2 roll 1 pick rot").is_ok());
+    assert_eq!(
+        forth.provenance(),
+        Some(&[Literal, Result("+"), Literal, Literal][..])
+    );
+    assert_eq!(Forth::new().provenance(), None);
+}
+
+#[test]
+fn printing_words() {
+    let mut forth = Forth::new();
+    assert!(forth
+        .eval("1 2 .s -3 . cr 2 spaces 65 emit space -1 spaces .\" a \"b\"")
+        .is_ok());
+    assert_eq!(forth.output().text(), "<2> 1 2 -3 \n  A a \"b");
+    assert_eq!(forth.stack(), [1, 2]);
+    assert_eq!(
+        kind(Forth::new().eval(".\" open")),
+        Err(ErrorKind::InvalidWord)
+    );
+}
+
+#[test]
+fn printing_is_reported_as_events() {
+    let mut forth = Forth::new();
+    let events = forth.eval_with_events("65 emit .\" b\" 1 .").unwrap();
+    assert_eq!(
+        events,
+        [
+            EvalEvent::Pushed(65),
+            EvalEvent::Popped(65),
+            EvalEvent::Output(String::from("Ab")),
+            EvalEvent::Pushed(1),
+            EvalEvent::Popped(1),
+            EvalEvent::Output(String::from("1 ")),
+        ]
+    );
+    assert!(!forth
+        .eval_with_events("0 spaces")
+        .unwrap()
+        .iter()
+        .any(|event| matches!(event, EvalEvent::Output(_))));
+}
+
+#[test]
+fn printing_to_another_output() {
+    struct Shared(Arc<Mutex<String>>);
+    impl ForthOutput for Shared {
+        fn write_str(&mut self, text: &str) {
+            self.0.lock().unwrap().push_str(text);
+        }
+    }
+    let printed = Arc::new(Mutex::new(String::new()));
+    let mut forth = ForthBuilder::new()
+        .output(Box::new(Shared(printed.clone())))
+        .build();
+    assert!(forth.eval("1 . 233 emit .\" x\"").is_ok());
+    assert_eq!(*printed.lock().unwrap(), "1 éx");
+    assert_eq!(forth.output().text(), "");
+
+    let mut bytes = Vec::new();
+    WriterOutput::new(&mut bytes).emit_char('é');
+    assert_eq!(bytes, "é".as_bytes());
+}
+
+#[test]
+fn spaces_past_the_output_limit_are_dropped_without_being_made() {
+    let mut forth = ForthBuilder::new().output_limit(8).build();
+    assert!(forth.eval(".\" abc\" 1000000000000 spaces").is_ok());
+    assert_eq!(forth.output().text(), " ".repeat(8));
+    assert_eq!(forth.output().dropped(), 999_999_999_995);
+}
@@ -0,0 +1,27 @@
+use forth_tui::{Forth, ForthResult};
+
+// A one-line RPN calculator with its own interpreter,
+// so quick arithmetic never touches the main session's stack. A line with
+// an error is undone, for a typo not to leave half of it done.
+pub struct Calculator {
+    pub forth: Forth,
+    pub input: String,
+    pub status: ForthResult,
+}
+
+impl Default for Calculator {
+    fn default() -> Calculator {
+        Calculator {
+            forth: Forth::new(),
+            input: String::new(),
+            status: Ok(()),
+        }
+    }
+}
+
+impl Calculator {
+    pub fn submit(&mut self) {
+        self.status = self.forth.eval_atomic(&self.input);
+        self.input.clear();
+    }
+}
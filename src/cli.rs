@@ -0,0 +1,582 @@
+use crate::daemon::{self, Remote};
+use crate::dap;
+use crate::project::Project;
+use forth_tui::{
+    glossary, DirectoryFiles, Forth, ForthOutput, ForthResult, ReaderInput, TraceEvent, Tracer,
+    Value,
+};
+use serde::Serialize;
+use std::fs::{self, File};
+use std::io::{self, BufRead, BufReader, BufWriter, IsTerminal, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+const USAGE: &str = "Usage:
+    forth-tui [--readonly]          start the terminal app
+    forth-tui [--readonly] DIR      start the terminal app on the Forth sources of DIR
+    forth-tui [--readonly] FILE     start the terminal app on FILE, evaluated as it is edited
+                                    (--readonly: run and look at the code, without changing it)
+    forth-tui glossary FILE...      print a Markdown glossary of the definitions in FILE
+    forth-tui run [--exit-code | --watch] [--timeout SECONDS] [--trace FILE] [--seed N] FILE
+                                    evaluate FILE and print the stack
+    forth-tui check [--timeout SECONDS] [--seed N] [PATH...]
+                                    evaluate every source file, in the current directory by default
+    forth-tui test --snapshot [--update] [--timeout SECONDS] [--seed N] [PATH...]
+                                    compare results to the snapshots recorded on the first run
+    forth-tui --eval [--format text|json] [--timeout SECONDS] [--trace FILE] [--seed N] FILE | -
+                                    evaluate FILE, or stdin for -, printing what it prints then
+                                    the stack, or all that as a JSON object; the same as piping
+                                    code to forth-tui
+    forth-tui --repl [--timeout SECONDS] [--seed N]
+                                    evaluate lines read from stdin, printing ok or the error,
+                                    then the stack
+    forth-tui daemon [SOCKET]       keep a session running in the background
+    forth-tui daemon --stop [SOCKET]
+    forth-tui attach [SOCKET]       start the terminal app on the daemon's session
+    forth-tui dap                   run a debug adapter on stdin/stdout, for editors
+    forth-tui jupyter CONNECTION_FILE
+    forth-tui jupyter --install     register the Jupyter kernel (jupyter feature)";
+
+// Appended to a test file's name to name its snapshot
+const SNAPSHOT_EXTENSION: &str = ".snapshot";
+
+const WATCH_INTERVAL: Duration = Duration::from_millis(250);
+
+// Runs a command line subcommand instead of the terminal app
+pub fn run(command: &str, args: &[String]) -> io::Result<()> {
+    if let Err(err) = run_command(command, args) {
+        eprintln!("forth-tui: {}", err);
+        process::exit(1);
+    }
+    Ok(())
+}
+
+fn run_command(command: &str, args: &[String]) -> io::Result<()> {
+    match command {
+        "glossary" if !args.is_empty() => glossary(args),
+        "run" => run_file(args),
+        "--eval" => eval_batch(args),
+        "check" | "test" => test(args),
+        "--repl" => repl(args),
+        "daemon" => daemon(args),
+        "dap" => dap::run(),
+        #[cfg(feature = "jupyter")]
+        "jupyter" if !args.is_empty() => jupyter(&args[0]),
+        "-h" | "--help" | "help" => {
+            println!("{}", USAGE);
+            Ok(())
+        }
+        _ => usage(),
+    }
+}
+
+fn usage() -> io::Result<()> {
+    eprintln!("{}", USAGE);
+    process::exit(2)
+}
+
+fn glossary(files: &[String]) -> io::Result<()> {
+    for file in files {
+        let source = fs::read_to_string(file)?;
+        let title = Path::new(file)
+            .file_name()
+            .map_or(file.clone(), |name| name.to_string_lossy().into_owned());
+        println!("{}", glossary::markdown(&title, &source));
+    }
+    Ok(())
+}
+
+#[cfg(feature = "jupyter")]
+fn jupyter(arg: &str) -> io::Result<()> {
+    if arg == "--install" {
+        let dir = crate::jupyter::install()?;
+        println!("installed the kernel spec in {}", dir.display());
+        Ok(())
+    } else {
+        crate::jupyter::run(Path::new(arg))
+    }
+}
+
+fn daemon(args: &[String]) -> io::Result<()> {
+    let (option, socket) = match args.first().map(String::as_str) {
+        Some(option @ ("--foreground" | "--stop")) => (Some(option), args.get(1)),
+        _ => (None, args.first()),
+    };
+    let socket = socket.map_or_else(daemon::default_socket, PathBuf::from);
+    match option {
+        Some("--foreground") => daemon::serve(&socket),
+        Some(_) => Remote::new(socket).stop(),
+        None => {
+            daemon::spawn(&socket)?;
+            println!("Daemon listening on {}", socket.display());
+            Ok(())
+        }
+    }
+}
+
+// Options shared by the commands evaluating files
+#[derive(Default)]
+struct RunOptions {
+    timeout: Option<Duration>,
+    trace: Option<PathBuf>,
+    seed: Option<u64>,
+    // KEY and ACCEPT read stdin, rather than finding no input
+    input: bool,
+    // printed text goes to stdout as it is printed, rather than staying in
+    // the interpreter's output, whether it ended a line being kept here
+    output: Option<Arc<AtomicBool>>,
+}
+
+impl RunOptions {
+    // Takes the option starting at `arg`, returning false if it isn't one
+    fn parse<'a>(
+        &mut self,
+        arg: &str,
+        args: &mut impl Iterator<Item = &'a String>,
+    ) -> io::Result<bool> {
+        match arg {
+            "--timeout" => self.timeout = Some(parse_timeout(args.next())?),
+            "--trace" => match args.next() {
+                Some(file) => self.trace = Some(PathBuf::from(file)),
+                None => return Err(invalid_input("missing --trace file")),
+            },
+            "--seed" => match args.next().and_then(|seed| seed.parse().ok()) {
+                Some(seed) => self.seed = Some(seed),
+                None => return Err(invalid_input("invalid --seed number")),
+            },
+            _ => return Ok(false),
+        }
+        Ok(true)
+    }
+
+    // INCLUDE and INCLUDED read files from `dir`, the source's directory
+    fn eval(&self, source: &str, dir: &Path) -> io::Result<(Forth, ForthResult)> {
+        let mut builder = Forth::builder().files(Box::new(DirectoryFiles::new(dir)));
+        if let Some(timeout) = self.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(seed) = self.seed {
+            builder = builder.seed(seed);
+        }
+        if self.input {
+            builder = builder.input(Box::new(ReaderInput::new(BufReader::new(io::stdin()))));
+        }
+        if let Some(ends_line) = &self.output {
+            builder = builder.output(Box::new(LineOutput {
+                ends_line: ends_line.clone(),
+            }));
+        }
+        let trace = match &self.trace {
+            Some(file) => Some(Arc::new(Mutex::new(BufWriter::new(File::create(file)?)))),
+            None => None,
+        };
+        if let Some(writer) = &trace {
+            builder = builder.tracer(json_lines_tracer(writer.clone()));
+        }
+        let mut forth = builder.build();
+        let status = forth.eval(source);
+        if let Some(Ok(mut writer)) = trace.as_ref().map(|writer| writer.lock()) {
+            writer.flush()?;
+        }
+        if let (None, Some(seed)) = (self.seed, forth.seed()) {
+            // the seed picked from the clock, to run the program again the same way
+            eprintln!("seed: {}", seed);
+        }
+        Ok((forth, status))
+    }
+}
+
+// Evaluates a script without the terminal app, so it can be used from the shell
+fn run_file(args: &[String]) -> io::Result<()> {
+    let mut exit_code = false;
+    let mut watch = false;
+    let mut options = RunOptions::default();
+    let mut file = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--exit-code" => exit_code = true,
+            "--watch" => watch = true,
+            _ if options.parse(arg, &mut args)? => {}
+            _ if file.is_none() => file = Some(arg),
+            _ => return usage(),
+        }
+    }
+    let Some(file) = file else {
+        return usage();
+    };
+    if watch {
+        return watch_file(file, options);
+    }
+    options.input = true;
+    let ends_line = Arc::new(AtomicBool::new(true));
+    options.output = Some(ends_line.clone());
+
+    let source = fs::read_to_string(file)?;
+    let (forth, status) = options.eval(&source, source_dir(Path::new(file)))?;
+    // what the program printed is followed by a line break of its own
+    if !ends_line.load(Ordering::Relaxed) {
+        println!();
+    }
+    if status.is_err() {
+        eprintln!("{}: {}", file, crate::status_message(&status));
+        process::exit(1);
+    }
+    println!("{}", crate::stack_text(&forth));
+    if exit_code {
+        // exit codes are a byte wide: only the low 8 bits of the value are kept
+        process::exit(forth.peek().unwrap_or(0) as i32);
+    }
+    Ok(())
+}
+
+// Evaluates a file, or stdin for -, for shell pipelines and CI: what the
+// program prints goes to stdout, then the stack on a line of its own. An
+// error is printed to stderr after the stack, exiting with 1. With
+// `--format json`, a single JSON object holds all of it instead.
+fn eval_batch(args: &[String]) -> io::Result<()> {
+    let mut options = RunOptions::default();
+    let mut json = false;
+    let mut source = None;
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--format" => match args.next().map(String::as_str) {
+                Some("json") => json = true,
+                Some("text") => json = false,
+                _ => return Err(invalid_input("--format is text or json")),
+            },
+            _ if options.parse(arg, &mut args)? => {}
+            _ if source.is_none() => source = Some(arg),
+            _ => return usage(),
+        }
+    }
+    let Some(source) = source else {
+        return usage();
+    };
+    let (code, dir) = if source == "-" {
+        (io::read_to_string(io::stdin())?, Path::new(""))
+    } else {
+        (fs::read_to_string(source)?, source_dir(Path::new(source)))
+    };
+    // the JSON object holds the output, so it isn't printed as it goes
+    let ends_line = Arc::new(AtomicBool::new(true));
+    if !json {
+        options.output = Some(ends_line.clone());
+    }
+    let (forth, status) = options.eval(&code, dir)?;
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string(&BatchResult::new(&forth, &status))?
+        );
+    } else {
+        if !ends_line.load(Ordering::Relaxed) {
+            println!();
+        }
+        println!("{}", crate::stack_text(&forth));
+        if status.is_err() {
+            let name = if source == "-" {
+                "stdin"
+            } else {
+                source.as_str()
+            };
+            eprintln!("{}: {}", name, crate::status_message(&status));
+        }
+    }
+    if status.is_err() {
+        process::exit(1);
+    }
+    Ok(())
+}
+
+// The stack from the bottom, what was printed, the names the code defined in order
+// and the error message, or null
+#[derive(Serialize)]
+struct BatchResult<'a> {
+    stack: &'a [Value],
+    output: &'a str,
+    definitions: Vec<&'a str>,
+    error: Option<String>,
+}
+
+impl BatchResult<'_> {
+    fn new<'a>(forth: &'a Forth, status: &ForthResult) -> BatchResult<'a> {
+        BatchResult {
+            stack: forth.stack(),
+            output: forth.output().text(),
+            definitions: forth
+                .user_definitions()
+                .iter()
+                .map(|definition| definition.name.as_str())
+                .collect(),
+            error: status.as_ref().err().map(ToString::to_string),
+        }
+    }
+}
+
+// Where a source file is, the current directory for a bare file name
+fn source_dir(file: &Path) -> &Path {
+    file.parent().unwrap_or(Path::new(""))
+}
+
+// Runs the file again each time it is saved, until interrupted
+fn watch_file(file: &str, mut options: RunOptions) -> io::Result<()> {
+    let ends_line = Arc::new(AtomicBool::new(true));
+    options.output = Some(ends_line.clone());
+    let mut last_modified = None;
+    loop {
+        // editors saving through a temporary file make it briefly disappear
+        let modified = fs::metadata(file).and_then(|m| m.modified()).ok();
+        if modified.is_some() && modified != last_modified {
+            last_modified = modified;
+            println!("[Running {}]", file);
+            ends_line.store(true, Ordering::Relaxed);
+            match fs::read_to_string(file)
+                .and_then(|source| options.eval(&source, source_dir(Path::new(file))))
+            {
+                Ok((forth, status)) => {
+                    if !ends_line.load(Ordering::Relaxed) {
+                        println!();
+                    }
+                    if status.is_err() {
+                        println!("Error: {}", crate::status_message(&status));
+                    }
+                    println!("{}", crate::stack_text(&forth));
+                }
+                Err(err) => println!("{}", err),
+            }
+        }
+        thread::sleep(WATCH_INTERVAL);
+    }
+}
+
+// A read-eval-print loop on one interpreter, for a terminal too small for
+// the app, a connection over SSH or a script: each line read is evaluated,
+// undone if it fails like with eval_atomic, then ok or the error is printed
+// on a line, and the stack on the next. A prompt is shown on a terminal.
+// The timeout is for each line.
+fn repl(args: &[String]) -> io::Result<()> {
+    let mut timeout = None;
+    let mut builder = Forth::builder().files(Box::new(DirectoryFiles::new("")));
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--timeout" => timeout = Some(parse_timeout(args.next())?),
+            "--seed" => match args.next().and_then(|seed| seed.parse().ok()) {
+                Some(seed) => builder = builder.seed(seed),
+                None => return Err(invalid_input("invalid --seed number")),
+            },
+            _ => return usage(),
+        }
+    }
+    let ends_line = Arc::new(AtomicBool::new(true));
+    let mut forth = builder
+        .output(Box::new(LineOutput {
+            ends_line: ends_line.clone(),
+        }))
+        .build();
+    let prompt = io::stdin().is_terminal();
+    let mut lines = io::stdin().lock().lines();
+    loop {
+        if prompt {
+            print!("> ");
+            io::stdout().flush()?;
+        }
+        let Some(line) = lines.next().transpose()? else {
+            break;
+        };
+        ends_line.store(true, Ordering::Relaxed);
+        let snapshot = forth.snapshot();
+        let status = match timeout {
+            Some(timeout) => forth.eval_with_timeout(&line, timeout),
+            None => forth.eval(&line),
+        };
+        if status.is_err() {
+            forth.restore(&snapshot);
+        }
+        // what the line printed is followed by a line break of its own
+        if !ends_line.load(Ordering::Relaxed) {
+            println!();
+        }
+        match status {
+            Ok(()) => println!("ok"),
+            Err(_) => println!("Error: {}", crate::status_message(&status)),
+        }
+        println!("{}", crate::stack_text(&forth));
+    }
+    if prompt {
+        println!();
+    }
+    Ok(())
+}
+
+// Prints to stdout as the program prints, remembering whether the text
+// printed last ended a line
+struct LineOutput {
+    ends_line: Arc<AtomicBool>,
+}
+
+impl ForthOutput for LineOutput {
+    fn write_str(&mut self, text: &str) {
+        if text.is_empty() {
+            return;
+        }
+        let mut stdout = io::stdout();
+        let _ = stdout.write_all(text.as_bytes());
+        let _ = stdout.flush();
+        self.ends_line
+            .store(text.ends_with('\n'), Ordering::Relaxed);
+    }
+}
+
+#[derive(Serialize)]
+struct TraceLine<'a> {
+    word: &'a str,
+    depth: usize,
+    stack: &'a [Value],
+    elapsed_us: u64,
+}
+
+// Writes a JSON object per executed word, timed from the start of the run
+fn json_lines_tracer(writer: Arc<Mutex<BufWriter<File>>>) -> Tracer {
+    let start = Instant::now();
+    Box::new(move |event: &TraceEvent| {
+        let line = TraceLine {
+            word: event.word,
+            depth: event.depth,
+            stack: event.stack,
+            elapsed_us: start.elapsed().as_micros() as u64,
+        };
+        // a trace is best effort: a full disk must not change how the program runs
+        if let (Ok(mut writer), Ok(line)) = (writer.lock(), serde_json::to_string(&line)) {
+            let _ = writeln!(writer, "{}", line);
+        }
+    })
+}
+
+fn parse_timeout(seconds: Option<&String>) -> io::Result<Duration> {
+    seconds
+        .and_then(|seconds| seconds.parse::<f64>().ok())
+        .and_then(|seconds| Duration::try_from_secs_f64(seconds).ok())
+        .ok_or_else(|| invalid_input("invalid --timeout seconds"))
+}
+
+fn invalid_input(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, message)
+}
+
+// Each file is evaluated on its own, fresh interpreter. With --snapshot, its
+// results must also match the ones recorded in its snapshot file, which
+// --update rewrites.
+fn test(args: &[String]) -> io::Result<()> {
+    let mut snapshot = false;
+    let mut update = false;
+    let mut options = RunOptions::default();
+    let mut paths = Vec::new();
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--snapshot" => snapshot = true,
+            "--update" => update = true,
+            _ if options.parse(arg, &mut args)? => {}
+            _ => paths.push(arg.clone()),
+        }
+    }
+    if paths.is_empty() {
+        paths.push(String::from("."));
+    }
+    let mut failures = 0;
+    for file in source_files(&paths)? {
+        let source = fs::read_to_string(&file)?;
+        let (forth, status) = options.eval(&source, source_dir(&file))?;
+        let passed = if snapshot {
+            check_snapshot(&file, &snapshot_text(&forth, &status), update)?
+        } else if status.is_err() {
+            println!("{}: {}", file.display(), crate::status_message(&status));
+            false
+        } else {
+            println!("{}: ok", file.display());
+            true
+        };
+        if !passed {
+            failures += 1;
+        }
+    }
+    if failures > 0 {
+        eprintln!("{} file(s) failed", failures);
+        process::exit(1);
+    }
+    Ok(())
+}
+
+// What a snapshot records of a run
+fn snapshot_text(forth: &Forth, status: &ForthResult) -> String {
+    let status = match status {
+        Ok(()) => String::from("ok"),
+        Err(_) => crate::status_message(status),
+    };
+    let mut text = format!(
+        "status: {}\nstack: {}\noutput:\n",
+        status,
+        crate::stack_text(forth)
+    );
+    // the output follows on lines of its own, ended like the others
+    let output = forth.output().text();
+    text.push_str(output);
+    if !output.is_empty() && !output.ends_with('\n') {
+        text.push('\n');
+    }
+    text
+}
+
+fn check_snapshot(file: &Path, actual: &str, update: bool) -> io::Result<bool> {
+    let mut snapshot = file.as_os_str().to_owned();
+    snapshot.push(SNAPSHOT_EXTENSION);
+    let snapshot = PathBuf::from(snapshot);
+    let expected = match fs::read_to_string(&snapshot) {
+        Ok(expected) => Some(expected),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => None,
+        Err(err) => return Err(err),
+    };
+    match expected {
+        Some(expected) if expected == actual => println!("{}: ok", file.display()),
+        Some(expected) if !update => {
+            println!("{}: snapshot mismatch", file.display());
+            for line in expected.lines() {
+                println!("  - {}", line);
+            }
+            for line in actual.lines() {
+                println!("  + {}", line);
+            }
+            return Ok(false);
+        }
+        Some(_) => {
+            fs::write(&snapshot, actual)?;
+            println!("{}: snapshot updated", file.display());
+        }
+        None => {
+            fs::write(&snapshot, actual)?;
+            println!("{}: snapshot recorded", file.display());
+        }
+    }
+    Ok(true)
+}
+
+// Directories are expanded to the sources they contain, like a project
+fn source_files(paths: &[String]) -> io::Result<Vec<PathBuf>> {
+    let mut files = Vec::new();
+    for path in paths.iter().map(Path::new) {
+        if path.is_dir() {
+            let project = Project::open(path)?;
+            files.extend(project.files.iter().map(|file| project.root.join(file)));
+        } else {
+            files.push(path.to_path_buf());
+        }
+    }
+    Ok(files)
+}
@@ -0,0 +1,108 @@
+use std::collections::HashMap;
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+pub const PROJECT_CONFIG_FILE: &str = "forth-tui.conf";
+
+// Settings read from `key = value` lines, `#` starting a comment.
+// The user config lives in the config directory (~/.config/forth-tui/config),
+// and a project's forth-tui.conf overrides it.
+#[derive(Default)]
+pub struct Config {
+    values: HashMap<String, String>,
+}
+
+impl Config {
+    pub fn load(project_root: Option<&Path>) -> Config {
+        let mut config = Config::default();
+        if let Some(dir) = config_dir() {
+            config.merge_file(&dir.join("config"));
+        }
+        if let Some(root) = project_root {
+            config.merge_file(&root.join(PROJECT_CONFIG_FILE));
+        }
+        config
+    }
+
+    pub fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    pub fn get_bool(&self, key: &str) -> Option<bool> {
+        match self.get(key)? {
+            "true" | "yes" | "on" => Some(true),
+            "false" | "no" | "off" => Some(false),
+            _ => None,
+        }
+    }
+
+    // Settings whose key starts with `prefix`, by key without it
+    pub fn with_prefix<'a>(&'a self, prefix: &'a str) -> impl Iterator<Item = (&'a str, &'a str)> {
+        let mut values: Vec<(&str, &str)> = self
+            .values
+            .iter()
+            .filter_map(|(key, value)| Some((key.strip_prefix(prefix)?, value.as_str())))
+            .collect();
+        values.sort();
+        values.into_iter()
+    }
+
+    // A missing or unreadable file leaves the settings untouched
+    fn merge_file(&mut self, path: &Path) {
+        if let Ok(text) = fs::read_to_string(path) {
+            self.merge(&text);
+        }
+    }
+
+    fn merge(&mut self, text: &str) {
+        for line in text.lines() {
+            let line = line.split('#').next().unwrap_or("").trim();
+            if let Some((key, value)) = line.split_once('=') {
+                self.values
+                    .insert(key.trim().to_string(), value.trim().to_string());
+            }
+        }
+    }
+}
+
+pub fn config_dir() -> Option<PathBuf> {
+    match env::var_os("XDG_CONFIG_HOME") {
+        Some(dir) if !dir.is_empty() => Some(PathBuf::from(dir).join("forth-tui")),
+        _ => env::var_os("HOME").map(|home| PathBuf::from(home).join(".config/forth-tui")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn later_settings_override_earlier_ones() {
+        let mut config = Config::default();
+        config.merge("theme = dark\n# autosave = no\nautosave = yes # always\nbad line");
+        config.merge("theme=light");
+        assert_eq!(config.get("theme"), Some("light"));
+        assert_eq!(config.get_bool("autosave"), Some(true));
+        assert_eq!(config.get_bool("theme"), None);
+        assert_eq!(config.get("bad line"), None);
+    }
+
+    #[test]
+    fn settings_are_listed_by_prefix() {
+        let mut config = Config::default();
+        config.merge("snippet.b = 2\nsnippet.a = 1\ntheme = dark");
+        let snippets: Vec<_> = config.with_prefix("snippet.").collect();
+        assert_eq!(snippets, [("a", "1"), ("b", "2")]);
+    }
+
+    #[test]
+    fn the_project_config_is_read_from_its_root() {
+        let root = env::temp_dir().join(format!("forth-tui-config-{}", std::process::id()));
+        fs::create_dir_all(&root).unwrap();
+        fs::write(root.join(PROJECT_CONFIG_FILE), "fuel = 10").unwrap();
+        let config = Config::load(Some(&root));
+        fs::remove_dir_all(&root).unwrap();
+        assert_eq!(config.get("fuel"), Some("10"));
+    }
+}
@@ -0,0 +1,387 @@
+use crate::evaluator::{Evaluated, Request, Worker};
+use forth_tui::CancelToken;
+use serde::{Deserialize, Serialize};
+use std::env;
+use std::fs;
+use std::io::{self, Read, Write};
+use std::net::Shutdown;
+use std::os::unix::fs::{DirBuilderExt, MetadataExt, PermissionsExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::os::unix::process::CommandExt;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+
+// `forth-tui daemon` keeps a session alive in the background: the editor buffer
+// and the interpreter evaluating it. `forth-tui attach` runs the terminal app
+// against that session, so quitting (or losing) the terminal does not lose it.
+//
+// Each request is a single connection: the client writes the request, shuts down
+// its write half, and reads the response until the daemon closes the connection.
+// Requests and responses are serialized: an evaluation is sent as the app's
+// evaluation request, with its preludes and settings, and answered with the
+// whole state the evaluation left, snapshot and diagnostics included.
+//
+// Evaluations run on a thread of their own, with the fuel the request gives
+// them, so that a request to cancel the running one, or to stop the daemon,
+// is answered while it runs. Each connection is read on a thread of its own
+// too, and given up on after a while, so a client that never finishes its
+// request holds up no one else.
+//
+// The session's code goes only to its user: the socket is in the user's
+// runtime directory, or else in a directory of theirs only they can enter,
+// it can only be opened by them, and an app connects only to a socket the
+// user owns.
+
+pub fn default_socket() -> PathBuf {
+    match env::var_os("XDG_RUNTIME_DIR") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir).join("forth-tui.sock"),
+        _ => {
+            let user = env::var("USER").unwrap_or_else(|_| String::from("default"));
+            env::temp_dir()
+                .join(format!("forth-tui-{}", user))
+                .join("daemon.sock")
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+enum DaemonRequest {
+    Get,
+    Eval(Request),
+    // fails the running evaluation with ErrorKind::Cancelled
+    Cancel,
+    Stop,
+}
+
+// Session state as seen by an attached app: the editor buffer, and what its
+// latest evaluation left, if there was one
+#[derive(Default, Serialize, Deserialize)]
+pub struct RemoteState {
+    pub buffer: String,
+    pub evaluated: Option<Evaluated>,
+}
+
+#[derive(Clone)]
+pub struct Remote {
+    socket: PathBuf,
+}
+
+impl Remote {
+    pub fn new(socket: PathBuf) -> Remote {
+        Remote { socket }
+    }
+
+    pub fn fetch(&self) -> io::Result<RemoteState> {
+        parse_state(&self.request(&DaemonRequest::Get, None)?)
+    }
+
+    // Has the daemon evaluate the request, asking it to cancel the
+    // evaluation once the token is cancelled
+    pub fn eval(&self, request: &Request, cancel_token: &CancelToken) -> io::Result<Evaluated> {
+        let mut request = request.clone();
+        // the daemon may have been started from another directory
+        if let Ok(root) = fs::canonicalize(&request.root) {
+            request.root = root;
+        }
+        let (overflow_mode, strictness, case_mode) =
+            (request.overflow_mode, request.strictness, request.case_mode);
+        let response = self.request(&DaemonRequest::Eval(request), Some(cancel_token))?;
+        let mut evaluated = parse_state(&response)?
+            .evaluated
+            .ok_or_else(invalid_response)?;
+        // settings are the app's, not part of the state sent back
+        evaluated.forth.set_overflow_mode(overflow_mode);
+        evaluated.forth.set_strictness(strictness);
+        evaluated.forth.set_case_mode(case_mode);
+        Ok(evaluated)
+    }
+
+    pub fn cancel(&self) -> io::Result<()> {
+        self.request(&DaemonRequest::Cancel, None).map(|_| ())
+    }
+
+    pub fn stop(&self) -> io::Result<()> {
+        self.request(&DaemonRequest::Stop, None).map(|_| ())
+    }
+
+    // Sends the request and waits for the response, asking the daemon to
+    // cancel its evaluation once the token, if any, is cancelled
+    fn request(
+        &self,
+        request: &DaemonRequest,
+        cancel_token: Option<&CancelToken>,
+    ) -> io::Result<Vec<u8>> {
+        let mut stream = connect(&self.socket)?;
+        stream.write_all(&serde_json::to_vec(request)?)?;
+        stream.shutdown(Shutdown::Write)?;
+        if cancel_token.is_some() {
+            stream.set_read_timeout(Some(CANCEL_POLL))?;
+        }
+        let mut response = Vec::new();
+        let mut cancelled = false;
+        loop {
+            match stream.read_to_end(&mut response) {
+                Ok(_) => return Ok(response),
+                Err(err)
+                    if matches!(
+                        err.kind(),
+                        io::ErrorKind::WouldBlock | io::ErrorKind::TimedOut
+                    ) =>
+                {
+                    if !cancelled && cancel_token.is_some_and(CancelToken::is_cancelled) {
+                        cancelled = true;
+                        self.cancel()?;
+                    }
+                }
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+                Err(err) => return Err(err),
+            }
+        }
+    }
+}
+
+// How often an app waiting for an evaluation checks whether to cancel it
+const CANCEL_POLL: Duration = Duration::from_millis(50);
+
+// How long the daemon waits for a client to finish sending its request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(5);
+
+// Starts the daemon as a background process, in its own process group so that
+// closing the terminal doesn't take it down
+pub fn spawn(socket: &Path) -> io::Result<()> {
+    if connect(socket).is_ok() {
+        return Err(already_running(socket));
+    }
+    Command::new(env::current_exe()?)
+        .arg("daemon")
+        .arg("--foreground")
+        .arg(socket)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .process_group(0)
+        .spawn()?;
+    Ok(())
+}
+
+pub fn serve(socket: &Path) -> io::Result<()> {
+    if let Some(dir) = socket.parent().filter(|dir| !dir.exists()) {
+        fs::DirBuilder::new().mode(0o700).create(dir)?;
+    }
+    if socket.exists() {
+        if connect(socket).is_ok() {
+            return Err(already_running(socket));
+        }
+        // left behind by a daemon that didn't stop cleanly
+        fs::remove_file(socket)?;
+    }
+
+    let listener = UnixListener::bind(socket)?;
+    fs::set_permissions(socket, fs::Permissions::from_mode(0o600))?;
+    let session = Arc::new(Mutex::new(RemoteState::default()));
+    let cancel_token = CancelToken::new();
+    let daemon = Daemon {
+        evaluations: spawn_worker(Arc::clone(&session), cancel_token.clone()),
+        session,
+        cancel_token,
+        stopping: Arc::new(AtomicBool::new(false)),
+        socket: socket.to_path_buf(),
+    };
+    for stream in listener.incoming() {
+        if daemon.stopping.load(Ordering::SeqCst) {
+            break;
+        }
+        // a connection that failed is the client's problem, not the daemon's
+        let Ok(stream) = stream else {
+            continue;
+        };
+        let daemon = daemon.clone();
+        thread::spawn(move || daemon.answer(stream));
+    }
+    fs::remove_file(socket)
+}
+
+// What the threads answering connections share
+#[derive(Clone)]
+struct Daemon {
+    session: Arc<Mutex<RemoteState>>,
+    cancel_token: CancelToken,
+    evaluations: mpsc::Sender<(UnixStream, Request)>,
+    stopping: Arc<AtomicBool>,
+    socket: PathBuf,
+}
+
+impl Daemon {
+    fn answer(&self, mut stream: UnixStream) {
+        let mut request = Vec::new();
+        let read = stream
+            .set_read_timeout(Some(REQUEST_TIMEOUT))
+            .and_then(|_| stream.read_to_end(&mut request));
+        if read.is_err() {
+            return;
+        }
+        match serde_json::from_slice(&request) {
+            Ok(DaemonRequest::Stop) => {
+                self.cancel_token.cancel();
+                self.stopping.store(true, Ordering::SeqCst);
+                drop(stream);
+                // wakes the daemon up, waiting for the next connection
+                let _ = UnixStream::connect(&self.socket);
+                return;
+            }
+            Ok(DaemonRequest::Cancel) => self.cancel_token.cancel(),
+            Ok(DaemonRequest::Eval(request)) => {
+                // answered by the worker, once evaluated
+                let _ = self.evaluations.send((stream, request));
+                return;
+            }
+            Ok(DaemonRequest::Get) | Err(_) => {}
+        }
+        let response = format_state(&self.session.lock().unwrap());
+        // the client may be gone already, which must not stop the daemon
+        let _ = stream.write_all(&response);
+    }
+}
+
+// Evaluates the requests sent, in turn, answering each on its connection
+// with the session's state once evaluated
+fn spawn_worker(
+    session: Arc<Mutex<RemoteState>>,
+    cancel_token: CancelToken,
+) -> mpsc::Sender<(UnixStream, Request)> {
+    let (evaluations, requests) = mpsc::channel::<(UnixStream, Request)>();
+    thread::spawn(move || {
+        let mut worker = Worker::default();
+        for (mut stream, request) in requests {
+            cancel_token.reset();
+            let buffer = request.buffer.clone();
+            let evaluated = worker.evaluate(request, cancel_token.clone());
+            let mut session = session.lock().unwrap();
+            session.buffer = buffer;
+            session.evaluated = Some(evaluated);
+            let _ = stream.write_all(&format_state(&session));
+        }
+    });
+    evaluations
+}
+
+// Connects to a socket only if it's the user's own, as anyone can make one
+// where the daemon's would be
+fn connect(socket: &Path) -> io::Result<UnixStream> {
+    // SAFETY: geteuid has no preconditions and cannot fail
+    let user = unsafe { libc::geteuid() };
+    if fs::symlink_metadata(socket)?.uid() != user {
+        return Err(io::Error::new(
+            io::ErrorKind::PermissionDenied,
+            format!("{} belongs to another user", socket.display()),
+        ));
+    }
+    UnixStream::connect(socket)
+}
+
+fn already_running(socket: &Path) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::AddrInUse,
+        format!("a daemon is already listening on {}", socket.display()),
+    )
+}
+
+fn invalid_response() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "invalid daemon response")
+}
+
+fn format_state(state: &RemoteState) -> Vec<u8> {
+    serde_json::to_vec(state).unwrap_or_default()
+}
+
+fn parse_state(response: &[u8]) -> io::Result<RemoteState> {
+    serde_json::from_slice(response).map_err(|_| invalid_response())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use forth_tui::{CaseMode, Error, ErrorKind, Forth, OverflowMode, Strictness};
+
+    fn request(code: &str) -> Request {
+        Request {
+            user_prelude: None,
+            dictionary: String::new(),
+            prelude: String::new(),
+            code: code.to_string(),
+            provenance: false,
+            overflow_mode: OverflowMode::Checked,
+            strictness: Strictness::default(),
+            case_mode: CaseMode::Sensitive,
+            fuel: u64::MAX,
+            root: PathBuf::from("."),
+            include_anywhere: false,
+            buffer: code.to_string(),
+        }
+    }
+
+    #[test]
+    fn errors_of_included_files_reach_the_app() {
+        let cannot = ErrorKind::CannotInclude(String::from("a b.fth"), String::from("not found"));
+        let error = Error::new(
+            ErrorKind::Included(String::from("lib.fth"), Box::new(cannot.into())),
+            None,
+        );
+        let state = RemoteState {
+            buffer: String::from("1 2\nINCLUDE lib.fth"),
+            evaluated: Some(Evaluated {
+                forth: Forth::new(),
+                prelude_status: Err(error.clone()),
+                saved_from: 0,
+                diagnostics: Vec::new(),
+            }),
+        };
+        let state = parse_state(&format_state(&state)).unwrap();
+        assert_eq!(state.buffer, "1 2\nINCLUDE lib.fth");
+        assert_eq!(state.evaluated.unwrap().prelude_status, Err(error));
+    }
+
+    #[test]
+    fn evaluations_keep_their_state_and_can_be_cancelled() {
+        let socket = env::temp_dir().join(format!("forth-tui-test-{}.sock", std::process::id()));
+        let served = socket.clone();
+        let daemon = thread::spawn(move || serve(&served));
+        let remote = Remote::new(socket.clone());
+        while remote.fetch().is_err() {
+            thread::sleep(Duration::from_millis(10));
+        }
+        let mode = fs::metadata(&socket).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+        // a client that never finishes its request holds up no one else
+        let _idle = UnixStream::connect(&socket).unwrap();
+
+        let cancel_token = CancelToken::new();
+        let code = "1 2 >R 3 .\" hi\" 16 BASE !";
+        let evaluated = remote.eval(&request(code), &cancel_token).unwrap();
+        let forth = &evaluated.forth;
+        assert_eq!(forth.stack(), [1, 3]);
+        assert_eq!(forth.return_stack(), [2]);
+        assert_eq!(forth.output().text(), "hi");
+        assert_eq!(forth.base(), 16);
+        assert_eq!(forth.case_mode(), CaseMode::Sensitive);
+        assert_eq!(remote.fetch().unwrap().buffer, code);
+
+        let cancelling = cancel_token.clone();
+        thread::spawn(move || {
+            thread::sleep(Duration::from_millis(100));
+            cancelling.cancel();
+        });
+        let evaluated = remote.eval(&request("BEGIN AGAIN"), &cancel_token).unwrap();
+        let error = &evaluated.diagnostics[0].error;
+        assert_eq!(error.kind(), &ErrorKind::Cancelled);
+
+        remote.stop().unwrap();
+        daemon.join().unwrap().unwrap();
+        assert!(!socket.exists());
+    }
+}
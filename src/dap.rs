@@ -0,0 +1,589 @@
+use crate::config::Config;
+use forth_tui::{DirectoryFiles, Forth, ForthFiles, ForthResult, StepCursor, Token};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::fs;
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+// `forth-tui dap` is a debug adapter speaking the Debug Adapter Protocol on
+// stdin/stdout, so editors can debug .fth files with their own debugger UI.
+// https://microsoft.github.io/debug-adapter-protocol/specification
+//
+// A program is run one top-level word at a time, with the words it takes: a
+// whole `: ... ;` definition, the name after VARIABLE or the text of a string
+// counting as one step. Calls to definitions are stepped over, so stepping
+// into or out of them isn't supported. What a step prints is sent as output.
+//
+// INCLUDE reads files from the directory the debugger was launched in, or
+// else the program's, as the app reads them from its project.
+
+const THREAD_ID: u64 = 1;
+const STACK_REFERENCE: u64 = 1;
+const DEFINITIONS_REFERENCE: u64 = 2;
+
+// A step of the program, with the position it starts at
+#[derive(Clone)]
+struct Step {
+    line: usize,
+    column: usize,
+    code: String,
+}
+
+struct Session {
+    forth: Forth,
+    program: Option<PathBuf>,
+    source: String,
+    // where the next step is, and the step run last
+    cursor: StepCursor,
+    last: Option<Step>,
+    // status message of the step that failed
+    error: Option<String>,
+    // bytes of output sent to the editor so far
+    printed: usize,
+    // breakpoint lines by source path, as sent by the editor
+    breakpoints: HashMap<PathBuf, Vec<usize>>,
+    stop_on_entry: bool,
+    configured: bool,
+    running: bool,
+    seq: u64,
+}
+
+pub fn run() -> io::Result<()> {
+    let stdin = io::stdin();
+    let mut input = stdin.lock();
+    let mut session = Session::new();
+    while let Some(request) = read_message(&mut input)? {
+        if !session.handle(&request)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+impl Session {
+    fn new() -> Session {
+        Session {
+            forth: Forth::new(),
+            program: None,
+            source: String::new(),
+            cursor: StepCursor::new(),
+            last: None,
+            error: None,
+            printed: 0,
+            breakpoints: HashMap::new(),
+            stop_on_entry: false,
+            configured: false,
+            running: false,
+            seq: 0,
+        }
+    }
+
+    // Returns false once the editor disconnected
+    fn handle(&mut self, request: &Value) -> io::Result<bool> {
+        let command = request.get("command").and_then(Value::as_str).unwrap_or("");
+        let arguments = request.get("arguments").cloned().unwrap_or(Value::Null);
+        match command {
+            "initialize" => self.respond(request, Ok(capabilities()))?,
+            // breakpoints are asked for once the program is known, for them
+            // to be checked against it
+            "launch" => {
+                let result = self.launch(&arguments);
+                self.respond(request, result.map(|_| Value::Null))?;
+                self.event("initialized", json!({}))?;
+                self.start()?;
+            }
+            "setBreakpoints" => {
+                let body = self.set_breakpoints(&arguments);
+                self.respond(request, Ok(body))?;
+            }
+            "configurationDone" => {
+                self.respond(request, Ok(Value::Null))?;
+                self.configured = true;
+                self.start()?;
+            }
+            "threads" => {
+                let body = json!({ "threads": [{ "id": THREAD_ID, "name": "main" }] });
+                self.respond(request, Ok(body))?;
+            }
+            "stackTrace" => {
+                let body = self.stack_trace();
+                self.respond(request, Ok(body))?;
+            }
+            "scopes" => self.respond(request, Ok(scopes()))?,
+            "variables" => {
+                let reference = arguments
+                    .get("variablesReference")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0);
+                let body = self.variables(reference);
+                self.respond(request, Ok(body))?;
+            }
+            "evaluate" => {
+                let result = self.evaluate(&arguments);
+                self.send_output()?;
+                self.respond(request, result)?;
+            }
+            "continue" => {
+                let body = json!({ "allThreadsContinued": true });
+                self.respond(request, Ok(body))?;
+                self.resume(false)?;
+            }
+            "next" => {
+                self.respond(request, Ok(Value::Null))?;
+                self.resume(true)?;
+            }
+            // steps run to completion before the next request is read
+            "pause" => self.respond(request, Ok(Value::Null))?,
+            "disconnect" | "terminate" => {
+                self.respond(request, Ok(Value::Null))?;
+                return Ok(false);
+            }
+            _ => self.respond(request, Err(format!("unsupported request {}", command)))?,
+        }
+        Ok(true)
+    }
+
+    fn launch(&mut self, arguments: &Value) -> Result<(), String> {
+        let program = arguments
+            .get("program")
+            .and_then(Value::as_str)
+            .ok_or("missing program to debug")?;
+        let program = fs::canonicalize(program).map_err(|err| format!("{}: {}", program, err))?;
+        self.source = fs::read_to_string(&program).map_err(|err| err.to_string())?;
+        let root = match arguments.get("cwd").and_then(Value::as_str) {
+            Some(cwd) => PathBuf::from(cwd),
+            None => program.parent().map(PathBuf::from).unwrap_or_default(),
+        };
+        self.forth = Forth::builder().files(workspace_files(root)).build();
+        self.program = Some(program);
+        self.stop_on_entry = arguments
+            .get("stopOnEntry")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        Ok(())
+    }
+
+    // The program starts once it is launched and the editor sent its breakpoints
+    fn start(&mut self) -> io::Result<()> {
+        if self.running || !self.configured || self.program.is_none() {
+            return Ok(());
+        }
+        self.running = true;
+        if self.cursor.next_word(&self.source).is_none() {
+            return self.exit(0);
+        }
+        if self.stop_on_entry {
+            self.stopped("entry", None)
+        } else if self.at_breakpoint() {
+            self.stopped("breakpoint", None)
+        } else {
+            self.resume(false)
+        }
+    }
+
+    // Runs steps until the next breakpoint, or a single one
+    fn resume(&mut self, single_step: bool) -> io::Result<()> {
+        if self.error.is_some() {
+            return self.exit(1);
+        }
+        while let Some(status) = self.step() {
+            self.send_output()?;
+            if status.is_err() {
+                let message = crate::status_message(&status);
+                self.error = Some(message.clone());
+                return self.stopped("exception", Some(message));
+            }
+            if self.cursor.next_word(&self.source).is_none() {
+                break;
+            }
+            if single_step {
+                return self.stopped("step", None);
+            }
+            if self.at_breakpoint() {
+                return self.stopped("breakpoint", None);
+            }
+        }
+        self.exit(0)
+    }
+
+    // Runs the next step, None once the program ran to its end
+    fn step(&mut self) -> Option<ForthResult> {
+        let (_, line, column) = self.cursor.next_word(&self.source)?;
+        let (code, status) = self.forth.eval_step(&self.source, &mut self.cursor)?;
+        self.last = Some(Step {
+            line,
+            column,
+            code: code.to_string(),
+        });
+        Some(status)
+    }
+
+    // What the program printed since this was last asked, if anything. Text
+    // printed past the output limit may be gone by then.
+    fn new_output(&mut self) -> Option<String> {
+        let output = self.forth.output();
+        let text = output.text();
+        let printed = output.dropped() + text.len();
+        let new = printed.saturating_sub(self.printed).min(text.len());
+        self.printed = printed;
+        let new = text.get(text.len() - new..)?;
+        (!new.is_empty()).then(|| new.to_string())
+    }
+
+    fn send_output(&mut self) -> io::Result<()> {
+        match self.new_output() {
+            Some(output) => self.event("output", json!({ "category": "stdout", "output": output })),
+            None => Ok(()),
+        }
+    }
+
+    // Whether the next step is the first one of a line with a breakpoint
+    fn at_breakpoint(&self) -> bool {
+        let Some((_, line, _)) = self.cursor.next_word(&self.source) else {
+            return false;
+        };
+        let first_on_line = self.last.as_ref().is_none_or(|last| last.line != line);
+        first_on_line && self.breakpoint_lines().contains(&line)
+    }
+
+    fn breakpoint_lines(&self) -> &[usize] {
+        self.program
+            .as_ref()
+            .and_then(|program| self.breakpoints.get(program))
+            .map_or(&[], Vec::as_slice)
+    }
+
+    // The step that failed, or the one about to run, known by its first word
+    fn current_step(&self) -> Option<Step> {
+        match self.error {
+            Some(_) => self.last.clone(),
+            None => {
+                let (word, line, column) = self.cursor.next_word(&self.source)?;
+                Some(Step {
+                    line,
+                    column,
+                    code: word.to_string(),
+                })
+            }
+        }
+    }
+
+    fn set_breakpoints(&mut self, arguments: &Value) -> Value {
+        let path = arguments
+            .get("source")
+            .and_then(|source| source.get("path"))
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        let path = fs::canonicalize(path).unwrap_or_else(|_| PathBuf::from(path));
+        let lines: Vec<usize> = match arguments.get("breakpoints") {
+            Some(Value::Array(breakpoints)) => breakpoints
+                .iter()
+                .filter_map(|breakpoint| breakpoint.get("line").and_then(Value::as_u64))
+                .map(|line| line as usize)
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        // breakpoints in other files are never reached, as calls are
+        // stepped over; nor are those on lines no step starts on
+        let step_lines = match self.program.as_ref() == Some(&path) {
+            true => StepCursor::step_lines(&self.source),
+            false => Vec::new(),
+        };
+        let breakpoints = lines
+            .iter()
+            .map(|line| json!({ "verified": step_lines.contains(line), "line": line }))
+            .collect::<Vec<_>>();
+        self.breakpoints.insert(path, lines);
+        json!({ "breakpoints": breakpoints })
+    }
+
+    fn stack_trace(&self) -> Value {
+        let mut frames = Vec::new();
+        if let (Some(program), Some(step)) = (&self.program, self.current_step()) {
+            let name = program
+                .file_name()
+                .map_or(String::new(), |name| name.to_string_lossy().into_owned());
+            frames.push(json!({
+                "id": 1,
+                "name": step.code,
+                "source": { "name": name, "path": program.to_string_lossy() },
+                "line": step.line,
+                "column": step.column,
+            }));
+        }
+        let total = frames.len();
+        json!({ "stackFrames": frames, "totalFrames": total })
+    }
+
+    fn variables(&self, reference: u64) -> Value {
+        let variables = match reference {
+            // top of the stack first, as a debugger lists the innermost frame first
+            STACK_REFERENCE => self
+                .forth
+                .stack()
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(i, value)| variable(&format!("[{}]", i), &value.to_string()))
+                .collect(),
+            DEFINITIONS_REFERENCE => self
+                .forth
+                .user_definitions()
+                .iter()
+                .map(|definition| variable(&definition.name, &definition.instructions.join(" ")))
+                .collect(),
+            _ => Vec::new(),
+        };
+        json!({ "variables": variables })
+    }
+
+    // Hovering a word shows its definition, the debug console runs code,
+    // undone if it fails
+    fn evaluate(&mut self, arguments: &Value) -> Result<Value, String> {
+        let expression = arguments
+            .get("expression")
+            .and_then(Value::as_str)
+            .unwrap_or("");
+        let result = if arguments.get("context").and_then(Value::as_str) == Some("repl") {
+            let status = self.forth.eval_atomic(expression);
+            if status.is_err() {
+                return Err(crate::status_message(&status).to_string());
+            }
+            crate::stack_text(&self.forth)
+        } else {
+            let case_mode = self.forth.case_mode();
+            let definition = self
+                .forth
+                .definitions
+                .iter()
+                .rev()
+                .find(|definition| case_mode.matches(&definition.name, expression))
+                .ok_or("not a defined word")?;
+            let mut words = vec![":", definition.name.as_str()];
+            words.extend(definition.instructions.iter().map(Token::as_str));
+            words.push(";");
+            words.join(" ")
+        };
+        Ok(json!({ "result": result, "variablesReference": 0 }))
+    }
+
+    fn stopped(&mut self, reason: &str, text: Option<String>) -> io::Result<()> {
+        let mut body = json!({
+            "reason": reason,
+            "threadId": THREAD_ID,
+            "allThreadsStopped": true,
+        });
+        if let Some(text) = text {
+            body["text"] = json!(text);
+        }
+        self.event("stopped", body)
+    }
+
+    fn exit(&mut self, code: u64) -> io::Result<()> {
+        self.event("exited", json!({ "exitCode": code }))?;
+        self.event("terminated", json!({}))
+    }
+
+    fn respond(&mut self, request: &Value, result: Result<Value, String>) -> io::Result<()> {
+        let mut message = json!({
+            "type": "response",
+            "request_seq": request.get("seq"),
+            "command": request.get("command"),
+            "success": result.is_ok(),
+        });
+        match result {
+            Ok(Value::Null) => {}
+            Ok(body) => message["body"] = body,
+            Err(error) => message["message"] = json!(error),
+        }
+        self.send(message)
+    }
+
+    fn event(&mut self, event: &str, body: Value) -> io::Result<()> {
+        self.send(json!({ "type": "event", "event": event, "body": body }))
+    }
+
+    fn send(&mut self, mut message: Value) -> io::Result<()> {
+        self.seq += 1;
+        message["seq"] = json!(self.seq);
+        let text = message.to_string();
+        let mut stdout = io::stdout().lock();
+        write!(stdout, "Content-Length: {}\r\n\r\n{}", text.len(), text)?;
+        stdout.flush()
+    }
+}
+
+// Files INCLUDE reads, confined to the directory unless the config there
+// says otherwise, as in the app
+fn workspace_files(root: PathBuf) -> Box<dyn ForthFiles> {
+    match Config::load(Some(&root)).get_bool("include_anywhere") {
+        Some(true) => Box::new(DirectoryFiles::new(root)),
+        _ => Box::new(DirectoryFiles::confined(root)),
+    }
+}
+
+fn capabilities() -> Value {
+    json!({
+        "supportsConfigurationDoneRequest": true,
+        "supportsEvaluateForHovers": true,
+        "supportsTerminateRequest": true,
+    })
+}
+
+fn scopes() -> Value {
+    let scope = |name: &str, reference: u64| json!({ "name": name, "variablesReference": reference, "expensive": false });
+    json!({
+        "scopes": [
+            scope("Stack", STACK_REFERENCE),
+            scope("Definitions", DEFINITIONS_REFERENCE),
+        ]
+    })
+}
+
+fn variable(name: &str, value: &str) -> Value {
+    json!({ "name": name, "value": value, "variablesReference": 0 })
+}
+
+// Reads the next `Content-Length` framed message, or None at the end of input
+fn read_message(input: &mut impl BufRead) -> io::Result<Option<Value>> {
+    let mut length = None;
+    loop {
+        let mut header = String::new();
+        if input.read_line(&mut header)? == 0 {
+            return Ok(None);
+        }
+        let header = header.trim();
+        if header.is_empty() {
+            break;
+        }
+        if let Some(value) = header.strip_prefix("Content-Length:") {
+            length = value.trim().parse::<usize>().ok();
+        }
+    }
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid debug adapter message");
+    let mut body = vec![0; length.ok_or_else(invalid)?];
+    input.read_exact(&mut body)?;
+    serde_json::from_slice(&body)
+        .map(Some)
+        .map_err(|_| invalid())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_steps(source: &str) -> (Session, Vec<String>) {
+        let mut session = Session::new();
+        session.source = source.to_string();
+        let mut steps = Vec::new();
+        while let Some(status) = session.step() {
+            assert!(status.is_ok(), "{}: {:?}", source, status);
+            steps.push(session.last.as_ref().unwrap().code.clone());
+        }
+        (session, steps)
+    }
+
+    #[test]
+    fn words_take_their_arguments_as_one_step() {
+        let source = ".\" hi\" CR VARIABLE X 5 X ! 3 CONSTANT C ( comment )\n\
+                      ' C EXECUTE CREATE T S\" a b\" NIP \\ to the end\n\
+                      : TWICE ( n -- 2n )\n  2 * ;\nX @ TWICE";
+        let (session, steps) = run_steps(source);
+        assert_eq!(session.forth.output().text(), "hi\n");
+        assert_eq!(session.forth.stack(), [3, 3, 10]);
+        assert_eq!(
+            steps,
+            [
+                ".\" hi\"",
+                "CR",
+                "VARIABLE X",
+                "5",
+                "X",
+                "!",
+                "3",
+                "CONSTANT C",
+                "' C",
+                "EXECUTE",
+                "CREATE T",
+                "S\" a b\"",
+                "NIP",
+                ": TWICE ( n -- 2n )\n  2 * ;",
+                "X",
+                "@",
+                "TWICE",
+            ]
+        );
+        let last = session.last.unwrap();
+        assert_eq!((last.line, last.column), (5, 5));
+    }
+
+    #[test]
+    fn breakpoints_stop_at_the_first_step_of_their_line() {
+        let mut session = Session::new();
+        session.source = String::from("1 2\n( skipped )\n3 4");
+        session.program = Some(PathBuf::from("test.fth"));
+        session
+            .breakpoints
+            .insert(PathBuf::from("test.fth"), vec![3]);
+        assert!(!session.at_breakpoint());
+        session.step();
+        session.step();
+        assert!(session.at_breakpoint());
+        session.step();
+        assert!(!session.at_breakpoint());
+    }
+
+    #[test]
+    fn only_breakpoints_on_lines_steps_start_on_are_verified() {
+        let mut session = Session::new();
+        session.source = String::from(": SQUARE\n  DUP * ;\n3 SQUARE");
+        session.program = Some(PathBuf::from("test.fth"));
+        let arguments = json!({
+            "source": { "path": "test.fth" },
+            "breakpoints": [{ "line": 1 }, { "line": 2 }, { "line": 3 }],
+        });
+        let verified: Vec<bool> = session.set_breakpoints(&arguments)["breakpoints"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|breakpoint| breakpoint["verified"].as_bool().unwrap())
+            .collect();
+        assert_eq!(verified, [true, false, true]);
+    }
+
+    #[test]
+    fn steps_print_what_they_print_once() {
+        let mut session = Session::new();
+        session.source = String::from("1 . CR 2 3 .\" a\" DROP");
+        assert_eq!(session.new_output(), None);
+        session.step();
+        session.step();
+        assert_eq!(session.new_output().as_deref(), Some("1 "));
+        session.step();
+        assert_eq!(session.new_output().as_deref(), Some("\n"));
+        while session.step().is_some() {}
+        assert_eq!(session.new_output().as_deref(), Some("a"));
+        assert_eq!(session.new_output(), None);
+    }
+
+    #[test]
+    fn programs_include_files_from_their_directory() {
+        let dir = std::env::temp_dir().join(format!("forth-tui-dap-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("lib.fth"), ": SQUARE DUP * ;").unwrap();
+        fs::write(dir.join("main.fth"), "INCLUDE lib.fth\n3 SQUARE").unwrap();
+        let mut session = Session::new();
+        let program = dir.join("main.fth");
+        let launched = session.launch(&json!({ "program": program.to_string_lossy() }));
+        let mut statuses = Vec::new();
+        while let Some(status) = session.step() {
+            statuses.push(status);
+        }
+        fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(launched, Ok(()));
+        assert!(statuses.iter().all(Result::is_ok), "{:?}", statuses);
+        assert_eq!(session.forth.stack(), [9]);
+    }
+}
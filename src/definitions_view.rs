@@ -0,0 +1,202 @@
+use forth_tui::{Definition, DefinitionKind, Forth, Token};
+
+// How the Definitions pane lists the session's definitions: in which order,
+// grouped by kind or not, and filtered by a quick search on their names
+
+#[derive(Default, PartialEq, Eq)]
+pub enum Order {
+    #[default]
+    Definition,
+    Name,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Kind {
+    Colon,
+    Variable,
+    Constant,
+    Value,
+    Deferred,
+    Created,
+    Marker,
+    Vocabulary,
+}
+
+impl Kind {
+    pub fn of(definition: &Definition) -> Kind {
+        match definition.kind {
+            DefinitionKind::Colon => Kind::Colon,
+            DefinitionKind::Variable => Kind::Variable,
+            DefinitionKind::Constant => Kind::Constant,
+            DefinitionKind::Value => Kind::Value,
+            DefinitionKind::Deferred => Kind::Deferred,
+            DefinitionKind::Created => Kind::Created,
+            DefinitionKind::Marker => Kind::Marker,
+            DefinitionKind::Vocabulary => Kind::Vocabulary,
+        }
+    }
+
+    pub fn heading(self) -> &'static str {
+        match self {
+            Kind::Colon => "Colon definitions",
+            Kind::Variable => "Variables",
+            Kind::Constant => "Constants",
+            Kind::Value => "Values",
+            Kind::Deferred => "Deferred words",
+            Kind::Created => "Created words",
+            Kind::Marker => "Markers",
+            Kind::Vocabulary => "Vocabularies",
+        }
+    }
+}
+
+// What a definition does: a colon definition's body, what kind of data it
+// names, the word a deferred word runs, or what a created word does
+pub fn description(forth: &Forth, definition: &Definition) -> String {
+    let body = definition.instructions.join(" ");
+    match definition.kind {
+        DefinitionKind::Colon if definition.immediate && body.is_empty() => {
+            String::from("(immediate)")
+        }
+        DefinitionKind::Colon if definition.immediate => format!("{} (immediate)", body),
+        DefinitionKind::Colon => body,
+        DefinitionKind::Variable => format!("variable at {}", body),
+        DefinitionKind::Constant => format!("constant {}", body),
+        DefinitionKind::Value => format!("value {}", body),
+        DefinitionKind::Deferred => match deferred_target(forth, definition) {
+            Some(target) => format!("deferred to {}", target),
+            None => String::from("deferred, not set"),
+        },
+        DefinitionKind::Marker => format!("marker, data space from {}", body),
+        DefinitionKind::Vocabulary => String::from("vocabulary"),
+        DefinitionKind::Created => {
+            let address = definition.instructions.first().map_or("", Token::as_str);
+            match does_words(forth, definition) {
+                Some(words) => format!("created at {}, does> {}", address, words),
+                None => format!("created at {}", address),
+            }
+        }
+    }
+}
+
+// Words a created word runs after pushing its address, from the DOES> of the
+// word that made it
+pub fn does_words(forth: &Forth, definition: &Definition) -> Option<String> {
+    let defining = definition.instructions.get(1)?;
+    let Ok(index) = defining.parse::<usize>() else {
+        // a daemon's response has the words themselves, after DOES>
+        return Some(definition.instructions[2..].join(" "));
+    };
+    let words = &forth.definitions.get(index)?.instructions;
+    let does = words.iter().position(|w| w.eq_ignore_ascii_case("DOES>"))?;
+    Some(words[does + 1..].join(" "))
+}
+
+// Name of the word a deferred word runs, if IS gave it one
+pub fn deferred_target<'a>(forth: &'a Forth, definition: &'a Definition) -> Option<&'a str> {
+    let body = definition.instructions.first()?;
+    match body.parse() {
+        Ok(xt) => forth.execution_token_name(xt),
+        // a daemon's response names the word instead
+        Err(_) => Some(body),
+    }
+}
+
+// The wordlists searched, first one first, and the one definitions go to
+pub fn search_order(forth: &Forth) -> String {
+    let order: Vec<String> = forth
+        .search_order()
+        .iter()
+        .map(|&wordlist| forth.wordlist_name(wordlist))
+        .collect();
+    format!(
+        "searching {} , defining in {}",
+        order.join(" "),
+        forth.wordlist_name(forth.current_wordlist())
+    )
+}
+
+pub enum Row<'a> {
+    Heading(Kind),
+    Definition(&'a Definition),
+}
+
+#[derive(Default)]
+pub struct DefinitionsView {
+    pub order: Order,
+    pub grouped: bool,
+    pub filter: String,
+    // whether the title shows the search order
+    pub search_order: bool,
+    // among the definitions listed, headings aside
+    pub selected: usize,
+}
+
+impl DefinitionsView {
+    pub fn toggle_order(&mut self) {
+        self.order = match self.order {
+            Order::Definition => Order::Name,
+            Order::Name => Order::Definition,
+        };
+    }
+
+    pub fn select_next(&mut self, forth: &Forth) {
+        let count = self.definitions(forth).count();
+        self.selected = (self.selected + 1).min(count.saturating_sub(1));
+    }
+
+    // The definition selected, the last one once the list got shorter
+    pub fn selected_definition<'a>(&self, forth: &'a Forth) -> Option<&'a Definition> {
+        self.definitions(forth).take(self.selected + 1).last()
+    }
+
+    fn definitions<'a>(&self, forth: &'a Forth) -> impl Iterator<Item = &'a Definition> {
+        self.rows(forth).into_iter().filter_map(|row| match row {
+            Row::Definition(definition) => Some(definition),
+            Row::Heading(_) => None,
+        })
+    }
+
+    pub fn title(&self, forth: &Forth) -> String {
+        let mut title = String::from("Definitions");
+        if self.order == Order::Name {
+            title.push_str(" by name");
+        }
+        if !self.filter.is_empty() {
+            title.push_str(&format!(" matching \"{}\"", self.filter));
+        }
+        if self.search_order {
+            title.push_str(&format!(" , {}", search_order(forth)));
+        }
+        title
+    }
+
+    pub fn rows<'a>(&self, forth: &'a Forth) -> Vec<Row<'a>> {
+        let filter = self.filter.to_ascii_uppercase();
+        let mut definitions: Vec<&Definition> = forth
+            .user_definitions()
+            .iter()
+            // nameless definitions, from :NONAME, are only reached by their token
+            .filter(|d| !d.name.is_empty() && d.name.to_ascii_uppercase().contains(&filter))
+            .collect();
+        // sorts are stable: redefinitions stay in the order they were made
+        if self.order == Order::Name {
+            definitions.sort_by(|a, b| a.name.cmp(&b.name));
+        }
+        if !self.grouped {
+            return definitions.into_iter().map(Row::Definition).collect();
+        }
+
+        definitions.sort_by_key(|d| Kind::of(d));
+        let mut rows = Vec::new();
+        let mut kind = None;
+        for definition in definitions {
+            if kind != Some(Kind::of(definition)) {
+                kind = Some(Kind::of(definition));
+                rows.push(Row::Heading(Kind::of(definition)));
+            }
+            rows.push(Row::Definition(definition));
+        }
+        rows
+    }
+}
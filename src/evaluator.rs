@@ -0,0 +1,207 @@
+use crate::daemon::Remote;
+use crate::persistence::DICTIONARY_FILE;
+use forth_tui::{
+    CancelToken, CaseMode, Diagnostic, DirectoryFiles, ErrorKind, Forth, ForthResult, LineCache,
+    OverflowMode, Strictness,
+};
+use serde::{Deserialize, Serialize};
+use std::io;
+use std::path::PathBuf;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+use std::time::Duration;
+
+// Evaluations run on a thread of their own, so that the app keeps drawing,
+// and showing the latest result, while a slow program runs. A request
+// queued behind a newer one is dropped, the code having changed since.
+// The worker keeps the interpreter's state before each line of the code, so
+// that an edit only has the code from its line on evaluated again. Attached
+// to a daemon, the worker has the daemon evaluate the code instead, and the
+// result is an error once the daemon can't be reached.
+pub struct Evaluator {
+    requests: Sender<(u64, Request)>,
+    results: Receiver<(u64, io::Result<Evaluated>)>,
+    // numbers of the latest request sent, and of the latest result received
+    sent: u64,
+    received: u64,
+}
+
+// The code to evaluate, after the preludes, and how
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Request {
+    // the user's prelude file, by name, with its source
+    pub user_prelude: Option<(String, String)>,
+    // the words saved to the user's dictionary, as they were at launch
+    pub dictionary: String,
+    // definitions reloaded from a previous session
+    pub prelude: String,
+    pub code: String,
+    pub provenance: bool,
+    pub overflow_mode: OverflowMode,
+    pub strictness: Strictness,
+    pub case_mode: CaseMode,
+    pub fuel: u64,
+    // the directory INCLUDE reads files from, and whether only from within it
+    pub root: PathBuf,
+    pub include_anywhere: bool,
+    // the editor's text, which a daemon keeps for the apps attaching to it
+    pub buffer: String,
+}
+
+#[derive(Serialize, Deserialize)]
+pub struct Evaluated {
+    pub forth: Forth,
+    // an error of the prelude stops the evaluation before the code
+    pub prelude_status: ForthResult,
+    // where the definitions reloaded start, those saved again with the code's
+    pub saved_from: usize,
+    // every error of the code, in order
+    pub diagnostics: Vec<Diagnostic>,
+}
+
+impl Evaluator {
+    // The token cancels the running evaluation, and is reset before the next
+    pub fn spawn(cancel_token: CancelToken, remote: Option<Remote>) -> Evaluator {
+        let (requests, received_requests) = mpsc::channel::<(u64, Request)>();
+        let (sent_results, results) = mpsc::channel();
+        thread::spawn(move || {
+            let mut worker = Worker::default();
+            while let Ok(mut request) = received_requests.recv() {
+                while let Ok(newer) = received_requests.try_recv() {
+                    request = newer;
+                }
+                cancel_token.reset();
+                let (number, request) = request;
+                let evaluated = match &remote {
+                    Some(remote) => remote.eval(&request, &cancel_token),
+                    None => Ok(worker.evaluate(request, cancel_token.clone())),
+                };
+                if sent_results.send((number, evaluated)).is_err() {
+                    break;
+                }
+            }
+        });
+        Evaluator {
+            requests,
+            results,
+            sent: 0,
+            received: 0,
+        }
+    }
+
+    pub fn send(&mut self, request: Request) {
+        self.sent += 1;
+        // a worker gone leaves the app showing its last result
+        let _ = self.requests.send((self.sent, request));
+    }
+
+    // The latest result not received yet, waiting at most `wait` for one
+    pub fn receive(&mut self, wait: Duration) -> Option<io::Result<Evaluated>> {
+        if !self.is_busy() {
+            return None;
+        }
+        let first = self.results.recv_timeout(wait).ok()?;
+        let (number, evaluated) = self.results.try_iter().last().unwrap_or(first);
+        // the result of a request sent before those discarded
+        if number <= self.received {
+            return None;
+        }
+        self.received = number;
+        Some(evaluated)
+    }
+
+    // Drops the results of the requests sent so far, when they come
+    pub fn discard(&mut self) {
+        self.received = self.sent;
+    }
+
+    // Whether the result of the latest request is still to come
+    pub fn is_busy(&self) -> bool {
+        self.received < self.sent
+    }
+}
+
+// Evaluates requests in turn, keeping the states before each line of the
+// code for the next request, as long as the preludes and settings are the same
+#[derive(Default)]
+pub struct Worker {
+    cache: LineCache,
+    last: Option<Request>,
+}
+
+impl Worker {
+    pub fn evaluate(&mut self, request: Request, cancel_token: CancelToken) -> Evaluated {
+        // the states cached are those the prelude and settings led to
+        if !self
+            .last
+            .as_ref()
+            .is_some_and(|last| last.has_setup_of(&request))
+        {
+            self.cache.clear();
+        }
+        self.last = Some(request.clone());
+        request.evaluate(cancel_token, &mut self.cache)
+    }
+}
+
+impl Request {
+    // Whether the other request only differs by its code
+    fn has_setup_of(&self, other: &Request) -> bool {
+        self.user_prelude == other.user_prelude
+            && self.dictionary == other.dictionary
+            && self.prelude == other.prelude
+            && self.provenance == other.provenance
+            && self.overflow_mode == other.overflow_mode
+            && self.strictness == other.strictness
+            && self.case_mode == other.case_mode
+            && self.fuel == other.fuel
+            && self.root == other.root
+            && self.include_anywhere == other.include_anywhere
+    }
+
+    fn evaluate(self, cancel_token: CancelToken, cache: &mut LineCache) -> Evaluated {
+        let files = match self.include_anywhere {
+            true => DirectoryFiles::new(self.root),
+            false => DirectoryFiles::confined(self.root),
+        };
+        let mut forth = Forth::builder()
+            .files(Box::new(files))
+            .cancel_token(cancel_token)
+            .provenance(self.provenance)
+            .overflow_mode(self.overflow_mode)
+            .strictness(self.strictness)
+            .case_mode(self.case_mode)
+            .fuel(self.fuel)
+            .build();
+        // the user's prelude adds to the interpreter's, an error naming its file
+        let mut prelude_status = match &self.user_prelude {
+            Some((file, source)) => forth
+                .eval_prelude(source)
+                .map_err(|error| ErrorKind::Included(file.clone(), Box::new(error)).into()),
+            None => Ok(()),
+        };
+        // definitions reloaded are part of the prelude, not listed again as
+        // the code's, and come before the dictionary's words
+        let saved_from = forth.definitions.len();
+        if prelude_status.is_ok() {
+            prelude_status = forth.eval_prelude(&self.prelude);
+        }
+        if prelude_status.is_ok() {
+            prelude_status = forth.eval(&self.dictionary).map_err(|error| {
+                ErrorKind::Included(DICTIONARY_FILE.to_string(), Box::new(error)).into()
+            });
+        }
+        let mut diagnostics = Vec::new();
+        if prelude_status.is_ok() {
+            // the prelude's warnings are not the code's
+            forth.take_warnings();
+            diagnostics = forth.eval_all_cached(&self.code, cache);
+        }
+        Evaluated {
+            forth,
+            prelude_status,
+            saved_from,
+            diagnostics,
+        }
+    }
+}
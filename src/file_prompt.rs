@@ -0,0 +1,87 @@
+use crate::project::is_source;
+use std::fs;
+use std::path::Path;
+
+// Most files listed under the path typed
+pub const MAX_CANDIDATES: usize = 8;
+
+// A path typed in a popup, to open a source file from or save the editor's
+// code to. Paths are relative to the directory the app works in.
+pub struct FilePrompt {
+    pub action: FileAction,
+    pub input: String,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum FileAction {
+    Open,
+    Save,
+}
+
+impl FilePrompt {
+    pub fn new(action: FileAction) -> FilePrompt {
+        FilePrompt {
+            action,
+            input: String::new(),
+        }
+    }
+
+    pub fn title(&self) -> &'static str {
+        match self.action {
+            FileAction::Open => "Open file",
+            FileAction::Save => "Save as",
+        }
+    }
+
+    // The source files and directories in the directory typed so far whose
+    // name starts with what follows it, as paths to type, directories
+    // ending with a slash
+    pub fn candidates(&self, root: &Path) -> Vec<String> {
+        let (dir, start) = match self.input.rsplit_once('/') {
+            Some((dir, start)) => (format!("{}/", dir), start),
+            None => (String::new(), self.input.as_str()),
+        };
+        let Ok(entries) = fs::read_dir(root.join(if dir.is_empty() { "." } else { &dir })) else {
+            return Vec::new();
+        };
+        let mut candidates: Vec<String> = entries
+            .filter_map(|entry| entry.ok())
+            .filter_map(|entry| {
+                let name = entry.file_name().to_string_lossy().into_owned();
+                // hidden files only show up once a dot is typed
+                if !name.starts_with(start) || (name.starts_with('.') && !start.starts_with('.')) {
+                    return None;
+                }
+                let path = entry.path();
+                if path.is_dir() {
+                    Some(format!("{}{}/", dir, name))
+                } else {
+                    is_source(&path).then(|| format!("{}{}", dir, name))
+                }
+            })
+            .collect();
+        candidates.sort();
+        candidates
+    }
+
+    // Types what all the candidates start with
+    pub fn complete(&mut self, root: &Path) {
+        let candidates = self.candidates(root);
+        let Some(first) = candidates.first() else {
+            return;
+        };
+        let mut common = first.len();
+        for candidate in &candidates[1..] {
+            common = first
+                .char_indices()
+                .zip(candidate.chars())
+                .take_while(|((_, a), b)| a == b)
+                .last()
+                .map_or(0, |((index, a), _)| index + a.len_utf8())
+                .min(common);
+        }
+        if common > self.input.len() {
+            self.input = first[..common].to_string();
+        }
+    }
+}
@@ -0,0 +1,221 @@
+use forth_tui::CaseMode;
+use std::collections::BTreeMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// Folded definitions are shown in the editor as a single `: NAME ⋯ ;` line.
+// The hidden lines are kept here and spliced back whenever the code is evaluated,
+// so folding never changes what runs. Edits changing a summary line are
+// refused, as they would lose the lines it hides.
+const FOLD_MARKER: &str = "⋯";
+
+// The names of the definitions folded in each file, next to the saved
+// definitions, for the folds to be made again when the file is opened
+pub const FOLDS_FILE: &str = ".forth-tui-folds.json";
+
+struct Fold {
+    name: String,
+    summary: String,
+    lines: Vec<String>,
+}
+
+#[derive(Default)]
+pub struct Folds {
+    folds: Vec<Fold>,
+}
+
+impl Folds {
+    // Folds the definition starting at `row`, or unfolds it if it is already folded.
+    // Returns the new editor lines, or None when there is nothing to fold there.
+    pub fn toggle(
+        &mut self,
+        lines: &[String],
+        row: usize,
+        case_mode: CaseMode,
+    ) -> Option<Vec<String>> {
+        let line = lines.get(row)?;
+        if let Some(position) = self.folds.iter().position(|f| &f.summary == line) {
+            let fold = self.folds.remove(position);
+            let mut new_lines = lines[..row].to_vec();
+            new_lines.extend(fold.lines);
+            new_lines.extend_from_slice(&lines[row + 1..]);
+            return Some(new_lines);
+        }
+
+        let mut words = line.split_whitespace();
+        if words.next() != Some(":") {
+            return None;
+        }
+        let name = case_mode.name(words.next()?);
+        let end = (row..lines.len()).find(|&r| lines[r].split_whitespace().any(|w| w == ";"))?;
+        if end == row {
+            return None;
+        }
+
+        let summary = self.unique_summary(&name);
+        self.folds.push(Fold {
+            name,
+            summary: summary.clone(),
+            lines: lines[row..=end].to_vec(),
+        });
+        let mut new_lines = lines[..row].to_vec();
+        new_lines.push(summary);
+        new_lines.extend_from_slice(&lines[end + 1..]);
+        Some(new_lines)
+    }
+
+    // Folds every definition of the names, as they were when the file was
+    // last left; the new editor lines
+    pub fn fold_names(
+        &mut self,
+        lines: &[String],
+        names: &[String],
+        case_mode: CaseMode,
+    ) -> Vec<String> {
+        let mut lines = lines.to_vec();
+        let mut row = 0;
+        while row < lines.len() {
+            let mut words = lines[row].split_whitespace();
+            let named = words.next() == Some(":")
+                && words
+                    .next()
+                    .is_some_and(|name| names.iter().any(|n| case_mode.matches(n, name)));
+            if named {
+                if let Some(folded) = self.toggle(&lines, row, case_mode) {
+                    lines = folded;
+                }
+            }
+            row += 1;
+        }
+        lines
+    }
+
+    // Names of the definitions folded, in the order they were
+    pub fn names(&self) -> Vec<String> {
+        self.folds.iter().map(|fold| fold.name.clone()).collect()
+    }
+
+    // Whether each fold is still shown by a single line, as editing the
+    // lines around it can join its summary with them or remove it
+    pub fn are_shown(&self, lines: &[String]) -> bool {
+        self.folds
+            .iter()
+            .all(|f| lines.iter().filter(|&line| line == &f.summary).count() == 1)
+    }
+
+    // Editor lines with every folded definition expanded back
+    pub fn expand(&self, lines: &[String]) -> Vec<String> {
+        let mut expanded = Vec::with_capacity(lines.len());
+        for line in lines {
+            match self.folds.iter().find(|f| &f.summary == line) {
+                Some(fold) => expanded.extend(fold.lines.iter().cloned()),
+                None => expanded.push(line.clone()),
+            }
+        }
+        expanded
+    }
+
+    // Editor row showing a row of the expanded lines, None for a row in a fold
+    pub fn editor_row(&self, lines: &[String], expanded_row: usize) -> Option<usize> {
+        let mut start = 0;
+        for (row, line) in lines.iter().enumerate() {
+            let fold = self.folds.iter().find(|f| &f.summary == line);
+            let length = fold.map_or(1, |fold| fold.lines.len());
+            if expanded_row < start + length {
+                return fold.is_none().then_some(row);
+            }
+            start += length;
+        }
+        None
+    }
+
+    // Redefinitions of the same word get numbered markers so each fold stays distinct
+    fn unique_summary(&self, name: &str) -> String {
+        let mut summary = format!(": {} {} ;", name, FOLD_MARKER);
+        let mut n = 2;
+        while self.folds.iter().any(|f| f.summary == summary) {
+            summary = format!(": {} {}{} ;", name, FOLD_MARKER, n);
+            n += 1;
+        }
+        summary
+    }
+}
+
+// The names folded in a file, by its path from the root; none without a
+// folds file
+pub fn load_folds(root: &Path, file: &Path) -> Vec<String> {
+    let mut folds = read_folds(root);
+    folds
+        .remove(&file.to_string_lossy().into_owned())
+        .unwrap_or_default()
+}
+
+// Keeps the names folded in a file, writing the folds file
+pub fn save_folds(root: &Path, file: &Path, names: Vec<String>) -> io::Result<()> {
+    let mut folds = read_folds(root);
+    let file = file.to_string_lossy().into_owned();
+    if names.is_empty() {
+        folds.remove(&file);
+    } else {
+        folds.insert(file, names);
+    }
+    fs::write(root.join(FOLDS_FILE), serde_json::to_string(&folds)?)
+}
+
+fn read_folds(root: &Path) -> BTreeMap<String, Vec<String>> {
+    fs::read_to_string(root.join(FOLDS_FILE))
+        .ok()
+        .and_then(|json| serde_json::from_str(&json).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(code: &str) -> Vec<String> {
+        code.lines().map(String::from).collect()
+    }
+
+    #[test]
+    fn folding_hides_a_definition_until_expanded() {
+        let code = lines("1\n: sq\n  dup * ;\n2 sq");
+        let mut folds = Folds::default();
+        let folded = folds.toggle(&code, 1, CaseMode::Insensitive).unwrap();
+        assert_eq!(folded, lines("1\n: SQ ⋯ ;\n2 sq"));
+        assert_eq!(folds.expand(&folded), code);
+        assert_eq!(folds.editor_row(&folded, 2), None);
+        assert_eq!(folds.editor_row(&folded, 3), Some(2));
+        assert_eq!(folds.toggle(&folded, 1, CaseMode::Insensitive), Some(code));
+    }
+
+    #[test]
+    fn summaries_keep_the_case_of_names_in_case_sensitive_modes() {
+        let code = lines(": sq\n  dup * ;");
+        let mut folds = Folds::default();
+        let folded = folds.toggle(&code, 0, CaseMode::Sensitive).unwrap();
+        assert_eq!(folded, lines(": sq ⋯ ;"));
+        assert_eq!(folds.names(), ["sq"]);
+    }
+
+    #[test]
+    fn folds_are_made_again_by_name() {
+        let code = lines(": a\n 1 ;\n: b\n 2 ;\n: A\n 3 ;");
+        let mut folds = Folds::default();
+        let names = [String::from("a")];
+        let folded = folds.fold_names(&code, &names, CaseMode::Insensitive);
+        assert_eq!(folded, lines(": A ⋯ ;\n: b\n 2 ;\n: A ⋯2 ;"));
+        assert_eq!(folds.expand(&folded), code);
+    }
+
+    #[test]
+    fn edits_joining_a_summary_leave_its_fold_not_shown() {
+        let code = lines(": a\n 1 ;\n2");
+        let mut folds = Folds::default();
+        let folded = folds.toggle(&code, 0, CaseMode::Insensitive).unwrap();
+        assert!(folds.are_shown(&folded));
+        assert!(!folds.are_shown(&lines(": A ⋯ ;2")));
+        assert!(!folds.are_shown(&lines(": A ⋯ ;\n: A ⋯ ;\n2")));
+    }
+}
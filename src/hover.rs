@@ -0,0 +1,80 @@
+use crate::definitions_view;
+use crate::inspector::word_spans;
+use forth_tui::{glossary, Forth};
+
+// Longest body shown in a tooltip, in characters
+const BODY_SUMMARY_LENGTH: usize = 40;
+
+// Tooltip shown over a word in the editor: its name and stack effect, then
+// its documentation for a built-in word, or its body for a definition
+pub fn tooltip(forth: &Forth, source: &str, word: &str) -> Option<Vec<String>> {
+    let case_mode = forth.case_mode();
+    let mut definitions = forth.definitions.iter().rev();
+    if let Some(definition) = definitions.find(|d| case_mode.matches(&d.name, word)) {
+        let name = &definition.name;
+        // the glossary's names are in upper case
+        let entry = glossary::entries(source)
+            .into_iter()
+            .rev()
+            .find(|entry| entry.name.eq_ignore_ascii_case(name));
+        // the stack effect written next to this definition, or the one it was
+        // made with when the source has changed since
+        let stack_effect = entry
+            .as_ref()
+            .and_then(|e| e.stack_effect.as_deref())
+            .or(definition.stack_effect.as_deref());
+        let mut lines = vec![heading(name, stack_effect)];
+        if let Some(entry) = entry.filter(|entry| !entry.doc.is_empty()) {
+            lines.push(entry.doc);
+        }
+        lines.push(summary(&definitions_view::description(forth, definition)));
+        return Some(lines);
+    }
+    let entry = glossary::built_in(word)?;
+    Some(vec![
+        heading(&entry.name, entry.stack_effect.as_deref()),
+        entry.doc,
+    ])
+}
+
+fn heading(name: &str, stack_effect: Option<&str>) -> String {
+    match stack_effect {
+        Some(stack_effect) => format!("{} {}", name, stack_effect),
+        None => name.to_string(),
+    }
+}
+
+fn summary(body: &str) -> String {
+    if body.chars().count() <= BODY_SUMMARY_LENGTH {
+        return format!(": {} ;", body);
+    }
+    let start: String = body.chars().take(BODY_SUMMARY_LENGTH).collect();
+    format!(": {} ⋯", start)
+}
+
+// A tooltip and the terminal cell the mouse rests on
+pub struct Tooltip {
+    pub column: u16,
+    pub row: u16,
+    pub lines: Vec<String>,
+}
+
+// Word at a row and column of the text, not counting the spaces after it
+pub fn word_under(lines: &[String], (row, column): (usize, usize)) -> Option<&str> {
+    word_spans(lines.get(row)?)
+        .into_iter()
+        .find(|&(start, end, _)| start <= column && column < end)
+        .map(|(_, _, word)| word)
+}
+
+// First row or column to show so that the cursor stays in view, the way the
+// editor widget scrolls
+pub fn scroll_top(previous: u16, cursor: u16, length: u16) -> u16 {
+    if cursor < previous {
+        cursor
+    } else if previous + length <= cursor {
+        cursor + 1 - length
+    } else {
+        previous
+    }
+}
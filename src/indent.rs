@@ -0,0 +1,62 @@
+// Indentation of code inside definitions and control structures: a level per
+// structure left open, with the words closing a structure (or going on to
+// its next part, like ELSE) back at the level of the word opening it
+
+pub const INDENT: &str = "  ";
+
+const OPENING: [&str; 9] = [
+    ":", ":NONAME", "TEST:", "IF", "DO", "?DO", "BEGIN", "CASE", "OF",
+];
+const CLOSING: [&str; 10] = [
+    ";", ";TEST", "THEN", "LOOP", "+LOOP", "UNTIL", "AGAIN", "REPEAT", "ENDOF", "ENDCASE",
+];
+const CONTINUING: [&str; 2] = ["ELSE", "WHILE"];
+
+fn is_one_of(word: &str, words: &[&str]) -> bool {
+    words.iter().any(|w| w.eq_ignore_ascii_case(word))
+}
+
+// Levels open after `text`, starting with `depth` of them
+pub fn depth_after(depth: usize, text: &str) -> usize {
+    text.split_whitespace().fold(depth, |depth, word| {
+        if is_one_of(word, &OPENING) {
+            depth + 1
+        } else if is_one_of(word, &CLOSING) {
+            depth.saturating_sub(1)
+        } else {
+            depth
+        }
+    })
+}
+
+// Level of a line when `depth` levels are open before it
+pub fn line_level(depth: usize, line: &str) -> usize {
+    match line.split_whitespace().next() {
+        Some(word) if is_one_of(word, &CLOSING) || is_one_of(word, &CONTINUING) => {
+            depth.saturating_sub(1)
+        }
+        _ => depth,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn structures_open_a_level_until_closed() {
+        assert_eq!(depth_after(0, ": sq dup *"), 1);
+        assert_eq!(depth_after(1, "0< if negate then ;"), 0);
+        assert_eq!(depth_after(0, "10 0 DO I . LOOP"), 0);
+        assert_eq!(depth_after(0, ";"), 0);
+    }
+
+    #[test]
+    fn closing_and_continuing_words_go_back_a_level() {
+        assert_eq!(line_level(2, "else"), 1);
+        assert_eq!(line_level(2, "  THEN ;"), 1);
+        assert_eq!(line_level(2, "dup *"), 2);
+        assert_eq!(line_level(0, "WHILE"), 0);
+        assert_eq!(line_level(1, ""), 1);
+    }
+}
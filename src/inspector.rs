@@ -0,0 +1,244 @@
+use forth_tui::{Forth, Instruction};
+
+// Definition the editor cursor is on: either a word naming a definition,
+// or the body of a `: NAME ... ;` definition written on the cursor line
+pub fn definition_at_cursor(
+    forth: &Forth,
+    lines: &[String],
+    cursor: (usize, usize),
+) -> Option<usize> {
+    let line = lines.get(cursor.0)?;
+    let latest = |name: &str| {
+        let case_mode = forth.case_mode();
+        forth
+            .definitions
+            .iter()
+            .rposition(|d| case_mode.matches(&d.name, name))
+    };
+
+    let mut enclosing = None;
+    let mut words = word_spans(line).into_iter().peekable();
+    while let Some((start, end, word)) = words.next() {
+        if start > cursor.1 {
+            break;
+        }
+        match word {
+            ":" => enclosing = words.peek().map(|&(_, _, name)| name),
+            ";" => enclosing = None,
+            _ => {
+                if cursor.1 <= end {
+                    if let Some(index) = latest(word) {
+                        return Some(index);
+                    }
+                }
+            }
+        }
+    }
+    enclosing.and_then(latest)
+}
+
+// Human readable listing of a definition's instructions
+pub fn compiled_listing(forth: &Forth, index: usize) -> Vec<String> {
+    match forth.compiled_definition(index) {
+        Ok(instructions) => instructions
+            .iter()
+            .enumerate()
+            .map(|(address, instruction)| {
+                format!("{:04} {}", address, describe(forth, instruction))
+            })
+            .collect(),
+        Err(err) => vec![format!("Cannot compile: {}", err)],
+    }
+}
+
+fn describe(forth: &Forth, instruction: &Instruction) -> String {
+    match instruction {
+        Instruction::Number(value) => format!("PUSH {}", value),
+        Instruction::DoubleNumber => String::from("PUSH DOUBLE"),
+        Instruction::FloatNumber => String::from("PUSH FLOAT"),
+        Instruction::Add => String::from("ADD"),
+        Instruction::Subtract => String::from("SUB"),
+        Instruction::Multiply => String::from("MUL"),
+        Instruction::Divide => String::from("DIV"),
+        Instruction::Min => String::from("MIN"),
+        Instruction::Max => String::from("MAX"),
+        Instruction::Abs => String::from("ABS"),
+        Instruction::Negate => String::from("NEG"),
+        Instruction::Within => String::from("WITHIN"),
+        Instruction::Mod => String::from("MOD"),
+        Instruction::DivMod => String::from("DIVMOD"),
+        Instruction::StarSlash => String::from("MULDIV"),
+        Instruction::StarSlashMod => String::from("MULDIVMOD"),
+        Instruction::Dup => String::from("DUP"),
+        Instruction::Drop => String::from("DROP"),
+        Instruction::Over => String::from("OVER"),
+        Instruction::Swap => String::from("SWAP"),
+        Instruction::Rot => String::from("ROT"),
+        Instruction::MinusRot => String::from("-ROT"),
+        Instruction::Nip => String::from("NIP"),
+        Instruction::Tuck => String::from("TUCK"),
+        Instruction::TwoDup => String::from("2DUP"),
+        Instruction::TwoDrop => String::from("2DROP"),
+        Instruction::TwoSwap => String::from("2SWAP"),
+        Instruction::TwoOver => String::from("2OVER"),
+        Instruction::ToR => String::from(">R"),
+        Instruction::FromR => String::from("R>"),
+        Instruction::RFetch => String::from("R@"),
+        Instruction::TwoToR => String::from("2>R"),
+        Instruction::TwoFromR => String::from("2R>"),
+        Instruction::Pick => String::from("PICK"),
+        Instruction::Roll => String::from("ROLL"),
+        Instruction::QuestionDup => String::from("?DUP"),
+        Instruction::Cells => String::from("CELLS"),
+        Instruction::CellPlus => String::from("CELL+"),
+        Instruction::Chars => String::from("CHARS"),
+        Instruction::CharPlus => String::from("CHAR+"),
+        Instruction::Align => String::from("ALIGN"),
+        Instruction::Aligned => String::from("ALIGNED"),
+        Instruction::Count => String::from("COUNT"),
+        Instruction::Page => String::from("PAGE"),
+        Instruction::AtXy => String::from("AT-XY"),
+        Instruction::Random => String::from("RANDOM"),
+        Instruction::Key => String::from("KEY"),
+        Instruction::Accept => String::from("ACCEPT"),
+        Instruction::Here => String::from("HERE"),
+        Instruction::Allot => String::from("ALLOT"),
+        Instruction::Fetch => String::from("FETCH"),
+        Instruction::Store => String::from("STORE"),
+        Instruction::PlusStore => String::from("ADDSTORE"),
+        Instruction::CFetch => String::from("CFETCH"),
+        Instruction::CStore => String::from("CSTORE"),
+        Instruction::Comma => String::from("COMMA"),
+        Instruction::XcSize => String::from("XC-SIZE"),
+        Instruction::XcFetchPlus => String::from("XC@+"),
+        Instruction::XcStorePlus => String::from("XC!+"),
+        Instruction::XcharPlus => String::from("XCHAR+"),
+        Instruction::XSize => String::from("X-SIZE"),
+        Instruction::Xemit => String::from("XEMIT"),
+        Instruction::Dot => String::from("PRINT"),
+        Instruction::UDot => String::from("UPRINT"),
+        Instruction::DotS => String::from("PRINTSTACK"),
+        Instruction::Emit => String::from("EMIT"),
+        Instruction::Cr => String::from("CR"),
+        Instruction::Space => String::from("SPACE"),
+        Instruction::Spaces => String::from("SPACES"),
+        Instruction::Recurse => String::from("RECURSE"),
+        Instruction::Execute => String::from("EXECUTE"),
+        Instruction::Create => String::from("CREATE"),
+        Instruction::Does => String::from("DOES>"),
+        Instruction::CComma => String::from("C,"),
+        Instruction::Immediate => String::from("IMMEDIATE"),
+        Instruction::State => String::from("STATE"),
+        Instruction::DPlus => String::from("D+"),
+        Instruction::DMinus => String::from("D-"),
+        Instruction::DNegate => String::from("DNEGATE"),
+        Instruction::DDot => String::from("D."),
+        Instruction::SToD => String::from("S>D"),
+        Instruction::MStar => String::from("M*"),
+        Instruction::UmStar => String::from("UM*"),
+        Instruction::UmSlashMod => String::from("UM/MOD"),
+        Instruction::FPlus => String::from("F+"),
+        Instruction::FMinus => String::from("F-"),
+        Instruction::FStar => String::from("F*"),
+        Instruction::FSlash => String::from("F/"),
+        Instruction::FDot => String::from("F."),
+        Instruction::FDup => String::from("FDUP"),
+        Instruction::FSwap => String::from("FSWAP"),
+        Instruction::FDrop => String::from("FDROP"),
+        Instruction::FLess => String::from("F<"),
+        Instruction::Base => String::from("BASE"),
+        Instruction::Hex => String::from("HEX"),
+        Instruction::Decimal => String::from("DECIMAL"),
+        Instruction::LeftBracket => String::from("INTERPRET"),
+        Instruction::RightBracket => String::from("COMPILE"),
+        Instruction::Literal => String::from("LITERAL"),
+        Instruction::Postpone => String::from("POSTPONE"),
+        Instruction::Forget => String::from("FORGET"),
+        Instruction::Include => String::from("INCLUDE"),
+        Instruction::Included => String::from("INCLUDED"),
+        Instruction::ForthWordlist => String::from("FORTH-WORDLIST"),
+        Instruction::Wordlist => String::from("WORDLIST"),
+        Instruction::GetOrder => String::from("GET-ORDER"),
+        Instruction::SetOrder => String::from("SET-ORDER"),
+        Instruction::GetCurrent => String::from("GET-CURRENT"),
+        Instruction::SetCurrent => String::from("SET-CURRENT"),
+        Instruction::Definitions => String::from("DEFINITIONS"),
+        Instruction::Also => String::from("ALSO"),
+        Instruction::Only => String::from("ONLY"),
+        Instruction::Previous => String::from("PREVIOUS"),
+        Instruction::Forth => String::from("FORTH"),
+        Instruction::Type => String::from("TYPE"),
+        Instruction::Move => String::from("MOVE"),
+        Instruction::Fill => String::from("FILL"),
+        Instruction::Equal => String::from("EQ"),
+        Instruction::NotEqual => String::from("NE"),
+        Instruction::Less => String::from("LT"),
+        Instruction::Greater => String::from("GT"),
+        Instruction::LessOrEqual => String::from("LE"),
+        Instruction::GreaterOrEqual => String::from("GE"),
+        Instruction::ULess => String::from("ULT"),
+        Instruction::UGreater => String::from("UGT"),
+        Instruction::ZeroEqual => String::from("0EQ"),
+        Instruction::ZeroLess => String::from("0LT"),
+        Instruction::ZeroGreater => String::from("0GT"),
+        Instruction::And => String::from("AND"),
+        Instruction::Or => String::from("OR"),
+        Instruction::Xor => String::from("XOR"),
+        Instruction::Invert => String::from("INVERT"),
+        Instruction::LShift => String::from("LSHIFT"),
+        Instruction::RShift => String::from("RSHIFT"),
+        Instruction::TwoStar => String::from("2*"),
+        Instruction::TwoSlash => String::from("2/"),
+        Instruction::LoopIndex => String::from("I"),
+        Instruction::OuterLoopIndex => String::from("J"),
+        Instruction::CountedString => String::from("CSTRING"),
+        Instruction::String => String::from("STRING"),
+        Instruction::PrintString => String::from("PRINTSTRING"),
+        Instruction::AbortQuote => String::from("ABORT\""),
+        Instruction::Catch => String::from("CATCH"),
+        Instruction::Throw => String::from("THROW"),
+        Instruction::Abort => String::from("ABORT"),
+        Instruction::To => String::from("TO"),
+        Instruction::Tick => String::from("TICK"),
+        Instruction::Is => String::from("IS"),
+        Instruction::Begin => String::from("BEGIN"),
+        Instruction::Again => String::from("AGAIN"),
+        Instruction::Until => String::from("UNTIL"),
+        Instruction::While => String::from("WHILE"),
+        Instruction::Repeat => String::from("REPEAT"),
+        Instruction::If => String::from("IF"),
+        Instruction::Else => String::from("ELSE"),
+        Instruction::Then => String::from("THEN"),
+        Instruction::Do => String::from("DO"),
+        Instruction::Loop => String::from("LOOP"),
+        Instruction::PlusLoop => String::from("+LOOP"),
+        Instruction::Case => String::from("CASE"),
+        Instruction::Of => String::from("OF"),
+        Instruction::Endof => String::from("ENDOF"),
+        Instruction::Endcase => String::from("ENDCASE"),
+        Instruction::CallDefinition(index) => match forth.definitions.get(*index) {
+            Some(definition) => format!("CALL {} (#{})", definition.name, index),
+            None => format!("CALL #{}", index),
+        },
+    }
+}
+
+// Words of a line along with the columns (in characters) they span
+pub fn word_spans(line: &str) -> Vec<(usize, usize, &str)> {
+    let mut spans = Vec::new();
+    let mut start = None;
+    let chars = line
+        .char_indices()
+        .chain(std::iter::once((line.len(), ' ')));
+    for (column, (byte, c)) in chars.enumerate() {
+        match (c.is_whitespace(), start) {
+            (false, None) => start = Some((column, byte)),
+            (true, Some((start_column, start_byte))) => {
+                spans.push((start_column, column, &line[start_byte..byte]));
+                start = None;
+            }
+            _ => {}
+        }
+    }
+    spans
+}
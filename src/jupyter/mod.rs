@@ -0,0 +1,463 @@
+// A Jupyter kernel running Forth cells against a persistent interpreter.
+// Start it with `forth-tui jupyter CONNECTION_FILE` (`forth-tui jupyter --install`
+// registers the kernel spec doing so).
+// https://jupyter-client.readthedocs.io/en/stable/messaging.html
+
+mod hmac;
+mod zmtp;
+
+use forth_tui::{CancelToken, Forth};
+use serde_json::{json, Value};
+use std::env;
+use std::fs;
+use std::io;
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{SystemTime, UNIX_EPOCH};
+use zmtp::Message;
+
+const DELIMITER: &[u8] = b"<IDS|MSG>";
+const PROTOCOL_VERSION: &str = "5.3";
+
+// A request read from the shell or control socket, with the connection to reply on
+struct Request {
+    message: Message,
+    reply_to: Arc<Mutex<TcpStream>>,
+}
+
+// The key messages are signed with, if any
+#[derive(Clone)]
+struct Key(Vec<u8>);
+
+struct Kernel {
+    forth: Forth,
+    // cancelled by an interrupt request on the control channel, as soon as
+    // it is read, and reset before each cell
+    cancel_token: CancelToken,
+    execution_count: u64,
+    key: Key,
+    session: String,
+    message_count: u64,
+    // connected iopub subscribers, all receiving every published message
+    subscribers: Arc<Mutex<Vec<TcpStream>>>,
+}
+
+pub fn run(connection_file: &Path) -> io::Result<()> {
+    let invalid = || io::Error::new(io::ErrorKind::InvalidData, "invalid connection file");
+    let connection: Value =
+        serde_json::from_str(&fs::read_to_string(connection_file)?).map_err(|_| invalid())?;
+    let ip = connection
+        .get("ip")
+        .and_then(Value::as_str)
+        .unwrap_or("127.0.0.1");
+    let port = |name: &str| {
+        connection
+            .get(name)
+            .and_then(Value::as_u64)
+            .ok_or_else(invalid)
+    };
+    let address = |port: u64| format!("{}:{}", ip, port);
+
+    let key = Key(connection
+        .get("key")
+        .and_then(Value::as_str)
+        .unwrap_or("")
+        .as_bytes()
+        .to_vec());
+    let cancel_token = CancelToken::new();
+
+    let (requests, incoming) = mpsc::channel();
+    listen_requests(&address(port("shell_port")?), requests.clone(), None)?;
+    let interrupts = Some((key.clone(), cancel_token.clone()));
+    listen_requests(&address(port("control_port")?), requests, interrupts)?;
+    listen_silent(&address(port("stdin_port")?), "ROUTER")?;
+    listen_heartbeat(&address(port("hb_port")?))?;
+    let subscribers = listen_publisher(&address(port("iopub_port")?))?;
+
+    let mut kernel = Kernel {
+        forth: Forth::builder().cancel_token(cancel_token.clone()).build(),
+        cancel_token,
+        execution_count: 0,
+        key,
+        session: unique_id(0),
+        message_count: 0,
+        subscribers,
+    };
+    for request in incoming {
+        if !kernel.handle(request) {
+            break;
+        }
+    }
+    Ok(())
+}
+
+// Writes a kernel spec so that Jupyter lists this kernel
+pub fn install() -> io::Result<PathBuf> {
+    let data_dir = match env::var_os("JUPYTER_DATA_DIR") {
+        Some(dir) => PathBuf::from(dir),
+        None => env::var_os("HOME")
+            .map(|home| PathBuf::from(home).join(".local/share/jupyter"))
+            .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "no home directory"))?,
+    };
+    let dir = data_dir.join("kernels").join("forth-tui");
+    fs::create_dir_all(&dir)?;
+    let spec = json!({
+        "argv": [env::current_exe()?.to_string_lossy(), "jupyter", "{connection_file}"],
+        "display_name": "Forth (forth-tui)",
+        "language": "forth",
+    });
+    fs::write(dir.join("kernel.json"), spec.to_string())?;
+    Ok(dir)
+}
+
+impl Kernel {
+    // Returns false once the kernel was asked to shut down
+    fn handle(&mut self, request: Request) -> bool {
+        let Some(parts) = self.key.verified_parts(&request.message) else {
+            return true;
+        };
+        let header: Value = serde_json::from_slice(&parts[1]).unwrap_or(Value::Null);
+        let content: Value = serde_json::from_slice(&parts[4]).unwrap_or(Value::Null);
+        let msg_type = header.get("msg_type").and_then(Value::as_str).unwrap_or("");
+
+        self.publish("status", &header, json!({ "execution_state": "busy" }));
+        let mut running = true;
+        let reply = match msg_type {
+            "kernel_info_request" => Some(("kernel_info_reply", kernel_info())),
+            "execute_request" => Some(("execute_reply", self.execute(&header, &content))),
+            "is_complete_request" => Some(("is_complete_reply", is_complete(&content))),
+            "comm_info_request" => {
+                Some(("comm_info_reply", json!({ "status": "ok", "comms": {} })))
+            }
+            "interrupt_request" => Some(("interrupt_reply", json!({ "status": "ok" }))),
+            "shutdown_request" => {
+                running = false;
+                let restart = content
+                    .get("restart")
+                    .and_then(Value::as_bool)
+                    .unwrap_or(false);
+                Some((
+                    "shutdown_reply",
+                    json!({ "status": "ok", "restart": restart }),
+                ))
+            }
+            _ => None,
+        };
+        if let Some((reply_type, reply_content)) = reply {
+            let frames = self.message(reply_type, &header, reply_content);
+            if let Ok(mut stream) = request.reply_to.lock() {
+                // a client that went away just misses its reply
+                let _ = zmtp::write_message(&mut *stream, &frames);
+            }
+        }
+        self.publish("status", &header, json!({ "execution_state": "idle" }));
+        running
+    }
+
+    fn execute(&mut self, parent: &Value, content: &Value) -> Value {
+        let code = content.get("code").and_then(Value::as_str).unwrap_or("");
+        let silent = content
+            .get("silent")
+            .and_then(Value::as_bool)
+            .unwrap_or(false);
+        if !silent {
+            self.execution_count += 1;
+        }
+        let count = self.execution_count;
+        let input = json!({ "code": code, "execution_count": count });
+        self.publish("execute_input", parent, input);
+
+        self.cancel_token.reset();
+        self.forth.output_mut().clear();
+        let status = self.forth.eval(code);
+        let printed = self.forth.output().text();
+        if !printed.is_empty() {
+            let stream = json!({ "name": "stdout", "text": printed });
+            self.publish("stream", parent, stream);
+        }
+        match &status {
+            Ok(()) => {
+                if !silent {
+                    let result = json!({
+                        "execution_count": count,
+                        "data": {
+                            "text/plain": crate::stack_text(&self.forth),
+                            "text/html": stack_html(&self.forth),
+                        },
+                        "metadata": {},
+                    });
+                    self.publish("execute_result", parent, result);
+                }
+                json!({
+                    "status": "ok",
+                    "execution_count": count,
+                    "payload": [],
+                    "user_expressions": {},
+                })
+            }
+            Err(err) => {
+                let message = crate::status_message(&status);
+                let mut error = json!({
+                    "ename": format!("{:?}", err),
+                    "evalue": message,
+                    "traceback": [message],
+                });
+                self.publish("error", parent, error.clone());
+                error["status"] = json!("error");
+                error["execution_count"] = json!(count);
+                error
+            }
+        }
+    }
+
+    // Frames of a message, from the delimiter on
+    fn message(&mut self, msg_type: &str, parent: &Value, content: Value) -> Message {
+        self.message_count += 1;
+        let header = json!({
+            "msg_id": unique_id(self.message_count),
+            "session": self.session,
+            "username": "kernel",
+            "date": iso8601_now(),
+            "msg_type": msg_type,
+            "version": PROTOCOL_VERSION,
+        });
+        let parts: Vec<Vec<u8>> = [header, parent.clone(), json!({}), content]
+            .iter()
+            .map(|part| part.to_string().into_bytes())
+            .collect();
+        let mut frames = vec![DELIMITER.to_vec(), self.key.sign(&parts).into_bytes()];
+        frames.extend(parts);
+        frames
+    }
+
+    fn publish(&mut self, msg_type: &str, parent: &Value, content: Value) {
+        let mut frames = vec![format!("kernel.{}", msg_type).into_bytes()];
+        frames.extend(self.message(msg_type, parent, content));
+        if let Ok(mut subscribers) = self.subscribers.lock() {
+            subscribers.retain_mut(|stream| zmtp::write_message(stream, &frames).is_ok());
+        }
+    }
+}
+
+impl Key {
+    // The frames of a message after the delimiter, if they are all there
+    // and signed with the key
+    fn verified_parts<'a>(&self, message: &'a Message) -> Option<&'a [Vec<u8>]> {
+        let position = message.iter().position(|f| f == DELIMITER)?;
+        let parts = &message[position + 1..];
+        (parts.len() >= 5 && self.verify(parts)).then_some(parts)
+    }
+
+    fn verify(&self, parts: &[Vec<u8>]) -> bool {
+        let signed: Vec<&[u8]> = parts[1..5].iter().map(Vec::as_slice).collect();
+        self.0.is_empty() || hmac::verify(&self.0, &signed, &parts[0])
+    }
+
+    fn sign(&self, parts: &[Vec<u8>]) -> String {
+        if self.0.is_empty() {
+            return String::new();
+        }
+        let parts: Vec<&[u8]> = parts.iter().map(Vec::as_slice).collect();
+        hmac::hex(&hmac::hmac_sha256(&self.0, &parts))
+    }
+
+    // Whether the message is an interrupt request signed with the key
+    fn is_interrupt(&self, message: &Message) -> bool {
+        let Some(parts) = self.verified_parts(message) else {
+            return false;
+        };
+        let header: Value = serde_json::from_slice(&parts[1]).unwrap_or(Value::Null);
+        header.get("msg_type").and_then(Value::as_str) == Some("interrupt_request")
+    }
+}
+
+fn kernel_info() -> Value {
+    json!({
+        "status": "ok",
+        "protocol_version": PROTOCOL_VERSION,
+        "implementation": "forth-tui",
+        "implementation_version": env!("CARGO_PKG_VERSION"),
+        "language_info": {
+            "name": "forth",
+            "version": "",
+            "mimetype": "text/x-forth",
+            "file_extension": ".fth",
+        },
+        "banner": "Forth TUI kernel",
+        "help_links": [],
+    })
+}
+
+// A cell is incomplete while a definition is left open
+fn is_complete(content: &Value) -> Value {
+    let code = content.get("code").and_then(Value::as_str).unwrap_or("");
+    let mut open = false;
+    for word in code.split_whitespace() {
+        match word {
+            ":" => open = true,
+            ";" => open = false,
+            _ => {}
+        }
+    }
+    let status = if open { "incomplete" } else { "complete" };
+    json!({ "status": status, "indent": "" })
+}
+
+fn stack_html(forth: &Forth) -> String {
+    let cells: String = forth
+        .stack()
+        .iter()
+        .map(|v| format!("<td style=\"border:1px solid;padding:2px 8px\">{}</td>", v))
+        .collect();
+    format!(
+        "<table><tr><th style=\"padding-right:8px\">stack ({})</th>{}</tr></table>",
+        forth.stack().len(),
+        cells
+    )
+}
+
+// Requests are handled in turn by the kernel. Interrupts, given the key and
+// the token to cancel, cancel the cell running as soon as they are read, the
+// kernel only replying to them once the cell stopped.
+type Interrupts = Option<(Key, CancelToken)>;
+
+fn listen_requests(
+    address: &str,
+    requests: Sender<Request>,
+    interrupts: Interrupts,
+) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let requests = requests.clone();
+            let interrupts = interrupts.clone();
+            thread::spawn(move || forward_requests(stream, requests, interrupts));
+        }
+    });
+    Ok(())
+}
+
+fn forward_requests(
+    mut stream: TcpStream,
+    requests: Sender<Request>,
+    interrupts: Interrupts,
+) -> io::Result<()> {
+    zmtp::handshake(&mut stream, "ROUTER")?;
+    let reply_to = Arc::new(Mutex::new(stream.try_clone()?));
+    loop {
+        let message = zmtp::read_message(&mut stream)?;
+        if let Some((key, cancel_token)) = &interrupts {
+            if key.is_interrupt(&message) {
+                cancel_token.cancel();
+            }
+        }
+        let reply_to = reply_to.clone();
+        if requests.send(Request { message, reply_to }).is_err() {
+            return Ok(());
+        }
+    }
+}
+
+// Heartbeats are echoed back as they come
+fn listen_heartbeat(address: &str) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            thread::spawn(move || echo(stream));
+        }
+    });
+    Ok(())
+}
+
+fn echo(mut stream: TcpStream) -> io::Result<()> {
+    zmtp::handshake(&mut stream, "REP")?;
+    loop {
+        let message = zmtp::read_message(&mut stream)?;
+        zmtp::write_message(&mut stream, &message)?;
+    }
+}
+
+// Subscriptions are ignored: every subscriber gets every message
+fn listen_publisher(address: &str) -> io::Result<Arc<Mutex<Vec<TcpStream>>>> {
+    let listener = TcpListener::bind(address)?;
+    let subscribers = Arc::new(Mutex::new(Vec::new()));
+    let shared = subscribers.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming().flatten() {
+            let subscribers = shared.clone();
+            thread::spawn(move || subscribe(stream, subscribers));
+        }
+    });
+    Ok(subscribers)
+}
+
+fn subscribe(mut stream: TcpStream, subscribers: Arc<Mutex<Vec<TcpStream>>>) -> io::Result<()> {
+    zmtp::handshake(&mut stream, "PUB")?;
+    if let Ok(mut subscribers) = subscribers.lock() {
+        subscribers.push(stream.try_clone()?);
+    }
+    discard(stream)
+}
+
+// The stdin channel is only needed for input requests, which Forth cells don't make
+fn listen_silent(address: &str, socket_type: &'static str) -> io::Result<()> {
+    let listener = TcpListener::bind(address)?;
+    thread::spawn(move || {
+        for mut stream in listener.incoming().flatten() {
+            thread::spawn(move || {
+                zmtp::handshake(&mut stream, socket_type)?;
+                discard(stream)
+            });
+        }
+    });
+    Ok(())
+}
+
+fn discard(mut stream: TcpStream) -> io::Result<()> {
+    loop {
+        zmtp::read_message(&mut stream)?;
+    }
+}
+
+fn unique_id(counter: u64) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_nanos());
+    format!("{:032x}-{}-{}", nanos, std::process::id(), counter)
+}
+
+fn iso8601_now() -> String {
+    let now = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    let seconds = now.as_secs();
+    let (year, month, day) = civil_from_days((seconds / 86400) as i64);
+    let time = seconds % 86400;
+    format!(
+        "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}.{:06}Z",
+        year,
+        month,
+        day,
+        time / 3600,
+        time % 3600 / 60,
+        time % 60,
+        now.subsec_micros()
+    )
+}
+
+// Gregorian date of a number of days since 1970-01-01 (Howard Hinnant's algorithm)
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z.rem_euclid(146097);
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
@@ -0,0 +1,167 @@
+use std::io::{self, Read, Write};
+
+// ZMTP 3.0 with the NULL security mechanism: the wire protocol spoken by ZeroMQ
+// sockets, which Jupyter clients use to talk to kernels.
+// https://rfc.zeromq.org/spec/23/
+
+const FLAG_MORE: u8 = 0x01;
+const FLAG_LONG: u8 = 0x02;
+const FLAG_COMMAND: u8 = 0x04;
+
+// Limits on what a peer may send, checked before anything is allocated for it.
+// Cells, and the replies to them, are far smaller.
+const MAX_FRAME_SIZE: usize = 16 << 20;
+const MAX_MESSAGE_SIZE: usize = 64 << 20;
+
+pub type Message = Vec<Vec<u8>>;
+
+// Exchanges greetings and READY commands, announcing our socket type
+pub fn handshake(stream: &mut (impl Read + Write), socket_type: &str) -> io::Result<()> {
+    let mut greeting = [0u8; 64];
+    greeting[0] = 0xFF;
+    greeting[9] = 0x7F;
+    greeting[10] = 3;
+    greeting[12..16].copy_from_slice(b"NULL");
+    stream.write_all(&greeting)?;
+
+    let mut peer = [0u8; 64];
+    stream.read_exact(&mut peer)?;
+    if peer[0] != 0xFF || peer[9] != 0x7F || peer[10] < 3 || &peer[12..16] != b"NULL" {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "unsupported ZMTP peer",
+        ));
+    }
+
+    let mut ready = vec![5];
+    ready.extend_from_slice(b"READY");
+    ready.push(11);
+    ready.extend_from_slice(b"Socket-Type");
+    ready.extend_from_slice(&(socket_type.len() as u32).to_be_bytes());
+    ready.extend_from_slice(socket_type.as_bytes());
+    write_frame(stream, &ready, FLAG_COMMAND)?;
+
+    // the peer's READY (or an ERROR) comes back as a command frame
+    let (flags, _) = read_frame(stream)?;
+    if flags & FLAG_COMMAND == 0 {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            "expected a READY command",
+        ));
+    }
+    Ok(())
+}
+
+// Reads the next multi-part message, skipping commands
+pub fn read_message(stream: &mut impl Read) -> io::Result<Message> {
+    let mut frames = Vec::new();
+    let mut size = 0;
+    loop {
+        let (flags, body) = read_frame(stream)?;
+        if flags & FLAG_COMMAND != 0 {
+            continue;
+        }
+        size += body.len();
+        if size > MAX_MESSAGE_SIZE {
+            return Err(too_large("message"));
+        }
+        frames.push(body);
+        if flags & FLAG_MORE == 0 {
+            return Ok(frames);
+        }
+    }
+}
+
+pub fn write_message(stream: &mut impl Write, frames: &[Vec<u8>]) -> io::Result<()> {
+    let mut bytes = Vec::new();
+    for (i, frame) in frames.iter().enumerate() {
+        let more = if i + 1 < frames.len() { FLAG_MORE } else { 0 };
+        encode_frame(&mut bytes, frame, more);
+    }
+    stream.write_all(&bytes)
+}
+
+fn write_frame(stream: &mut impl Write, body: &[u8], flags: u8) -> io::Result<()> {
+    let mut bytes = Vec::new();
+    encode_frame(&mut bytes, body, flags);
+    stream.write_all(&bytes)
+}
+
+fn encode_frame(bytes: &mut Vec<u8>, body: &[u8], flags: u8) {
+    if body.len() > 255 {
+        bytes.push(flags | FLAG_LONG);
+        bytes.extend_from_slice(&(body.len() as u64).to_be_bytes());
+    } else {
+        bytes.push(flags);
+        bytes.push(body.len() as u8);
+    }
+    bytes.extend_from_slice(body);
+}
+
+fn read_frame(stream: &mut impl Read) -> io::Result<(u8, Vec<u8>)> {
+    let mut flags = [0u8; 1];
+    stream.read_exact(&mut flags)?;
+    let size = if flags[0] & FLAG_LONG != 0 {
+        let mut size = [0u8; 8];
+        stream.read_exact(&mut size)?;
+        usize::try_from(u64::from_be_bytes(size)).unwrap_or(usize::MAX)
+    } else {
+        let mut size = [0u8; 1];
+        stream.read_exact(&mut size)?;
+        size[0] as usize
+    };
+    if size > MAX_FRAME_SIZE {
+        return Err(too_large("frame"));
+    }
+    let mut body = vec![0u8; size];
+    stream.read_exact(&mut body)?;
+    Ok((flags[0], body))
+}
+
+fn too_large(what: &str) -> io::Error {
+    io::Error::new(
+        io::ErrorKind::InvalidData,
+        format!("ZMTP {} too large", what),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::os::unix::net::UnixStream;
+    use std::thread;
+
+    #[test]
+    fn peers_greet_each_other_and_exchange_messages() {
+        let (mut kernel, mut client) = UnixStream::pair().unwrap();
+        let peer = thread::spawn(move || {
+            handshake(&mut client, "DEALER").unwrap();
+            write_message(&mut client, &[b"short".to_vec(), vec![7; 300]]).unwrap();
+            read_message(&mut client).unwrap()
+        });
+        handshake(&mut kernel, "ROUTER").unwrap();
+        let message = read_message(&mut kernel).unwrap();
+        assert_eq!(message, [b"short".to_vec(), vec![7; 300]]);
+        write_message(&mut kernel, &[Vec::new()]).unwrap();
+        assert_eq!(peer.join().unwrap(), [Vec::<u8>::new()]);
+    }
+
+    #[test]
+    fn oversized_frames_are_refused_before_being_read() {
+        let mut bytes = vec![FLAG_LONG];
+        bytes.extend_from_slice(&u64::MAX.to_be_bytes());
+        let error = read_message(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn messages_are_limited_in_total() {
+        let frame = vec![0; MAX_FRAME_SIZE];
+        let mut bytes = Vec::new();
+        for _ in 0..MAX_MESSAGE_SIZE / MAX_FRAME_SIZE + 1 {
+            encode_frame(&mut bytes, &frame, FLAG_MORE);
+        }
+        let error = read_message(&mut bytes.as_slice()).unwrap_err();
+        assert_eq!(error.kind(), io::ErrorKind::InvalidData);
+    }
+}
@@ -0,0 +1,57 @@
+use crossterm::event::KeyEvent;
+
+const MAX_COUNT: usize = 999;
+
+// Records the keystrokes sent to the editor so they can be replayed later,
+// in the spirit of vim's q/@ macros
+#[derive(Default)]
+pub struct MacroRecorder {
+    recording: Option<Vec<KeyEvent>>,
+    last_macro: Vec<KeyEvent>,
+    // repeat count typed in the menu before playing
+    count: Option<usize>,
+}
+
+impl MacroRecorder {
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
+    }
+
+    pub fn has_macro(&self) -> bool {
+        !self.last_macro.is_empty()
+    }
+
+    pub fn count(&self) -> Option<usize> {
+        self.count
+    }
+
+    pub fn start(&mut self) {
+        self.recording = Some(Vec::new());
+    }
+
+    // Keeps the previous macro if nothing was recorded
+    pub fn stop(&mut self) {
+        if let Some(keys) = self.recording.take() {
+            if !keys.is_empty() {
+                self.last_macro = keys;
+            }
+        }
+    }
+
+    pub fn record(&mut self, key: KeyEvent) {
+        if let Some(keys) = self.recording.as_mut() {
+            keys.push(key);
+        }
+    }
+
+    pub fn push_count_digit(&mut self, digit: u32) {
+        let count = self.count.unwrap_or(0) * 10 + digit as usize;
+        self.count = Some(count.min(MAX_COUNT));
+    }
+
+    // Keystrokes to replay, repeated as many times as the typed count
+    pub fn play(&mut self) -> Vec<KeyEvent> {
+        let times = self.count.take().unwrap_or(1);
+        self.last_macro.repeat(times)
+    }
+}
@@ -1,32 +1,239 @@
-use crossterm::event::{DisableMouseCapture, EnableMouseCapture, Event, KeyCode};
+mod calculator;
+mod cli;
+mod config;
+mod daemon;
+mod dap;
+mod definitions_view;
+mod evaluator;
+mod file_prompt;
+mod folding;
+mod hover;
+mod indent;
+mod inspector;
+#[cfg(feature = "jupyter")]
+mod jupyter;
+mod macro_recorder;
+mod navigation;
+mod persistence;
+mod project;
+mod session;
+mod snippets;
+mod stack_edit;
+mod terminal_events;
+
+use calculator::Calculator;
+use config::Config;
+use crossterm::event::{
+    DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyModifiers, MouseButton,
+    MouseEvent, MouseEventKind,
+};
 use crossterm::terminal::{
     disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen,
 };
-use forth_tui::{Forth, ForthResult};
-use std::io::{self, StdoutLock};
+use daemon::Remote;
+use definitions_view::{DefinitionsView, Row};
+use evaluator::{Evaluator, Request};
+use file_prompt::{FileAction, FilePrompt, MAX_CANDIDATES};
+use folding::Folds;
+use forth_tui::{
+    glossary, CancelToken, CaseMode, DefinitionKind, Forth, ForthResult, Location, OverflowMode,
+    Strictness, TestFailure, TestOutcome, Token,
+};
+use hover::Tooltip;
+use macro_recorder::MacroRecorder;
+use navigation::JumpStack;
+use persistence::SavedWords;
+use project::Project;
+use snippets::{Snippet, TabStops};
+use stack_edit::{StackEdit, StackPrompt};
+use std::env;
+use std::fs;
+use std::io::{self, IsTerminal, StdoutLock};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
 use tui::backend::{Backend, CrosstermBackend};
-use tui::layout::{Alignment, Constraint, Direction, Layout};
+use tui::layout::{Alignment, Constraint, Direction, Layout, Rect};
 use tui::style::{Color, Modifier, Style};
-use tui::text::Spans;
-use tui::widgets::{Block, Borders, Paragraph, Widget};
+use tui::text::{Span, Spans};
+use tui::widgets::{Block, Borders, Clear, Paragraph, Widget, Wrap};
 use tui::Frame;
 use tui::Terminal;
-use tui_textarea::TextArea;
+use tui_textarea::{CursorMove, TextArea};
+
+const GLOSSARY_FILE: &str = "GLOSSARY.md";
+
+// Lines of program output shown, unless the scrollback setting says otherwise
+const DEFAULT_SCROLLBACK: usize = 1000;
+
+// Steps an evaluation of the editor's code can take, a fifth of a second or
+// so, for a program looping forever not to freeze the app, unless the fuel
+// setting says otherwise
+const DEFAULT_FUEL: u64 = 10_000_000;
+
+// Shown while an evaluation runs, a frame every SPINNER_INTERVAL. The result
+// of an evaluation done within QUICK_EVALUATION shows without it.
+const SPINNER: [char; 8] = ['⣾', '⣽', '⣻', '⢿', '⡿', '⣟', '⣯', '⣷'];
+const SPINNER_INTERVAL: Duration = Duration::from_millis(80);
+const QUICK_EVALUATION: Duration = Duration::from_millis(20);
+
+// Typing evaluates the code once no key has been pressed for this long
+const DEBOUNCE: Duration = Duration::from_millis(150);
+
+// Unknown words, failing or skipped
+const WARNING_COLOR: Color = Color::Rgb(255, 164, 76);
 
 // App holds the state of the application
 struct App {
     // Forth evaluator
     pub forth: Forth,
     pub code_status: ForthResult,
+    // row, column and width in the editor of the words the code failed at
+    pub error_words: Vec<(usize, usize, usize)>,
+    // and of the unknown words skipped in tolerant mode
+    pub warning_words: Vec<(usize, usize, usize)>,
     pub input_mode: InputMode,
+    // corrections made from the stack pane, replayed after each evaluation
+    pub stack_edits: Vec<StackEdit>,
+    // base chosen from the stack pane, set as BASE after each evaluation
+    pub stack_base: Option<u32>,
+    pub stack_selection: usize,
+    pub stack_prompt: Option<StackPrompt>,
+    // stack cell being dragged with the mouse, and where it would be dropped
+    pub stack_drag: Option<(usize, usize)>,
+    // last rendered area of the stack pane, used to locate mouse events
+    pub stack_area: Rect,
+    // last rendered area of the editor and its scrolling, to find the word under the mouse
+    pub editor_area: Rect,
+    pub editor_scroll: (u16, u16),
+    pub output_height: u16,
+    pub tooltip: Option<Tooltip>,
+    // the prelude file named in the config, by name, with its source
+    pub user_prelude: Option<(String, String)>,
+    // the words kept in the user's dictionary, and its source at launch
+    pub saved_words: SavedWords,
+    pub dictionary: String,
+    // definitions reloaded from a previous session, evaluated before the editor content
+    pub prelude: String,
+    pub autosave: bool,
+    // show where each stack value came from
+    pub provenance: bool,
+    // what arithmetic does past the largest or smallest cell
+    pub overflow_mode: OverflowMode,
+    // whether unknown words fail the code or are skipped
+    pub strictness: Strictness,
+    // how the names of definitions are kept and found
+    pub case_mode: CaseMode,
+    // steps each evaluation can take
+    pub fuel: u64,
+    // INCLUDE reading files outside of the root too
+    pub include_anywhere: bool,
+    // lines of program output the Output pane keeps
+    pub scrollback: usize,
+    // lines the Output pane is scrolled up from its latest line
+    pub output_scroll: usize,
+    pub saved_source: String,
+    // where the definitions saved start: those reloaded, then the code's
+    pub saved_from: usize,
+    // saved definitions found at startup, waiting for the user to accept them
+    pub pending_reload: Option<(String, usize)>,
+    pub macro_recorder: MacroRecorder,
+    pub folds: Folds,
+    pub calculator: Calculator,
+    pub definitions_view: DefinitionsView,
+    pub snippets: Vec<Snippet>,
+    pub snippet_selection: usize,
+    // results of the last run of the inline tests
+    pub test_outcomes: Vec<TestOutcome>,
+    pub test_selection: usize,
+    // places left to fill in the last inserted snippet
+    pub tab_stops: TabStops,
+    pub jumps: JumpStack,
+    // one-off feedback shown in the footer until the next key press
+    pub notice: Option<String>,
+    // directory holding the saved definitions: the project root, or the current directory
+    pub root: PathBuf,
+    pub project: Option<Project>,
+    pub file_selection: usize,
+    // relative to the root, unless typed as an absolute path
+    pub current_file: Option<PathBuf>,
+    // from --readonly: the code can be run and looked at, not changed
+    pub readonly: bool,
+    // the current file's code as it was last read or written
+    pub saved_code: String,
+    pub file_prompt: Option<FilePrompt>,
+    // session of a daemon the app is attached to, evaluating the code instead of `forth`
+    pub remote: Option<Remote>,
+    // cancelled by the stop key to interrupt the running evaluation
+    pub cancel_token: CancelToken,
+    // runs the evaluations of the editor's code
+    pub evaluator: Evaluator,
+    pub spinner_frame: usize,
+    // when the code typed is to be evaluated, if it changed
+    pub evaluate_at: Option<Instant>,
+    // the code of the latest evaluation, prelude aside
+    pub evaluated_code: String,
 }
 
 impl Default for App {
     fn default() -> App {
+        let cancel_token = CancelToken::new();
         App {
             forth: Forth::new(),
             code_status: Ok(()),
+            error_words: Vec::new(),
+            warning_words: Vec::new(),
             input_mode: InputMode::Edit,
+            stack_edits: Vec::new(),
+            stack_base: None,
+            stack_selection: 0,
+            stack_prompt: None,
+            stack_drag: None,
+            stack_area: Rect::default(),
+            editor_area: Rect::default(),
+            editor_scroll: (0, 0),
+            output_height: 0,
+            tooltip: None,
+            user_prelude: None,
+            saved_words: SavedWords::default(),
+            dictionary: String::new(),
+            prelude: String::new(),
+            autosave: false,
+            provenance: false,
+            overflow_mode: OverflowMode::default(),
+            strictness: Strictness::default(),
+            case_mode: CaseMode::default(),
+            fuel: DEFAULT_FUEL,
+            include_anywhere: false,
+            scrollback: DEFAULT_SCROLLBACK,
+            output_scroll: 0,
+            saved_source: String::new(),
+            saved_from: 0,
+            pending_reload: None,
+            macro_recorder: MacroRecorder::default(),
+            folds: Folds::default(),
+            calculator: Calculator::default(),
+            definitions_view: DefinitionsView::default(),
+            snippets: Vec::new(),
+            snippet_selection: 0,
+            test_outcomes: Vec::new(),
+            test_selection: 0,
+            tab_stops: TabStops::default(),
+            jumps: JumpStack::default(),
+            notice: None,
+            root: PathBuf::from("."),
+            project: None,
+            file_selection: 0,
+            current_file: None,
+            readonly: false,
+            saved_code: String::new(),
+            file_prompt: None,
+            remote: None,
+            evaluator: Evaluator::spawn(cancel_token.clone(), None),
+            cancel_token,
+            spinner_frame: 0,
+            evaluate_at: None,
+            evaluated_code: String::new(),
         }
     }
 }
@@ -35,22 +242,706 @@ impl App {
     fn toggle_input_mode(&mut self) {
         self.input_mode = match self.input_mode {
             InputMode::Edit => InputMode::Menu,
-            InputMode::Menu => InputMode::Edit,
+            InputMode::Menu
+            | InputMode::Stack
+            | InputMode::Calculator
+            | InputMode::Files
+            | InputMode::FilePrompt
+            | InputMode::Definitions
+            | InputMode::Snippets
+            | InputMode::Tests => InputMode::Edit,
+        }
+    }
+
+    fn open_selected_file(&mut self, textarea: &mut TextArea) {
+        let file = match &self.project {
+            Some(project) => match project.files.get(self.file_selection) {
+                Some(file) => file.clone(),
+                None => return,
+            },
+            None => return,
+        };
+        if let Err(err) = self.open_file(textarea, &file) {
+            self.notice = Some(format!("Cannot open {}: {}", file.display(), err));
+        }
+    }
+
+    fn open_file(&mut self, textarea: &mut TextArea, file: &Path) -> io::Result<()> {
+        let source = fs::read_to_string(self.root.join(file))?;
+        self.show_file(textarea, file, &source);
+        self.evaluate(textarea);
+        Ok(())
+    }
+
+    // Puts the source of a file in the editor
+    fn show_file(&mut self, textarea: &mut TextArea, file: &Path, source: &str) {
+        *textarea = TextArea::new(source.lines().map(String::from).collect());
+        self.folds = Folds::default();
+        self.current_file = Some(file.to_path_buf());
+        self.saved_code = textarea.lines().join("\n");
+        // folded as it was when last left
+        let names = folding::load_folds(&self.root, file);
+        if !names.is_empty() {
+            let lines = self
+                .folds
+                .fold_names(textarea.lines(), &names, self.case_mode);
+            *textarea = TextArea::new(lines);
+        }
+    }
+
+    // Folds or unfolds the definition on the cursor's line, keeping the
+    // current file's folds for the next time it is opened
+    fn toggle_fold(&mut self, textarea: &mut TextArea) {
+        let (row, _) = textarea.cursor();
+        let Some(lines) = self.folds.toggle(textarea.lines(), row, self.case_mode) else {
+            return;
+        };
+        *textarea = TextArea::new(lines);
+        textarea.move_cursor(CursorMove::Jump(row as u16, 0));
+        self.evaluate(textarea);
+        if let Some(file) = &self.current_file {
+            if let Err(err) = folding::save_folds(&self.root, file, self.folds.names()) {
+                self.notice = Some(format!("Cannot save the folds: {}", err));
+            }
         }
     }
+
+    // Edits the code, unless the edit changes the line of a folded
+    // definition, which would lose the lines it hides
+    fn edit_code(&mut self, textarea: &mut TextArea, edit: impl FnOnce(&mut App, &mut TextArea)) {
+        let before = self
+            .folds
+            .are_shown(textarea.lines())
+            .then(|| (textarea.lines().to_vec(), textarea.cursor()));
+        edit(self, textarea);
+        if let Some((lines, (row, column))) = before {
+            if !self.folds.are_shown(textarea.lines()) {
+                *textarea = TextArea::new(lines);
+                textarea.move_cursor(CursorMove::Jump(row as u16, column as u16));
+                self.notice = Some(String::from("Unfold the definition to edit it"));
+                return;
+            }
+        }
+        self.schedule_evaluation();
+    }
+
+    fn insert_snippet(&mut self, textarea: &mut TextArea) {
+        let Some(snippet) = self.snippets.get(self.snippet_selection) else {
+            return;
+        };
+        let (row, column) = textarea.cursor();
+        let indent: String = textarea.lines()[row]
+            .chars()
+            .take_while(|c| c.is_whitespace())
+            .collect();
+        let (lines, stops) = snippet.expand(&indent);
+        for (index, line) in lines.into_iter().enumerate() {
+            if index > 0 {
+                textarea.insert_newline();
+            }
+            textarea.insert_str(line);
+        }
+        let stops = stops
+            .into_iter()
+            .map(|(line, at)| {
+                if line == 0 {
+                    (row, column + at)
+                } else {
+                    (row + line, at)
+                }
+            })
+            .collect();
+        self.tab_stops = TabStops::new(stops);
+        self.next_tab_stop(textarea);
+        self.evaluate(textarea);
+    }
+
+    fn next_tab_stop(&mut self, textarea: &mut TextArea) {
+        if let Some((row, column)) = self.tab_stops.next(textarea.lines()) {
+            textarea.move_cursor(CursorMove::Jump(row as u16, column as u16));
+        }
+    }
+
+    // Runs the inline tests, selecting the first failure, then evaluates the
+    // code again to undo whatever the tests changed
+    fn run_tests(&mut self, textarea: &TextArea) {
+        self.test_outcomes = self.forth.run_tests();
+        self.test_selection = self
+            .test_outcomes
+            .iter()
+            .position(|outcome| outcome.result.is_err())
+            .unwrap_or(0);
+        self.evaluate(textarea);
+    }
+
+    // Moves the cursor to the `TEST:` of the selected test, the tests of the
+    // prelude coming first
+    fn go_to_selected_test(&mut self, textarea: &mut TextArea) {
+        let prelude: Vec<String> = self.prelude.lines().map(String::from).collect();
+        let index = self
+            .test_selection
+            .checked_sub(navigation::test_positions(&prelude).len());
+        let position = index.and_then(|index| {
+            navigation::test_positions(textarea.lines())
+                .get(index)
+                .copied()
+        });
+        match position {
+            Some((row, column)) => {
+                self.jumps.push(textarea.cursor());
+                textarea.move_cursor(CursorMove::Jump(row as u16, column as u16));
+            }
+            None => self.notice = Some(String::from("This test is not in the editor")),
+        }
+    }
+
+    fn hover(&mut self, textarea: &TextArea, column: u16, row: u16) {
+        self.tooltip = None;
+        // inside the editor's borders
+        let area = self.editor_area;
+        if column <= area.x
+            || column + 1 >= area.right()
+            || row <= area.y
+            || row + 1 >= area.bottom()
+        {
+            return;
+        }
+        let position = (
+            (row - area.y - 1 + self.editor_scroll.0) as usize,
+            (column - area.x - 1 + self.editor_scroll.1) as usize,
+        );
+        let Some(word) = hover::word_under(textarea.lines(), position) else {
+            return;
+        };
+        let source = self.folds.expand(textarea.lines()).join("\n");
+        let source = format!("{}\n{}", self.prelude, source);
+        if let Some(lines) = hover::tooltip(&self.forth, &source, word) {
+            self.tooltip = Some(Tooltip { column, row, lines });
+        }
+    }
+
+    // Breaks the line, re-indenting it now that its first word is known and
+    // indenting the new line for the structures left open
+    fn insert_indented_newline(&mut self, textarea: &mut TextArea) {
+        let (row, column) = textarea.cursor();
+        let depth = textarea.lines()[..row]
+            .iter()
+            .fold(0, |depth, line| indent::depth_after(depth, line));
+        let line = textarea.lines()[row].clone();
+        let level = indent::line_level(depth, &line);
+        let leading = line.chars().take_while(|c| c.is_whitespace()).count();
+        let before: String = line.chars().take(column).collect();
+        let depth = indent::depth_after(depth, &before);
+
+        textarea.move_cursor(CursorMove::Head);
+        for _ in 0..leading {
+            textarea.delete_next_char();
+        }
+        textarea.insert_str(indent::INDENT.repeat(level));
+        let column = level * indent::INDENT.len() + column.saturating_sub(leading);
+        textarea.move_cursor(CursorMove::Jump(row as u16, column as u16));
+        textarea.insert_newline();
+        // the text moved to the new line comes without its spaces
+        while textarea.lines()[row + 1].starts_with(char::is_whitespace) {
+            textarea.delete_next_char();
+        }
+        textarea.insert_str(indent::INDENT.repeat(depth));
+    }
+
+    fn go_to_definition(&mut self, textarea: &mut TextArea) {
+        let cursor = textarea.cursor();
+        let Some(word) = navigation::word_at(textarea.lines(), cursor) else {
+            return;
+        };
+        match navigation::definition_position(textarea.lines(), word, cursor) {
+            Some((row, column)) => {
+                self.jumps.push(cursor);
+                textarea.move_cursor(CursorMove::Jump(row as u16, column as u16));
+            }
+            None => self.notice = Some(format!("{} is not defined in this buffer", word)),
+        }
+    }
+
+    fn jump_back(&mut self, textarea: &mut TextArea) {
+        if let Some((row, column)) = self.jumps.pop() {
+            textarea.move_cursor(CursorMove::Jump(row as u16, column as u16));
+        }
+    }
+
+    // Asks where to save the code when it isn't from a file
+    fn save_current_file(&mut self, textarea: &TextArea) {
+        let Some(file) = self.current_file.clone() else {
+            self.file_prompt = Some(FilePrompt::new(FileAction::Save));
+            self.input_mode = InputMode::FilePrompt;
+            return;
+        };
+        self.notice = Some(match self.save_file(textarea, &file) {
+            Ok(()) => format!("Saved {}", file.display()),
+            Err(err) => format!("Cannot save {}: {}", file.display(), err),
+        });
+    }
+
+    fn save_file(&mut self, textarea: &TextArea, file: &Path) -> io::Result<()> {
+        let code = self.folds.expand(textarea.lines()).join("\n");
+        fs::write(self.root.join(file), format!("{}\n", code))?;
+        self.current_file = Some(file.to_path_buf());
+        self.saved_code = code;
+        // a new file joins the sidebar
+        if let Some(project) = &self.project {
+            if !project.files.iter().any(|listed| listed == file) {
+                self.project = Project::open(&project.root).ok();
+            }
+        }
+        Ok(())
+    }
+
+    // Opens or saves to the file typed, staying in the prompt if it fails
+    fn submit_file_prompt(&mut self, textarea: &mut TextArea) {
+        let Some(prompt) = &self.file_prompt else {
+            return;
+        };
+        let (action, file) = (prompt.action, PathBuf::from(prompt.input.trim()));
+        if file.as_os_str().is_empty() {
+            return;
+        }
+        let result = match action {
+            FileAction::Open => self.open_file(textarea, &file),
+            FileAction::Save => self.save_file(textarea, &file),
+        };
+        match result {
+            Ok(()) => {
+                let done = match action {
+                    FileAction::Open => "Opened",
+                    FileAction::Save => "Saved",
+                };
+                self.notice = Some(format!("{} {}", done, file.display()));
+                self.file_prompt = None;
+                self.input_mode = InputMode::Edit;
+            }
+            Err(err) => {
+                let failed = match action {
+                    FileAction::Open => "open",
+                    FileAction::Save => "save",
+                };
+                self.notice = Some(format!("Cannot {} {}: {}", failed, file.display(), err));
+            }
+        }
+    }
+
+    // Whether the code differs from the current file's
+    fn is_modified(&self, textarea: &TextArea) -> bool {
+        self.current_file.is_some()
+            && self.folds.expand(textarea.lines()).join("\n") != self.saved_code
+    }
+
+    fn save_session(&mut self, textarea: &TextArea) {
+        // the state saved is the one of the code as it is
+        if self.evaluate_at.is_some() {
+            self.evaluate(textarea);
+        }
+        while self.evaluator.is_busy() {
+            if !self.receive_evaluation(textarea, Duration::MAX) {
+                break;
+            }
+        }
+        let code = self.folds.expand(textarea.lines()).join("\n");
+        let saved = session::save_session(
+            &self.root,
+            &code,
+            &self.forth,
+            &self.code_status,
+            &self.stack_edits,
+        );
+        self.notice = Some(match saved {
+            Ok(()) => format!("Session saved to {}", session::SESSION_FILE),
+            Err(err) => format!("Cannot save the session: {}", err),
+        });
+    }
+
+    // Shows the session's state as it was saved, until the code is edited
+    fn load_session(&mut self, textarea: &mut TextArea) {
+        let session = match session::load_session(&self.root) {
+            Ok(session) => session,
+            Err(err) => {
+                self.notice = Some(format!("Cannot load the session: {}", err));
+                return;
+            }
+        };
+        self.evaluator.discard();
+        *textarea = TextArea::new(session.code.lines().map(String::from).collect());
+        self.folds = Folds::default();
+        self.current_file = None;
+        self.evaluate_at = None;
+        self.evaluated_code = session.code;
+        self.forth = session.forth;
+        self.code_status = session.status;
+        self.stack_edits = session.stack_edits;
+        let location = self.code_status.as_ref().err().and_then(|e| e.location());
+        self.error_words = self.error_words(textarea, 0, location);
+        let warnings = self.forth.warnings().iter().filter_map(|w| w.location());
+        self.warning_words = self.error_words(textarea, 0, warnings);
+        self.output_scroll = 0;
+        self.clamp_stack_selection();
+        self.notice = Some(format!("Session loaded from {}", session::SESSION_FILE));
+    }
+
+    fn write_glossary(&mut self, textarea: &TextArea) {
+        let source = self.folds.expand(textarea.lines()).join("\n");
+        let text = glossary::markdown("Glossary", &format!("{}\n{}", self.prelude, source));
+        self.notice = Some(match fs::write(GLOSSARY_FILE, text) {
+            Ok(()) => format!("Glossary written to {}", GLOSSARY_FILE),
+            Err(err) => format!("Cannot write {}: {}", GLOSSARY_FILE, err),
+        });
+    }
+
+    // Evaluates the code once typing pauses
+    fn schedule_evaluation(&mut self) {
+        self.evaluate_at = Some(Instant::now() + DEBOUNCE);
+    }
+
+    // How long the app can wait for an event: until the scheduled evaluation,
+    // or the next frame of the spinner; None for as long as it takes
+    fn wait(&self) -> Option<Duration> {
+        let scheduled = self
+            .evaluate_at
+            .map(|at| at.saturating_duration_since(Instant::now()));
+        let spinner = self.evaluator.is_busy().then_some(SPINNER_INTERVAL);
+        scheduled.into_iter().chain(spinner).min()
+    }
+
+    // Time passed without an event
+    fn tick(&mut self, textarea: &TextArea) {
+        if self.evaluator.is_busy() {
+            self.spinner_frame += 1;
+        }
+        if self.evaluate_at.is_some_and(|at| at <= Instant::now()) {
+            let code = self.folds.expand(textarea.lines()).join("\n");
+            if code == self.evaluated_code {
+                self.evaluate_at = None;
+            } else {
+                self.evaluate(textarea);
+            }
+        }
+    }
+
+    fn evaluate(&mut self, textarea: &TextArea) {
+        self.output_scroll = 0;
+        self.evaluate_at = None;
+        let code = self.folds.expand(textarea.lines()).join("\n");
+        self.evaluated_code = code.clone();
+        self.evaluator.send(Request {
+            user_prelude: self.user_prelude.clone(),
+            dictionary: self.dictionary.clone(),
+            prelude: self.prelude.clone(),
+            code,
+            provenance: self.provenance,
+            overflow_mode: self.overflow_mode,
+            strictness: self.strictness,
+            case_mode: self.case_mode,
+            fuel: self.fuel,
+            root: self.root.clone(),
+            include_anywhere: self.include_anywhere,
+            buffer: textarea.lines().join("\n"),
+        });
+    }
+
+    // Shows the result of the latest evaluation done, if it wasn't yet.
+    // False when there was none to show.
+    fn receive_evaluation(&mut self, textarea: &TextArea, wait: Duration) -> bool {
+        let evaluated = match self.evaluator.receive(wait) {
+            Some(Ok(evaluated)) => evaluated,
+            Some(Err(err)) => {
+                // evaluated here from now on
+                self.notice = Some(format!("Detached from the daemon: {}", err));
+                self.remote = None;
+                self.evaluator = Evaluator::spawn(self.cancel_token.clone(), None);
+                self.evaluate(textarea);
+                return true;
+            }
+            None => return false,
+        };
+        self.forth = evaluated.forth;
+        self.code_status = evaluated.prelude_status;
+        self.saved_from = evaluated.saved_from;
+        self.error_words.clear();
+        self.warning_words.clear();
+        if self.code_status.is_ok() {
+            // every error is underlined, the first one is the status
+            let mut diagnostics = evaluated.diagnostics.into_iter();
+            let locations = diagnostics.as_slice().iter().map(|d| &d.location);
+            self.error_words = self.error_words(textarea, 0, locations);
+            self.code_status = diagnostics.next().map_or(Ok(()), |d| Err(d.error));
+            let warnings = self.forth.warnings().iter().filter_map(|w| w.location());
+            self.warning_words = self.error_words(textarea, 0, warnings);
+        }
+        self.after_evaluation();
+        true
+    }
+
+    // Replays the corrections made from the stack pane over a new result
+    fn after_evaluation(&mut self) {
+        if let Err(err) = stack_edit::apply_all(&mut self.forth, &self.stack_edits) {
+            self.code_status = Err(err);
+            self.error_words.clear();
+        }
+        if let Some(base) = self.stack_base {
+            self.forth.set_base(base);
+        }
+        self.clamp_stack_selection();
+        self.autosave_definitions();
+    }
+
+    // Where the words the code failed at are in the editor, given how many
+    // lines came before the code evaluated; leaving out words in a fold or before
+    fn error_words<'a>(
+        &self,
+        textarea: &TextArea,
+        lines_before: usize,
+        locations: impl IntoIterator<Item = &'a Location>,
+    ) -> Vec<(usize, usize, usize)> {
+        let lines = textarea.lines();
+        locations
+            .into_iter()
+            .filter_map(|location| {
+                let line = location.line.checked_sub(lines_before + 1)?;
+                let row = self.folds.editor_row(lines, line)?;
+                Some((row, location.column - 1, location.word.chars().count()))
+            })
+            .collect()
+    }
+
+    fn autosave_definitions(&mut self) {
+        if !self.autosave {
+            return;
+        }
+        let source = persistence::definitions_source(&self.forth, self.saved_from);
+        if source != self.saved_source {
+            match persistence::save_definitions(&self.root, &source) {
+                Ok(()) => self.saved_source = source,
+                Err(err) => {
+                    self.autosave = false;
+                    self.notice = Some(format!("Cannot save the definitions: {}", err));
+                }
+            }
+        }
+    }
+
+    // Saves the selected definition to the user's dictionary, for the next
+    // launches to have it, or stops saving it
+    fn toggle_saved_word(&mut self) {
+        let Some(definition) = self.definitions_view.selected_definition(&self.forth) else {
+            return;
+        };
+        let Some(dir) = config::config_dir() else {
+            self.notice = Some(String::from("No config directory to save the word to"));
+            return;
+        };
+        self.notice = Some(
+            match self.saved_words.toggle(&dir, &self.forth, definition) {
+                Ok(saved) if saved.is_empty() => format!("{} no longer saved", definition.name),
+                Ok(saved) => format!("Saved {}, defined on the next launches", saved.join(", ")),
+                Err(err) => format!("Cannot save the dictionary: {}", err),
+            },
+        );
+    }
+
+    fn reload_definitions(&mut self, source: String) {
+        self.saved_source = source.clone();
+        self.prelude = source;
+        self.autosave = true;
+    }
+
+    // Shows the stack in decimal, then hexadecimal, then binary
+    fn cycle_stack_base(&mut self) {
+        let base = match self.forth.base() {
+            10 => 16,
+            16 => 2,
+            _ => 10,
+        };
+        self.forth.set_base(base);
+        self.stack_base = Some(base);
+    }
+
+    fn edit_stack(&mut self, edit: StackEdit) {
+        if let Err(err) = stack_edit::apply_all(&mut self.forth, &[edit]) {
+            self.code_status = Err(err);
+            self.error_words.clear();
+        }
+        self.stack_edits.push(edit);
+        self.clamp_stack_selection();
+    }
+
+    // Index of the stack cell displayed at the given terminal row, if any
+    fn stack_index_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.stack_area;
+        let inside = column > area.x
+            && column < area.right().saturating_sub(1)
+            && row > area.y
+            && row < area.bottom().saturating_sub(1);
+        if !inside {
+            return None;
+        }
+        let index = (row - area.y - 1) as usize;
+        if index < self.forth.stack().len() {
+            Some(index)
+        } else {
+            None
+        }
+    }
+
+    fn clamp_stack_selection(&mut self) {
+        self.stack_selection = self
+            .stack_selection
+            .min(self.forth.stack().len().saturating_sub(1));
+    }
 }
 
 enum InputMode {
     Edit,
     Menu,
+    Stack,
+    Calculator,
+    Files,
+    FilePrompt,
+    Definitions,
+    Snippets,
+    Tests,
+}
+
+// What the command line asks for
+enum Launch {
+    // the terminal app, on a directory of sources or a file if one is given
+    App {
+        path: Option<PathBuf>,
+        readonly: bool,
+    },
+    // the terminal app on the session of the daemon at a socket
+    Attach(PathBuf),
+    // a command run without the terminal app
+    Command(String, Vec<String>),
+}
+
+impl Launch {
+    fn parse(args: Vec<String>) -> Launch {
+        if args.first().map(String::as_str) == Some("attach") {
+            let socket = args
+                .get(1)
+                .map_or_else(daemon::default_socket, PathBuf::from);
+            return Launch::Attach(socket);
+        }
+        // code piped in is evaluated, rather than taken as keystrokes
+        if args.is_empty() && !io::stdin().is_terminal() {
+            return Launch::Command("--eval".to_string(), vec!["-".to_string()]);
+        }
+        let readonly = args.iter().any(|arg| arg == "--readonly");
+        let paths: Vec<&String> = args.iter().filter(|&arg| arg != "--readonly").collect();
+        match paths[..] {
+            [] => Launch::App {
+                path: None,
+                readonly,
+            },
+            [path] if Path::new(path).is_dir() || project::is_source(Path::new(path)) => {
+                Launch::App {
+                    path: Some(PathBuf::from(path)),
+                    readonly,
+                }
+            }
+            // --readonly is only the app's: a command gets the other arguments
+            [command, ..] => Launch::Command(
+                command.clone(),
+                paths[1..].iter().map(|&arg| arg.clone()).collect(),
+            ),
+        }
+    }
 }
 
 fn main() -> io::Result<()> {
+    let launch = Launch::parse(env::args().skip(1).collect());
+    let readonly = matches!(launch, Launch::App { readonly: true, .. });
+    let mut textarea = TextArea::default();
+    let mut remote = None;
+    let mut file = None;
+    let project = match launch {
+        Launch::Attach(socket) => {
+            let attached = Remote::new(socket);
+            let state = attached.fetch()?;
+            textarea = TextArea::new(state.buffer.lines().map(String::from).collect());
+            remote = Some(attached);
+            None
+        }
+        Launch::App {
+            path: Some(path), ..
+        } if path.is_dir() => Some(Project::open(&path)?),
+        Launch::App { path, .. } => {
+            file = path;
+            None
+        }
+        Launch::Command(command, args) => return cli::run(&command, &args),
+    };
+    // read before the terminal is taken over, for an error to show; a file
+    // not there yet is written once saved
+    let source = match &file {
+        Some(file) => match fs::read_to_string(file) {
+            Err(err) if err.kind() == io::ErrorKind::NotFound => Some(String::new()),
+            read => Some(read?),
+        },
+        None => None,
+    };
+    let root = project
+        .as_ref()
+        .map_or(PathBuf::from("."), |project| project.root.clone());
+    let config = Config::load(project.as_ref().map(|project| project.root.as_path()));
+
     let mut terminal = init_terminal()?;
 
     // create app and run it
-    let mut app = App::default();
-    let res = run_app(&mut terminal, &mut app);
+    let mut app = App {
+        pending_reload: persistence::load_definitions(&root).ok().flatten(),
+        autosave: config.get_bool("autosave").unwrap_or(false),
+        snippets: snippets::load(&config),
+        scrollback: config
+            .get("scrollback")
+            .and_then(|lines| lines.parse().ok())
+            .unwrap_or(DEFAULT_SCROLLBACK),
+        fuel: config
+            .get("fuel")
+            .and_then(|steps| steps.parse().ok())
+            .unwrap_or(DEFAULT_FUEL),
+        include_anywhere: config.get_bool("include_anywhere").unwrap_or(false),
+        case_mode: match config.get("case") {
+            Some("preserve") => CaseMode::Preserve,
+            Some("sensitive") => CaseMode::Sensitive,
+            _ => CaseMode::Insensitive,
+        },
+        root,
+        project,
+        remote,
+        readonly,
+        ..App::default()
+    };
+    if let Some(remote) = &app.remote {
+        app.evaluator = Evaluator::spawn(app.cancel_token.clone(), Some(remote.clone()));
+    }
+    match SavedWords::load(config::config_dir().as_deref()) {
+        Ok(saved_words) => {
+            app.dictionary = saved_words.source();
+            app.saved_words = saved_words;
+        }
+        Err(err) => app.notice = Some(format!("Cannot read the dictionary: {}", err)),
+    }
+    if let Some(file) = config.get("prelude") {
+        match fs::read_to_string(app.root.join(file)) {
+            Ok(source) => app.user_prelude = Some((file.to_string(), source)),
+            Err(err) => app.notice = Some(format!("Cannot read the prelude {}: {}", file, err)),
+        }
+    }
+    if let (Some(file), Some(source)) = (&file, &source) {
+        app.show_file(&mut textarea, file, source);
+    }
+    // started first, so that the stop key also works for the initial evaluation
+    let events = terminal_events::spawn(app.cancel_token.clone());
+    app.evaluate(&textarea);
+    let res = run_app(&mut terminal, &mut app, textarea, events);
 
     // handle program exit
     disable_raw_mode()?;
@@ -76,32 +967,344 @@ fn init_terminal() -> io::Result<Terminal<CrosstermBackend<StdoutLock<'static>>>
     Terminal::new(backend)
 }
 
-fn run_app<B: Backend>(terminal: &mut Terminal<B>, app: &mut App) -> io::Result<()> {
-    let mut textarea = TextArea::default();
-
+fn run_app<B: Backend>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    mut textarea: TextArea,
+    events: Receiver<io::Result<Event>>,
+) -> io::Result<()> {
+    let stopped = || io::Error::new(io::ErrorKind::BrokenPipe, "terminal events stopped");
     loop {
-        terminal.draw(|f| ui(f, &mut textarea, &app))?;
+        app.receive_evaluation(&textarea, QUICK_EVALUATION);
+        terminal.draw(|f| ui(f, &mut textarea, app))?;
 
-        if let Event::Key(key) = crossterm::event::read()? {
-            if key.code == KeyCode::Esc {
-                app.toggle_input_mode();
+        let event = match app.wait() {
+            Some(wait) => match events.recv_timeout(wait) {
+                Ok(event) => event,
+                Err(RecvTimeoutError::Timeout) => {
+                    app.tick(&textarea);
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => return Err(stopped()),
+            },
+            None => events.recv().map_err(|_| stopped())?,
+        };
+        let key = match event? {
+            Event::Key(key) => key,
+            Event::Mouse(mouse) => {
+                handle_stack_mouse(app, mouse);
+                if mouse.kind == MouseEventKind::Moved {
+                    app.hover(&textarea, mouse.column, mouse.row);
+                }
+                continue;
             }
-
-            if let InputMode::Menu = app.input_mode {
-                if key.code == KeyCode::Char('q') {
-                    break;
+            _ => continue,
+        };
+        app.notice = None;
+        app.tooltip = None;
+        if let Some((source, _)) = app.pending_reload.take() {
+            if key.code == KeyCode::Char('y') {
+                app.reload_definitions(source);
+                app.evaluate(&textarea);
+            }
+            continue;
+        }
+        match app.input_mode {
+            InputMode::Menu => match key.code {
+                KeyCode::Esc => app.toggle_input_mode(),
+                KeyCode::Char('q') => break,
+                KeyCode::Char('a') => {
+                    app.autosave = !app.autosave;
+                    app.evaluate(&textarea);
                 }
-            } else {
-                textarea.input(key);
-                app.forth = Forth::new();
-                app.code_status = app.forth.eval(&textarea.lines().join("\t"));
-            };
+                KeyCode::Char('r') if app.readonly => {}
+                KeyCode::Char('r') if app.macro_recorder.is_recording() => {
+                    app.macro_recorder.stop();
+                }
+                KeyCode::Char('r') => {
+                    app.macro_recorder.start();
+                    app.toggle_input_mode();
+                }
+                KeyCode::Char(c @ '0'..='9') => {
+                    app.macro_recorder
+                        .push_count_digit(c.to_digit(10).unwrap_or(0));
+                }
+                KeyCode::Char('c') => app.input_mode = InputMode::Calculator,
+                KeyCode::Char('d') => app.input_mode = InputMode::Definitions,
+                KeyCode::Char('n') if !app.readonly => app.input_mode = InputMode::Snippets,
+                KeyCode::Char('t') => {
+                    app.run_tests(&textarea);
+                    app.input_mode = InputMode::Tests;
+                }
+                KeyCode::Char('p') => {
+                    app.provenance = !app.provenance;
+                    app.evaluate(&textarea);
+                }
+                KeyCode::Char('v') => {
+                    app.overflow_mode = match app.overflow_mode {
+                        OverflowMode::Wrapping => OverflowMode::Checked,
+                        OverflowMode::Checked => OverflowMode::Saturating,
+                        OverflowMode::Saturating => OverflowMode::Wrapping,
+                    };
+                    app.evaluate(&textarea);
+                }
+                KeyCode::Char('u') => {
+                    app.strictness = match app.strictness {
+                        Strictness::Strict => Strictness::Tolerant,
+                        Strictness::Tolerant => Strictness::Strict,
+                    };
+                    app.evaluate(&textarea);
+                }
+                KeyCode::Char('g') => app.write_glossary(&textarea),
+                KeyCode::Char('x') => app.save_session(&textarea),
+                KeyCode::Char('l') => {
+                    app.load_session(&mut textarea);
+                    app.input_mode = InputMode::Edit;
+                }
+                KeyCode::PageUp => {
+                    let page = app.output_height.saturating_sub(2).max(1) as usize;
+                    app.output_scroll = app.output_scroll.saturating_add(page);
+                }
+                KeyCode::PageDown => {
+                    let page = app.output_height.saturating_sub(2).max(1) as usize;
+                    app.output_scroll = app.output_scroll.saturating_sub(page);
+                }
+                KeyCode::Char('o') if app.project.is_some() => app.input_mode = InputMode::Files,
+                KeyCode::Char('o') => {
+                    app.file_prompt = Some(FilePrompt::new(FileAction::Open));
+                    app.input_mode = InputMode::FilePrompt;
+                }
+                KeyCode::Char('w') if !app.readonly => app.save_current_file(&textarea),
+                KeyCode::Char('z') => {
+                    app.toggle_fold(&mut textarea);
+                    app.toggle_input_mode();
+                }
+                KeyCode::Char('@') if app.macro_recorder.has_macro() && !app.readonly => {
+                    let keys = app.macro_recorder.play();
+                    app.edit_code(&mut textarea, |_, textarea| {
+                        for key in keys {
+                            textarea.input(key);
+                        }
+                    });
+                    app.evaluate(&textarea);
+                    app.toggle_input_mode();
+                }
+                KeyCode::Char('s') => {
+                    app.stack_selection = app.forth.stack().len().saturating_sub(1);
+                    app.input_mode = InputMode::Stack;
+                }
+                _ => {}
+            },
+            InputMode::Stack => handle_stack_key(app, key, &textarea),
+            InputMode::FilePrompt => match key.code {
+                KeyCode::Char(c) => {
+                    if let Some(prompt) = &mut app.file_prompt {
+                        prompt.input.push(c);
+                    }
+                }
+                KeyCode::Backspace => {
+                    if let Some(prompt) = &mut app.file_prompt {
+                        prompt.input.pop();
+                    }
+                }
+                KeyCode::Tab => {
+                    if let Some(prompt) = &mut app.file_prompt {
+                        prompt.complete(&app.root);
+                    }
+                }
+                KeyCode::Enter => app.submit_file_prompt(&mut textarea),
+                KeyCode::Esc => {
+                    app.file_prompt = None;
+                    app.input_mode = InputMode::Menu;
+                }
+                _ => {}
+            },
+            InputMode::Files => match key.code {
+                KeyCode::Up => app.file_selection = app.file_selection.saturating_sub(1),
+                KeyCode::Down => {
+                    let count = app.project.as_ref().map_or(0, |p| p.files.len());
+                    app.file_selection = (app.file_selection + 1).min(count.saturating_sub(1));
+                }
+                KeyCode::Enter => {
+                    app.open_selected_file(&mut textarea);
+                    app.input_mode = InputMode::Edit;
+                }
+                KeyCode::Esc => app.input_mode = InputMode::Menu,
+                _ => {}
+            },
+            InputMode::Snippets => match key.code {
+                KeyCode::Up => app.snippet_selection = app.snippet_selection.saturating_sub(1),
+                KeyCode::Down => {
+                    app.snippet_selection =
+                        (app.snippet_selection + 1).min(app.snippets.len().saturating_sub(1));
+                }
+                KeyCode::Enter => {
+                    app.insert_snippet(&mut textarea);
+                    app.input_mode = InputMode::Edit;
+                }
+                KeyCode::Esc => app.input_mode = InputMode::Menu,
+                _ => {}
+            },
+            InputMode::Tests => match key.code {
+                KeyCode::Up => app.test_selection = app.test_selection.saturating_sub(1),
+                KeyCode::Down => {
+                    app.test_selection =
+                        (app.test_selection + 1).min(app.test_outcomes.len().saturating_sub(1));
+                }
+                KeyCode::Enter if !app.test_outcomes.is_empty() => {
+                    app.go_to_selected_test(&mut textarea);
+                    app.input_mode = InputMode::Edit;
+                }
+                KeyCode::Char('t') => app.run_tests(&textarea),
+                KeyCode::Esc => app.input_mode = InputMode::Menu,
+                _ => {}
+            },
+            InputMode::Definitions => match key.code {
+                KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                    app.definitions_view.search_order = !app.definitions_view.search_order
+                }
+                KeyCode::Char(c) => app.definitions_view.filter.push(c),
+                KeyCode::Backspace => {
+                    app.definitions_view.filter.pop();
+                }
+                KeyCode::Tab => app.definitions_view.toggle_order(),
+                KeyCode::BackTab => app.definitions_view.grouped = !app.definitions_view.grouped,
+                KeyCode::Up => {
+                    let view = &mut app.definitions_view;
+                    view.selected = view.selected.saturating_sub(1);
+                }
+                KeyCode::Down => app.definitions_view.select_next(&app.forth),
+                KeyCode::Enter => app.toggle_saved_word(),
+                KeyCode::Esc => app.input_mode = InputMode::Menu,
+                _ => {}
+            },
+            InputMode::Calculator => match key.code {
+                KeyCode::Char(c) => app.calculator.input.push(c),
+                KeyCode::Backspace => {
+                    app.calculator.input.pop();
+                }
+                KeyCode::Enter => app.calculator.submit(),
+                KeyCode::Esc => app.input_mode = InputMode::Menu,
+                _ => {}
+            },
+            InputMode::Edit => {
+                if key.code == KeyCode::Esc {
+                    app.toggle_input_mode();
+                } else if key.code == KeyCode::Tab && !app.tab_stops.is_empty() {
+                    app.next_tab_stop(&mut textarea);
+                } else if key.code == KeyCode::F(12) && key.modifiers.contains(KeyModifiers::SHIFT)
+                {
+                    app.jump_back(&mut textarea);
+                } else if key.code == KeyCode::F(12) {
+                    app.go_to_definition(&mut textarea);
+                } else if app.readonly {
+                    if moves_cursor(&key) {
+                        textarea.input(key);
+                    }
+                } else if key.code == KeyCode::Enter && key.modifiers.is_empty() {
+                    app.macro_recorder.record(key);
+                    app.edit_code(&mut textarea, App::insert_indented_newline);
+                } else {
+                    app.macro_recorder.record(key);
+                    app.edit_code(&mut textarea, |_, textarea| {
+                        textarea.input(key);
+                    });
+                }
+            }
         }
     }
     Ok(())
 }
 
-fn ui<B: Backend>(f: &mut Frame<B>, textarea: &mut TextArea, app: &App) {
+// Keys that move around the code without changing it
+fn moves_cursor(key: &KeyEvent) -> bool {
+    matches!(
+        key.code,
+        KeyCode::Up
+            | KeyCode::Down
+            | KeyCode::Left
+            | KeyCode::Right
+            | KeyCode::Home
+            | KeyCode::End
+            | KeyCode::PageUp
+            | KeyCode::PageDown
+    ) && !key.modifiers.contains(KeyModifiers::CONTROL)
+}
+
+fn handle_stack_key(app: &mut App, key: KeyEvent, textarea: &TextArea) {
+    if let Some(prompt) = app.stack_prompt.as_mut() {
+        match key.code {
+            KeyCode::Char(c) if c.is_ascii_digit() || c == '-' => prompt.input.push(c),
+            KeyCode::Backspace => {
+                prompt.input.pop();
+            }
+            KeyCode::Enter => {
+                if let Some(edit) = prompt.to_edit() {
+                    app.stack_prompt = None;
+                    app.edit_stack(edit);
+                    if let StackEdit::Push(_) = edit {
+                        app.stack_selection = app.forth.stack().len() - 1;
+                    }
+                }
+            }
+            KeyCode::Esc => app.stack_prompt = None,
+            _ => {}
+        }
+        return;
+    }
+
+    let selected = app
+        .forth
+        .stack()
+        .get(app.stack_selection)
+        .map(|&value| (app.stack_selection, value));
+    match (key.code, selected) {
+        (KeyCode::Esc, _) => app.toggle_input_mode(),
+        (KeyCode::Up, _) => app.stack_selection = app.stack_selection.saturating_sub(1),
+        (KeyCode::Down, _) => {
+            app.stack_selection += 1;
+            app.clamp_stack_selection();
+        }
+        (KeyCode::Char('p'), _) => app.stack_prompt = Some(StackPrompt::push()),
+        (KeyCode::Char('x'), Some((index, _))) => app.edit_stack(StackEdit::Drop(index)),
+        (KeyCode::Char('d'), Some((index, _))) => app.edit_stack(StackEdit::Dup(index)),
+        (KeyCode::Char('e'), Some((index, value))) => {
+            app.stack_prompt = Some(StackPrompt::edit(index, value))
+        }
+        (KeyCode::Char('b'), _) => app.cycle_stack_base(),
+        (KeyCode::Char('c'), _) => {
+            app.stack_edits.clear();
+            app.stack_prompt = None;
+            // the stack is the code's own again
+            app.evaluate(textarea);
+        }
+        _ => {}
+    }
+}
+
+fn handle_stack_mouse(app: &mut App, mouse: MouseEvent) {
+    let index = app.stack_index_at(mouse.column, mouse.row);
+    match (mouse.kind, app.stack_drag) {
+        (MouseEventKind::Down(MouseButton::Left), _) => {
+            app.stack_drag = index.map(|index| (index, index));
+        }
+        (MouseEventKind::Drag(MouseButton::Left), Some((from, _))) => {
+            if let Some(to) = index {
+                app.stack_drag = Some((from, to));
+            }
+        }
+        (MouseEventKind::Up(MouseButton::Left), Some((from, to))) => {
+            app.stack_drag = None;
+            if from != to {
+                app.edit_stack(StackEdit::Move(from, to));
+                app.stack_selection = to;
+            }
+        }
+        _ => {}
+    }
+}
+
+fn ui<B: Backend>(f: &mut Frame<B>, textarea: &mut TextArea, app: &mut App) {
     let sections = Layout::default()
         .direction(Direction::Vertical)
         .constraints(
@@ -114,6 +1317,18 @@ fn ui<B: Backend>(f: &mut Frame<B>, textarea: &mut TextArea, app: &App) {
         )
         .split(f.size());
 
+    // projects get a file sidebar on the left
+    let (sidebar, body) = match app.project {
+        Some(_) => {
+            let columns = Layout::default()
+                .direction(Direction::Horizontal)
+                .constraints([Constraint::Percentage(18), Constraint::Percentage(82)].as_ref())
+                .split(sections[1]);
+            (Some(columns[0]), columns[1])
+        }
+        None => (None, sections[1]),
+    };
+
     let body_columns = Layout::default()
         .direction(Direction::Horizontal)
         .constraints(
@@ -124,31 +1339,179 @@ fn ui<B: Backend>(f: &mut Frame<B>, textarea: &mut TextArea, app: &App) {
             ]
             .as_ref(),
         )
-        .split(sections[1]);
+        .split(body);
 
     let footer_columns = Layout::default()
         .direction(Direction::Horizontal)
         .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
         .split(sections[2]);
 
-    f.render_widget(title_widget(), sections[0]);
-    f.render_widget(editor_widget(textarea, app), body_columns[0]);
-    f.render_widget(definitions_widget(app), body_columns[1]);
-    f.render_widget(stack_widget(app), body_columns[2]);
+    // the float stack only shows up once a program uses it
+    let stack_constraints = match app.forth.float_stack().is_empty() {
+        true => [Constraint::Percentage(70), Constraint::Percentage(30)].to_vec(),
+        false => [
+            Constraint::Percentage(50),
+            Constraint::Percentage(25),
+            Constraint::Percentage(25),
+        ]
+        .to_vec(),
+    };
+    let stack_rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints(stack_constraints)
+        .split(body_columns[2]);
+    app.stack_area = stack_rows[0];
+
+    f.render_widget(title_widget(app, app.is_modified(textarea)), sections[0]);
+    if let (Some(area), Some(project)) = (sidebar, &app.project) {
+        f.render_widget(files_widget(app, project), area);
+    }
+    let editor_rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(65), Constraint::Percentage(35)].as_ref())
+        .split(body_columns[0]);
+    let inner = Block::default().borders(Borders::ALL).inner(editor_rows[0]);
+    let cursor = textarea.cursor();
+    app.editor_area = editor_rows[0];
+    app.editor_scroll = (
+        hover::scroll_top(app.editor_scroll.0, cursor.0 as u16, inner.height),
+        hover::scroll_top(app.editor_scroll.1, cursor.1 as u16, inner.width),
+    );
+    f.render_widget(editor_widget(textarea, app), editor_rows[0]);
+    let style = Style::default()
+        .fg(Color::LightRed)
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    for &word in &app.error_words {
+        if let Some(area) = error_word_rect(app, inner, word) {
+            f.render_widget(Block::default().style(style), area);
+        }
+    }
+    let style = style.fg(WARNING_COLOR);
+    for &word in &app.warning_words {
+        if let Some(area) = error_word_rect(app, inner, word) {
+            f.render_widget(Block::default().style(style), area);
+        }
+    }
+    app.output_height = editor_rows[1].height;
+    f.render_widget(output_widget(app), editor_rows[1]);
+    let definitions_rows = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Percentage(60), Constraint::Percentage(40)].as_ref())
+        .split(body_columns[1]);
+    let inspected =
+        inspector::definition_at_cursor(&app.forth, textarea.lines(), textarea.cursor());
+
+    f.render_widget(definitions_widget(app), definitions_rows[0]);
+    f.render_widget(compiled_widget(app, inspected), definitions_rows[1]);
+    f.render_widget(stack_widget(app), stack_rows[0]);
+    f.render_widget(return_stack_widget(app), stack_rows[1]);
+    if let Some(&area) = stack_rows.get(2) {
+        f.render_widget(float_stack_widget(app), area);
+    }
     f.render_widget(editor_message_widget(app), footer_columns[0]);
-    f.render_widget(menu_widget(app), footer_columns[1])
+    f.render_widget(menu_widget(app), footer_columns[1]);
+
+    if let InputMode::Calculator = app.input_mode {
+        let area = centered_rect(50, 7, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(calculator_widget(&app.calculator), area);
+    }
+    if let Some(tooltip) = &app.tooltip {
+        let area = tooltip_rect(tooltip, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(tooltip_widget(tooltip), area);
+    }
+    if let InputMode::Snippets = app.input_mode {
+        let area = centered_rect(40, app.snippets.len() as u16 + 2, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(snippets_widget(app), area);
+    }
+    if let InputMode::Tests = app.input_mode {
+        let area = centered_rect(60, app.test_outcomes.len().max(1) as u16 + 2, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(tests_widget(app), area);
+    }
+    if let (InputMode::FilePrompt, Some(prompt)) = (&app.input_mode, &app.file_prompt) {
+        let candidates = prompt.candidates(&app.root);
+        let shown = candidates.len().min(MAX_CANDIDATES) as u16;
+        let area = centered_rect(50, shown + 3, f.size());
+        f.render_widget(Clear, area);
+        f.render_widget(file_prompt_widget(prompt, candidates), area);
+    }
+}
+
+// Where a word the code failed at is on screen, if it is scrolled into view
+fn error_word_rect(app: &App, inner: Rect, word: (usize, usize, usize)) -> Option<Rect> {
+    let (row, column, width) = word;
+    let (top, left) = (app.editor_scroll.0 as usize, app.editor_scroll.1 as usize);
+    let (row, column) = (row.checked_sub(top)?, column.checked_sub(left)?);
+    if row >= inner.height as usize || column >= inner.width as usize {
+        return None;
+    }
+    let x = inner.x + column as u16;
+    Some(Rect {
+        x,
+        y: inner.y + row as u16,
+        width: (width as u16).min(inner.right() - x),
+        height: 1,
+    })
+}
+
+// Below and right of the mouse, moved left or up to fit the terminal
+fn tooltip_rect(tooltip: &Tooltip, area: Rect) -> Rect {
+    let longest = tooltip.lines.iter().map(|line| line.chars().count());
+    let width = (longest.max().unwrap_or(0) as u16 + 2).min(area.width);
+    let height = (tooltip.lines.len() as u16 + 2).min(area.height);
+    Rect {
+        x: (tooltip.column + 1).min(area.right() - width),
+        y: (tooltip.row + 1).min(area.bottom() - height),
+        width,
+        height,
+    }
+}
+
+fn tooltip_widget(tooltip: &Tooltip) -> Paragraph<'_> {
+    let lines: Vec<Spans> = tooltip
+        .lines
+        .iter()
+        .map(|line| Spans::from(line.as_str()))
+        .collect();
+    Paragraph::new(lines).block(
+        Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::LightCyan)),
+    )
+}
+
+fn centered_rect(percent_x: u16, height: u16, area: Rect) -> Rect {
+    let width = area.width * percent_x / 100;
+    Rect {
+        x: area.x + (area.width - width) / 2,
+        y: area.y + area.height.saturating_sub(height) / 2,
+        width,
+        height: height.min(area.height),
+    }
 }
 
 fn status_color(app: &App) -> Color {
     match app.code_status {
-        Err(forth_tui::Error::UnknownWord) => Color::Rgb(255, 164, 76),
+        Err(ref err) if *err.kind() == forth_tui::ErrorKind::UnknownWord => WARNING_COLOR,
         Err(_) => Color::LightRed,
+        Ok(_) if !app.forth.warnings().is_empty() => WARNING_COLOR,
         Ok(_) => Color::White,
     }
 }
 
-fn title_widget<'a>() -> Paragraph<'a> {
-    Paragraph::new("Forth TUI")
+// A file with changes not saved yet is marked as modified
+fn title_widget(app: &App, modified: bool) -> Paragraph<'_> {
+    let title = match (&app.current_file, &app.remote) {
+        (Some(file), _) if app.readonly => format!("Forth TUI - {} [read-only]", file.display()),
+        (Some(file), _) if modified => format!("Forth TUI - {} [modified]", file.display()),
+        (Some(file), _) => format!("Forth TUI - {}", file.display()),
+        (None, Some(_)) => String::from("Forth TUI - attached"),
+        (None, None) => String::from("Forth TUI"),
+    };
+    Paragraph::new(title)
         .style(Style::default().fg(Color::LightCyan))
         .alignment(Alignment::Center)
         .block(
@@ -160,6 +1523,36 @@ fn title_widget<'a>() -> Paragraph<'a> {
         )
 }
 
+fn files_widget<'a>(app: &App, project: &'a Project) -> Paragraph<'a> {
+    let focused = matches!(app.input_mode, InputMode::Files);
+    let items: Vec<Spans> = project
+        .files
+        .iter()
+        .enumerate()
+        .map(|(index, file)| {
+            let style = if focused && index == app.file_selection {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else if app.current_file.as_ref() == Some(file) {
+                Style::default().fg(Color::LightCyan)
+            } else {
+                Style::default()
+            };
+            Spans::from(Span::styled(file.display().to_string(), style))
+        })
+        .collect();
+    let border_style = if focused {
+        Style::default().fg(Color::LightCyan)
+    } else {
+        Style::default()
+    };
+    Paragraph::new(items).block(
+        Block::default()
+            .title("Files")
+            .borders(Borders::ALL)
+            .border_style(border_style),
+    )
+}
+
 fn editor_widget<'a>(textarea: &'a mut TextArea, app: &App) -> impl Widget + 'a {
     let block = Block::default()
         .borders(Borders::ALL)
@@ -169,45 +1562,436 @@ fn editor_widget<'a>(textarea: &'a mut TextArea, app: &App) -> impl Widget + 'a
     textarea.widget()
 }
 
-fn editor_message_widget(app: &App) -> Paragraph {
-    let message = match app.code_status {
-        Err(forth_tui::Error::DivisionByZero) => "Error: Cannot divide by 0",
-        Err(forth_tui::Error::InvalidWord) => "Error: Invalid word definition",
-        Err(forth_tui::Error::StackUnderflow) => "Error: Stack underflow",
-        Err(forth_tui::Error::UnknownWord) => "Unknown word, type on :)",
-        Ok(_) => "",
-    };
+fn status_message(status: &ForthResult) -> String {
+    match status {
+        Err(err) => err.to_string(),
+        Ok(_) => String::new(),
+    }
+}
+
+// The stack the way Forth's `.S` prints it: depth, then values from the bottom
+fn stack_text(forth: &Forth) -> String {
+    let values: Vec<String> = forth.stack().iter().map(|v| v.to_string()).collect();
+    format!("<{}> {}", values.len(), values.join(" "))
+}
+
+fn editor_message_widget(app: &App) -> Paragraph<'_> {
+    if let Some(notice) = &app.notice {
+        return Paragraph::new(notice.as_str())
+            .style(Style::default().fg(Color::LightCyan))
+            .alignment(Alignment::Left);
+    }
+    if let Some((_, count)) = &app.pending_reload {
+        return Paragraph::new(format!("Reload {} saved definition(s) ? [y/n]", count))
+            .style(Style::default().fg(Color::LightCyan))
+            .alignment(Alignment::Left);
+    }
 
+    let mut message = match (&app.code_status, app.forth.warnings()) {
+        (Ok(()), []) => String::new(),
+        (Ok(()), [warning]) => format!("Skipped: {}", warning),
+        (Ok(()), warnings) => format!(
+            "Skipped {} unknown words: {}",
+            warnings.len(),
+            skipped_words(warnings).join(", ")
+        ),
+        (status, _) => status_message(status),
+    };
+    // the result shown is the latest done, until the running one is
+    if app.evaluator.is_busy() {
+        let frame = SPINNER[app.spinner_frame % SPINNER.len()];
+        message = format!("{} Running... {}", frame, message);
+    }
     Paragraph::new(message)
         .style(Style::default().fg(status_color(app)))
         .alignment(Alignment::Left)
 }
 
-fn menu_widget(app: &App) -> Paragraph {
-    let text = match app.input_mode {
-        InputMode::Edit => "[ESC] Access menu",
-        InputMode::Menu => "[q] Quit , [ESC] Resume editing",
+// The unknown words skipped, as written
+fn skipped_words(warnings: &[forth_tui::Error]) -> Vec<&str> {
+    let words = warnings.iter().filter_map(|w| w.suggestions());
+    words.map(|suggestions| suggestions.word.as_str()).collect()
+}
+
+fn menu_widget(app: &App) -> Paragraph<'_> {
+    let autosave = if app.autosave { "on" } else { "off" };
+    let provenance = if app.provenance { "on" } else { "off" };
+    let unknown_words = match app.strictness {
+        Strictness::Strict => "stop",
+        Strictness::Tolerant => "skip",
+    };
+    let overflow = match app.overflow_mode {
+        OverflowMode::Wrapping => "wrap",
+        OverflowMode::Checked => "error",
+        OverflowMode::Saturating => "saturate",
+    };
+    let recorder = &app.macro_recorder;
+    let macro_keys = match (recorder.is_recording(), recorder.has_macro()) {
+        (true, _) => String::from("[r] Stop recording"),
+        (false, true) => match recorder.count() {
+            Some(count) => format!("[r] Record macro , [@] Play macro {}x", count),
+            None => String::from("[r] Record macro , [@] Play macro"),
+        },
+        (false, false) => String::from("[r] Record macro"),
+    };
+    let text = match (&app.input_mode, &app.stack_prompt) {
+        (InputMode::Edit, _) if recorder.is_recording() => {
+            String::from("Recording macro... [ESC] Access menu")
+        }
+        (InputMode::Edit, _) if !app.tab_stops.is_empty() => {
+            String::from("[TAB] Next snippet field , [ESC] Access menu")
+        }
+        (InputMode::Edit, _) => String::from(
+            "[ESC] Access menu , [F12] Go to definition , [SHIFT+F12] Jump back , [CTRL+C] Stop a running loop",
+        ),
+        (InputMode::Menu, _) => {
+            let mut entries = vec![String::from("[q] Quit")];
+            entries.push(match app.project {
+                Some(_) => String::from("[o] Files"),
+                None => String::from("[o] Open file"),
+            });
+            if !app.readonly {
+                entries.push(match app.current_file {
+                    Some(_) => String::from("[w] Save"),
+                    None => String::from("[w] Save as"),
+                });
+            }
+            entries.extend([
+                String::from("[s] Edit stack"),
+                String::from("[c] Calculator"),
+                String::from("[d] Definitions"),
+                String::from("[t] Run tests"),
+                String::from("[g] Glossary"),
+                String::from("[x] Save session"),
+                String::from("[l] Load session"),
+                String::from("[PgUp/PgDn] Scroll output"),
+                String::from("[z] Fold/unfold"),
+                format!("[a] Autosave: {}", autosave),
+                format!("[p] Provenance: {}", provenance),
+                format!("[v] Overflow: {}", overflow),
+                format!("[u] Unknown words: {}", unknown_words),
+            ]);
+            // the code can't be changed by a snippet or a macro either
+            if !app.readonly {
+                entries.push(String::from("[n] Snippets"));
+                entries.push(macro_keys);
+            }
+            entries.push(String::from("[ESC] Resume editing"));
+            entries.join(" , ")
+        }
+        (InputMode::Stack, None) => String::from(
+            "[p] Push , [x] Drop , [d] Dup , [e] Edit , [b] Base , [c] Clear edits , [ESC] Resume editing",
+        ),
+        (InputMode::Stack, Some(_)) => String::from("[ENTER] Confirm , [ESC] Cancel"),
+        (InputMode::Files, _) => String::from("[ENTER] Open file , [ESC] Back to menu"),
+        (InputMode::FilePrompt, _) => match app.file_prompt.as_ref().map(|p| p.action) {
+            Some(FileAction::Save) => {
+                String::from("[TAB] Complete , [ENTER] Save , [ESC] Back to menu")
+            }
+            _ => String::from("[TAB] Complete , [ENTER] Open , [ESC] Back to menu"),
+        },
+        (InputMode::Snippets, _) => String::from("[ENTER] Insert snippet , [ESC] Back to menu"),
+        (InputMode::Tests, _) => String::from(
+            "[ENTER] Go to test , [t] Run again , [ESC] Back to menu",
+        ),
+        (InputMode::Calculator, _) => String::from("[ENTER] Evaluate , [ESC] Close calculator"),
+        (InputMode::Definitions, _) => {
+            let view = &app.definitions_view;
+            let order = match view.order {
+                definitions_view::Order::Definition => "order",
+                definitions_view::Order::Name => "name",
+            };
+            let grouped = if view.grouped { "on" } else { "off" };
+            let search_order = if view.search_order { "on" } else { "off" };
+            format!(
+                "Type to filter , [ENTER] Save across sessions , [TAB] Sort by: {} , [SHIFT+TAB] Group by kind: {} , [CTRL+O] Search order: {} , [ESC] Back to menu",
+                order, grouped, search_order
+            )
+        }
+    };
+    Paragraph::new(text)
+        .alignment(Alignment::Right)
+        .wrap(Wrap { trim: true })
+}
+
+fn definitions_widget(app: &App) -> Paragraph<'_> {
+    let view = &app.definitions_view;
+    let selected = match app.input_mode {
+        InputMode::Definitions => view.selected_definition(&app.forth),
+        _ => None,
+    };
+    let definition_items: Vec<Spans> = view
+        .rows(&app.forth)
+        .into_iter()
+        .map(|row| match row {
+            Row::Heading(kind) => Spans::from(Span::styled(
+                kind.heading(),
+                Style::default().add_modifier(Modifier::BOLD),
+            )),
+            Row::Definition(d) => {
+                let text = match d.kind {
+                    DefinitionKind::Colon => {
+                        // an empty body shows as nothing after the colon
+                        let mut words = vec![d.name.as_str()];
+                        words.extend(d.stack_effect.as_deref());
+                        words.push(":");
+                        words.extend(d.instructions.iter().map(Token::as_str));
+                        words.join(" ")
+                    }
+                    _ => format!(
+                        "{} {}",
+                        d.name,
+                        definitions_view::description(&app.forth, d)
+                    ),
+                };
+                // words saved to the user's dictionary are marked
+                let text = match app.saved_words.contains(&app.forth, &d.name) {
+                    true => format!("* {}", text),
+                    false => text,
+                };
+                let mut style = Style::default();
+                if selected.is_some_and(|selected| std::ptr::eq(selected, d)) {
+                    style = style.add_modifier(Modifier::REVERSED);
+                }
+                Spans::from(Span::styled(text, style))
+            }
+        })
+        .collect();
+    let border_style = match app.input_mode {
+        InputMode::Definitions => Style::default().fg(Color::LightCyan),
+        _ => Style::default(),
+    };
+    Paragraph::new(definition_items).block(
+        Block::default()
+            .title(view.title(&app.forth))
+            .borders(Borders::ALL)
+            .border_style(border_style),
+    )
+}
+
+// The latest lines the program printed, or its virtual screen from its top
+// left corner when it draws with PAGE and AT-XY
+fn output_widget(app: &App) -> Paragraph<'_> {
+    let output = app.forth.output();
+    if app.forth.screen().is_addressed() || output.text().is_empty() {
+        let lines: Vec<Spans> = app.forth.screen().lines().map(Spans::from).collect();
+        return Paragraph::new(lines).block(Block::default().title("Output").borders(Borders::ALL));
+    }
+    let text: Vec<&str> = output.text().lines().collect();
+    let kept = &text[text.len().saturating_sub(app.scrollback)..];
+    let mut lines = Vec::new();
+    if output.has_overflowed() || kept.len() < text.len() {
+        lines.push(Spans::from(Span::styled(
+            "[earlier output dropped]",
+            Style::default().fg(Color::DarkGray),
+        )));
+    }
+    lines.extend(kept.iter().map(|&line| Spans::from(line)));
+    // keep the latest lines in view, unless scrolled back
+    let height = app.output_height.saturating_sub(2) as usize;
+    let bottom = lines.len().saturating_sub(height);
+    let scroll = bottom.saturating_sub(app.output_scroll) as u16;
+    Paragraph::new(lines)
+        .scroll((scroll, 0))
+        .block(Block::default().title("Output").borders(Borders::ALL))
+}
+
+fn compiled_widget(app: &App, index: Option<usize>) -> Paragraph<'_> {
+    let (title, lines) = match index {
+        Some(index) => (
+            format!("Compiled: {}", app.forth.definitions[index].name),
+            inspector::compiled_listing(&app.forth, index),
+        ),
+        None => (String::from("Compiled"), Vec::new()),
     };
-    Paragraph::new(text).alignment(Alignment::Right)
+    let lines: Vec<Spans> = lines.into_iter().map(Spans::from).collect();
+    Paragraph::new(lines).block(Block::default().title(title).borders(Borders::ALL))
+}
+
+fn snippets_widget(app: &App) -> Paragraph<'_> {
+    let lines: Vec<Spans> = app
+        .snippets
+        .iter()
+        .enumerate()
+        .map(|(index, snippet)| {
+            let style = if index == app.snippet_selection {
+                Style::default().add_modifier(Modifier::REVERSED)
+            } else {
+                Style::default()
+            };
+            Spans::from(Span::styled(snippet.name.as_str(), style))
+        })
+        .collect();
+    Paragraph::new(lines).block(
+        Block::default()
+            .title("Snippets")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::LightCyan)),
+    )
+}
+
+fn return_stack_widget(app: &App) -> Paragraph<'_> {
+    let items: Vec<Spans> = app
+        .forth
+        .return_stack()
+        .iter()
+        .map(|v| Spans::from(v.to_string()))
+        .collect();
+    Paragraph::new(items).block(Block::default().title("Return stack").borders(Borders::ALL))
 }
 
-fn definitions_widget(app: &App) -> Paragraph {
-    let definition_items: Vec<Spans> = app
+fn float_stack_widget(app: &App) -> Paragraph<'_> {
+    let items: Vec<Spans> = app
         .forth
-        .definitions
+        .float_stack()
+        .iter()
+        .map(|r| Spans::from(r.to_string()))
+        .collect();
+    Paragraph::new(items).block(Block::default().title("Float stack").borders(Borders::ALL))
+}
+
+fn tests_widget(app: &App) -> Paragraph<'_> {
+    let mut lines: Vec<Spans> = app
+        .test_outcomes
         .iter()
-        .map(|d| Spans::from(format!("{} : {}", d.name, d.instructions.join(" "))))
+        .enumerate()
+        .map(|(index, outcome)| {
+            let (mark, detail, color) = match &outcome.result {
+                Ok(()) => ("✓", String::new(), Color::LightGreen),
+                Err(TestFailure::Mismatch { actual, expected }) => (
+                    "✗",
+                    format!(": got {:?}, expected {:?}", actual, expected),
+                    Color::LightRed,
+                ),
+                Err(TestFailure::Error(err)) => ("✗", format!(": {}", err), Color::LightRed),
+            };
+            let mut style = Style::default().fg(color);
+            if index == app.test_selection {
+                style = style.add_modifier(Modifier::REVERSED);
+            }
+            Spans::from(Span::styled(
+                format!("{} {}{}", mark, outcome.name, detail),
+                style,
+            ))
+        })
         .collect();
-    Paragraph::new(definition_items)
-        .block(Block::default().title("Definitions").borders(Borders::ALL))
+    if lines.is_empty() {
+        lines.push(Spans::from(
+            "No tests, write one as TEST: NAME ... -> ... ;TEST",
+        ));
+    }
+    let passed = app
+        .test_outcomes
+        .iter()
+        .filter(|outcome| outcome.result.is_ok())
+        .count();
+    Paragraph::new(lines).block(
+        Block::default()
+            .title(format!(
+                "Tests: {}/{} passed",
+                passed,
+                app.test_outcomes.len()
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::LightCyan)),
+    )
+}
+
+// The path typed, then the files it can be completed to
+fn file_prompt_widget(prompt: &FilePrompt, candidates: Vec<String>) -> Paragraph<'_> {
+    let mut lines = vec![Spans::from(format!("> {}", prompt.input))];
+    lines.extend(
+        candidates
+            .into_iter()
+            .take(MAX_CANDIDATES)
+            .map(|candidate| {
+                Spans::from(Span::styled(candidate, Style::default().fg(Color::Gray)))
+            }),
+    );
+    Paragraph::new(lines).block(
+        Block::default()
+            .title(prompt.title())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::LightCyan)),
+    )
+}
+
+fn calculator_widget(calculator: &Calculator) -> Paragraph<'_> {
+    let stack = calculator
+        .forth
+        .stack()
+        .iter()
+        .map(|v| v.to_string())
+        .collect::<Vec<String>>()
+        .join(" ");
+    let lines = vec![
+        Spans::from(Span::styled(stack, Style::default().fg(Color::LightCyan))),
+        Spans::from(""),
+        Spans::from(format!("> {}", calculator.input)),
+        Spans::from(Span::styled(
+            status_message(&calculator.status),
+            Style::default().fg(Color::LightRed),
+        )),
+    ];
+    Paragraph::new(lines).block(
+        Block::default()
+            .title("Calculator")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(Color::LightCyan)),
+    )
 }
 
-fn stack_widget(app: &App) -> Paragraph {
-    let stack_items: Vec<Spans> = app
+fn stack_widget(app: &App) -> Paragraph<'_> {
+    let stack_focused = matches!(app.input_mode, InputMode::Stack);
+    let mut stack_items: Vec<Spans> = app
         .forth
-        .stack
+        .stack()
         .iter()
-        .map(|v| Spans::from(format!("{}", v)))
+        .enumerate()
+        .map(|(index, v)| {
+            let style = match app.stack_drag {
+                Some((from, _)) if from == index => Style::default().fg(Color::DarkGray),
+                Some((_, to)) if to == index => Style::default().add_modifier(Modifier::UNDERLINED),
+                _ if stack_focused && index == app.stack_selection => {
+                    Style::default().add_modifier(Modifier::REVERSED)
+                }
+                _ => Style::default(),
+            };
+            let value = app.forth.format_number(*v);
+            let text = match app.forth.provenance().and_then(|p| p.get(index)) {
+                Some(provenance) => format!("{}  ({})", value, provenance),
+                None => value,
+            };
+            Spans::from(Span::styled(text, style))
+        })
         .collect();
-    Paragraph::new(stack_items).block(Block::default().title("Stack").borders(Borders::ALL))
+    if let Some(prompt) = &app.stack_prompt {
+        stack_items.push(Spans::from(Span::styled(
+            format!("{} > {}", prompt.label(), prompt.input),
+            Style::default().fg(Color::LightCyan),
+        )));
+    }
+
+    let mut title = match app.stack_edits.len() {
+        0 => String::from("Stack"),
+        1 => String::from("Stack (1 edit)"),
+        n => format!("Stack ({} edits)", n),
+    };
+    match app.forth.base() {
+        10 => {}
+        16 => title.push_str(" hex"),
+        2 => title.push_str(" binary"),
+        base => title.push_str(&format!(" base {}", base)),
+    }
+    let border_style = if stack_focused {
+        Style::default().fg(Color::LightCyan)
+    } else {
+        Style::default()
+    };
+    Paragraph::new(stack_items).block(
+        Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(border_style),
+    )
 }
@@ -0,0 +1,65 @@
+use crate::inspector::word_spans;
+
+// Word the cursor is on, or right after
+pub fn word_at(lines: &[String], cursor: (usize, usize)) -> Option<&str> {
+    let line = lines.get(cursor.0)?;
+    word_spans(line)
+        .into_iter()
+        .find(|&(start, end, _)| start <= cursor.1 && cursor.1 <= end)
+        .map(|(_, _, word)| word)
+}
+
+// Position of the name of the definition `word` calls from the cursor: the
+// latest one made before the cursor, or else the first one after it
+pub fn definition_position(
+    lines: &[String],
+    word: &str,
+    cursor: (usize, usize),
+) -> Option<(usize, usize)> {
+    let mut before = None;
+    let mut after = None;
+    let mut defining = false;
+    for (row, line) in lines.iter().enumerate() {
+        for (start, _, name) in word_spans(line) {
+            if defining && name.eq_ignore_ascii_case(word) {
+                if (row, start) <= cursor {
+                    before = Some((row, start));
+                } else if after.is_none() {
+                    after = Some((row, start));
+                }
+            }
+            defining = name == ":";
+        }
+    }
+    before.or(after)
+}
+
+// Positions of the `TEST:` words, in the order the tests are collected
+pub fn test_positions(lines: &[String]) -> Vec<(usize, usize)> {
+    lines
+        .iter()
+        .enumerate()
+        .flat_map(|(row, line)| {
+            word_spans(line)
+                .into_iter()
+                .filter(|(_, _, word)| word.eq_ignore_ascii_case("TEST:"))
+                .map(move |(start, _, _)| (row, start))
+        })
+        .collect()
+}
+
+// Positions the cursor jumped from, to go back to them in reverse order
+#[derive(Default)]
+pub struct JumpStack {
+    positions: Vec<(usize, usize)>,
+}
+
+impl JumpStack {
+    pub fn push(&mut self, position: (usize, usize)) {
+        self.positions.push(position);
+    }
+
+    pub fn pop(&mut self) -> Option<(usize, usize)> {
+        self.positions.pop()
+    }
+}
@@ -0,0 +1,272 @@
+use crate::definitions_view::deferred_target;
+use forth_tui::{Definition, DefinitionKind, Forth, Token};
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::ptr;
+
+// Saved definitions live next to the code, at the root of the project
+// (or in the directory the app was started from)
+pub const DEFINITIONS_FILE: &str = ".forth-tui-definitions.fth";
+
+// The user's dictionary lives in the config directory: the definitions
+// marked as saved, a line each, evaluated before the code on every launch
+pub const DICTIONARY_FILE: &str = "dictionary.fth";
+
+// Source code re-creating the session's definitions from `from` on, one per
+// line, in definition order, then setting the deferred words
+pub fn definitions_source(forth: &Forth, from: usize) -> String {
+    let kept = kept_definitions(forth, from);
+    let definitions: String = kept
+        .iter()
+        .map(|d| format!("{}\n", definition_source(d)))
+        .collect();
+    // after every definition, as the word a deferred word runs can come later
+    let case_mode = forth.case_mode();
+    let deferrals: String = kept
+        .iter()
+        .enumerate()
+        .filter(|(_, d)| d.kind == DefinitionKind::Deferred)
+        // IS sets the latest definition of the name
+        .filter(|&(i, d)| {
+            !kept[i + 1..]
+                .iter()
+                .any(|l| case_mode.matches(&l.name, &d.name))
+        })
+        .filter_map(|(_, d)| {
+            let target = deferred_target(forth, d)?;
+            // a nameless definition can't be named again
+            (target != ":NONAME").then(|| format!("' {} IS {}\n", target, d.name))
+        })
+        .collect();
+    definitions + &deferrals
+}
+
+// The definitions from `from` on worth saving: the latest of each name, and
+// the earlier ones a definition kept calls, so that reloading them and
+// defining them again doesn't grow the file
+fn kept_definitions(forth: &Forth, from: usize) -> Vec<&Definition> {
+    let case_mode = forth.case_mode();
+    let definitions = &forth.definitions[from.min(forth.definitions.len())..];
+    let mut kept = vec![false; definitions.len()];
+    let mut called = vec![false; definitions.len()];
+    for (i, d) in definitions.iter().enumerate().rev() {
+        // tokens of nameless definitions don't last beyond the session
+        if d.name.is_empty() {
+            continue;
+        }
+        let redefined = definitions[i + 1..]
+            .iter()
+            .any(|later| case_mode.matches(&later.name, &d.name));
+        if redefined && !called[i] {
+            continue;
+        }
+        kept[i] = true;
+        if d.kind == DefinitionKind::Colon {
+            for word in d.instructions.iter() {
+                let earlier = definitions[..i]
+                    .iter()
+                    .rposition(|e| case_mode.matches(&e.name, word));
+                if let Some(j) = earlier {
+                    called[j] = true;
+                }
+            }
+        }
+    }
+    definitions
+        .iter()
+        .zip(kept)
+        .filter_map(|(d, kept)| kept.then_some(d))
+        .collect()
+}
+
+// Source re-creating a definition, on a line
+fn definition_source(d: &Definition) -> String {
+    match d.kind {
+        // a variable gets a new cell, its contents are not kept
+        DefinitionKind::Variable => format!("VARIABLE {}", d.name),
+        DefinitionKind::Constant => format!("{} CONSTANT {}", d.instructions.join(" "), d.name),
+        DefinitionKind::Value => format!("{} VALUE {}", d.instructions.join(" "), d.name),
+        DefinitionKind::Deferred => format!("DEFER {}", d.name),
+        // neither the data after a created word nor what its DOES> runs is kept
+        DefinitionKind::Created => format!("CREATE {}", d.name),
+        DefinitionKind::Marker => format!("MARKER {}", d.name),
+        // the definitions made in a vocabulary are saved to FORTH
+        DefinitionKind::Vocabulary => format!("VOCABULARY {}", d.name),
+        DefinitionKind::Colon => {
+            let mut words = vec![":", d.name.as_str()];
+            words.extend(d.stack_effect.as_deref());
+            words.extend(d.instructions.iter().map(Token::as_str));
+            words.push(";");
+            if d.immediate {
+                words.push("IMMEDIATE");
+            }
+            words.join(" ")
+        }
+    }
+}
+
+// The definitions saved to the user's dictionary, by name, with the line
+// making each. A colon definition is saved along with the code's words it
+// uses, and a deferred word is saved unset.
+#[derive(Default)]
+pub struct SavedWords {
+    pub words: Vec<(String, String)>,
+}
+
+impl SavedWords {
+    // Empty without a config directory or dictionary file
+    pub fn load(dir: Option<&Path>) -> io::Result<SavedWords> {
+        let Some(dir) = dir else {
+            return Ok(SavedWords::default());
+        };
+        let source = match fs::read_to_string(dir.join(DICTIONARY_FILE)) {
+            Ok(source) => source,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(SavedWords::default()),
+            Err(err) => return Err(err),
+        };
+        // the name of each line's definition, from evaluating them in turn
+        let mut forth = Forth::new();
+        let mut words = Vec::new();
+        for line in source.lines() {
+            let defined = forth.user_definitions().len();
+            let _ = forth.eval(line);
+            if let Some(definition) = forth.user_definitions().get(defined) {
+                words.push((definition.name.clone(), line.to_string()));
+            }
+        }
+        Ok(SavedWords { words })
+    }
+
+    pub fn source(&self) -> String {
+        self.words
+            .iter()
+            .map(|(_, line)| format!("{}\n", line))
+            .collect()
+    }
+
+    pub fn contains(&self, forth: &Forth, name: &str) -> bool {
+        let case_mode = forth.case_mode();
+        self.words
+            .iter()
+            .any(|(saved, _)| case_mode.matches(saved, name))
+    }
+
+    // Saves the definition, and the words it uses not saved yet, or stops
+    // saving it if it is saved, writing the file; the names saved, none
+    // when it no longer is
+    pub fn toggle(
+        &mut self,
+        dir: &Path,
+        forth: &Forth,
+        definition: &Definition,
+    ) -> io::Result<Vec<String>> {
+        let case_mode = forth.case_mode();
+        let mut saved = Vec::new();
+        if self.contains(forth, &definition.name) {
+            self.words
+                .retain(|(name, _)| !case_mode.matches(name, &definition.name));
+        } else {
+            for d in dependencies(forth, definition)
+                .into_iter()
+                .chain([definition])
+            {
+                if !self.contains(forth, &d.name) {
+                    self.words.push((d.name.clone(), definition_source(d)));
+                    saved.push(d.name.clone());
+                }
+            }
+        }
+        fs::create_dir_all(dir)?;
+        fs::write(dir.join(DICTIONARY_FILE), self.source())?;
+        Ok(saved)
+    }
+}
+
+// The code's colon definitions a colon definition calls, and those they
+// call in turn, in the order they were made
+fn dependencies<'a>(forth: &'a Forth, definition: &'a Definition) -> Vec<&'a Definition> {
+    let case_mode = forth.case_mode();
+    let position = |d: &Definition| forth.definitions.iter().position(|other| ptr::eq(other, d));
+    let prelude = forth.definitions.len() - forth.user_definitions().len();
+    let mut found: Vec<&Definition> = Vec::new();
+    let mut pending = vec![definition];
+    while let Some(d) = pending.pop() {
+        if d.kind != DefinitionKind::Colon {
+            continue;
+        }
+        // the latest definition of a name made before the one calling it
+        let before = &forth.definitions[prelude..position(d).unwrap_or(prelude).max(prelude)];
+        for word in d.instructions.iter() {
+            let Some(used) = before
+                .iter()
+                .rev()
+                .find(|other| case_mode.matches(&other.name, word))
+            else {
+                continue;
+            };
+            if !found.iter().any(|&f| ptr::eq(f, used)) {
+                found.push(used);
+                pending.push(used);
+            }
+        }
+    }
+    found.sort_by_key(|&d| position(d));
+    found
+}
+
+// Returns the saved source and how many definitions it holds, if a valid file exists
+pub fn load_definitions(root: &Path) -> io::Result<Option<(String, usize)>> {
+    let source = match fs::read_to_string(root.join(DEFINITIONS_FILE)) {
+        Ok(source) => source,
+        Err(err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(err) => return Err(err),
+    };
+    let mut forth = Forth::new();
+    match forth.eval(&source) {
+        Ok(()) if !forth.user_definitions().is_empty() => {
+            Ok(Some((source, forth.user_definitions().len())))
+        }
+        _ => Ok(None),
+    }
+}
+
+pub fn save_definitions(root: &Path, source: &str) -> io::Result<()> {
+    fs::write(root.join(DEFINITIONS_FILE), source)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn source_of(code: &str) -> String {
+        let mut forth = Forth::new();
+        let from = forth.definitions.len();
+        forth.eval(code).unwrap();
+        definitions_source(&forth, from)
+    }
+
+    #[test]
+    fn redefined_words_are_saved_once() {
+        assert_eq!(source_of(": A 1 ; : A 2 ; : B A ;"), ": A 2 ;\n: B A ;\n");
+    }
+
+    #[test]
+    fn words_called_before_being_redefined_are_kept() {
+        assert_eq!(
+            source_of(": SQ DUP * ; : F SQ ; : SQ 0 ;"),
+            ": SQ DUP * ;\n: F SQ ;\n: SQ 0 ;\n"
+        );
+    }
+
+    #[test]
+    fn reloaded_definitions_are_not_saved_again() {
+        let code = ": SQ DUP * ; : F SQ ; : SQ 0 ; DEFER D ' F IS D";
+        let saved = source_of(code);
+        let mut forth = Forth::new();
+        let from = forth.definitions.len();
+        forth.eval_prelude(&saved).unwrap();
+        forth.eval(code).unwrap();
+        assert_eq!(definitions_source(&forth, from), saved);
+    }
+}
@@ -0,0 +1,53 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+pub const SOURCE_EXTENSIONS: [&str; 3] = ["fth", "fs", "4th"];
+
+// A directory of Forth sources opened with `forth-tui DIR`
+pub struct Project {
+    pub root: PathBuf,
+    // source files, relative to the root
+    pub files: Vec<PathBuf>,
+}
+
+impl Project {
+    pub fn open(root: &Path) -> io::Result<Project> {
+        let mut files = Vec::new();
+        collect_sources(root, root, &mut files)?;
+        files.sort();
+        Ok(Project {
+            root: root.to_path_buf(),
+            files,
+        })
+    }
+}
+
+pub fn is_source(path: &Path) -> bool {
+    path.extension()
+        .and_then(|extension| extension.to_str())
+        .is_some_and(|extension| SOURCE_EXTENSIONS.contains(&extension))
+}
+
+// Hidden directories and build output are skipped
+fn collect_sources(root: &Path, dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        let hidden = path
+            .file_name()
+            .is_some_and(|name| name.to_string_lossy().starts_with('.'));
+        if hidden {
+            continue;
+        }
+        if path.is_dir() {
+            if path.file_name().is_some_and(|name| name != "target") {
+                collect_sources(root, &path, files)?;
+            }
+        } else if is_source(&path) {
+            if let Ok(relative) = path.strip_prefix(root) {
+                files.push(relative.to_path_buf());
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,53 @@
+use crate::stack_edit::StackEdit;
+use forth_tui::{Forth, ForthResult};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// A session is saved next to the code, like the definitions
+pub const SESSION_FILE: &str = ".forth-session";
+
+// The editor's code and the interpreter's state it led to, read back as they
+// were rather than evaluated again, so that even the numbers RANDOM returned
+// or the keys KEY read stay the same
+#[derive(Deserialize)]
+pub struct Session {
+    // with its folds expanded
+    pub code: String,
+    pub forth: Forth,
+    pub status: ForthResult,
+    // corrections made from the stack pane, replayed after the next evaluations
+    pub stack_edits: Vec<StackEdit>,
+}
+
+// The same, borrowed from the app to be saved
+#[derive(Serialize)]
+struct SavedSession<'a> {
+    code: &'a str,
+    forth: &'a Forth,
+    status: &'a ForthResult,
+    stack_edits: &'a [StackEdit],
+}
+
+pub fn save_session(
+    root: &Path,
+    code: &str,
+    forth: &Forth,
+    status: &ForthResult,
+    stack_edits: &[StackEdit],
+) -> io::Result<()> {
+    let session = SavedSession {
+        code,
+        forth,
+        status,
+        stack_edits,
+    };
+    let json = serde_json::to_string(&session)?;
+    fs::write(root.join(SESSION_FILE), json)
+}
+
+pub fn load_session(root: &Path) -> io::Result<Session> {
+    let json = fs::read_to_string(root.join(SESSION_FILE))?;
+    Ok(serde_json::from_str(&json)?)
+}
@@ -0,0 +1,142 @@
+use crate::config::Config;
+use std::collections::VecDeque;
+
+// Code templates inserted into the editor. In a body, `$1` to `$9` mark the
+// places to fill in, which Tab then visits in order; `\n` in a snippet read
+// from the config breaks the line.
+pub struct Snippet {
+    pub name: String,
+    pub body: String,
+}
+
+const BUILT_IN: [(&str, &str); 5] = [
+    ("definition", ": $1 ( $2 -- $3 )\n  $4 ;"),
+    ("if", "IF\n  $1\nTHEN"),
+    ("if else", "IF\n  $1\nELSE\n  $2\nTHEN"),
+    ("do loop", "$1 $2 DO\n  $3\nLOOP"),
+    ("begin until", "BEGIN\n  $1\nUNTIL"),
+];
+
+// Built-in snippets, then the user's `snippet.NAME = body` settings
+pub fn load(config: &Config) -> Vec<Snippet> {
+    let built_in = BUILT_IN.iter().map(|&(name, body)| Snippet {
+        name: name.to_string(),
+        body: body.to_string(),
+    });
+    let configured = config.with_prefix("snippet.").map(|(name, body)| Snippet {
+        name: name.to_string(),
+        body: body.replace("\\n", "\n"),
+    });
+    built_in.chain(configured).collect()
+}
+
+impl Snippet {
+    // Lines to insert, each after the first starting with `indent`, and the
+    // places to fill in, as row and column from the start of the snippet
+    pub fn expand(&self, indent: &str) -> (Vec<String>, Vec<(usize, usize)>) {
+        let mut lines = Vec::new();
+        let mut stops = Vec::new();
+        for (row, text) in self.body.lines().enumerate() {
+            let mut line = if row == 0 {
+                String::new()
+            } else {
+                indent.to_string()
+            };
+            let mut chars = text.chars().peekable();
+            while let Some(c) = chars.next() {
+                match (c, chars.peek().and_then(|next| next.to_digit(10))) {
+                    ('$', Some(number)) if number > 0 => {
+                        chars.next();
+                        stops.push((number, row, line.chars().count()));
+                    }
+                    _ => line.push(c),
+                }
+            }
+            lines.push(line);
+        }
+        stops.sort_by_key(|&(number, _, _)| number);
+        let stops = stops.into_iter().map(|(_, row, column)| (row, column));
+        (lines, stops.collect())
+    }
+}
+
+// Places left to fill in, in editor positions. Text typed at a place moves
+// the places after it on the same line, which is accounted for when moving on.
+#[derive(Default)]
+pub struct TabStops {
+    stops: VecDeque<(usize, usize)>,
+    // the place being filled in, with the length of its line when reached
+    current: Option<(usize, usize)>,
+}
+
+impl TabStops {
+    pub fn new(stops: Vec<(usize, usize)>) -> TabStops {
+        TabStops {
+            stops: stops.into(),
+            current: None,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.stops.is_empty()
+    }
+
+    // Where to move the cursor next, if anywhere
+    pub fn next(&mut self, lines: &[String]) -> Option<(usize, usize)> {
+        let line_length = |row: usize| lines.get(row).map_or(0, |line| line.chars().count());
+        if let Some((row, length)) = self.current.take() {
+            let typed = line_length(row) as isize - length as isize;
+            for stop in self.stops.iter_mut().filter(|stop| stop.0 == row) {
+                stop.1 = stop.1.saturating_add_signed(typed);
+            }
+        }
+        let (row, column) = self.stops.pop_front()?;
+        self.current = Some((row, line_length(row)));
+        Some((row, column))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::PROJECT_CONFIG_FILE;
+
+    fn snippet(body: &str) -> Snippet {
+        Snippet {
+            name: String::from("test"),
+            body: body.to_string(),
+        }
+    }
+
+    #[test]
+    fn expanding_indents_lines_and_orders_the_places() {
+        let (lines, stops) = snippet("$2 IF\n$1 $0\nTHEN").expand("  ");
+        assert_eq!(lines, [" IF", "   $0", "  THEN"]);
+        assert_eq!(stops, [(1, 2), (0, 0)]);
+    }
+
+    #[test]
+    fn configured_snippets_follow_the_built_in_ones() {
+        let root = std::env::temp_dir().join(format!("forth-tui-snippets-{}", std::process::id()));
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(root.join(PROJECT_CONFIG_FILE), "snippet.twice = DUP +\\n$1").unwrap();
+        let snippets = load(&Config::load(Some(&root)));
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(snippets[0].name, "definition");
+        let twice = snippets.iter().position(|s| s.name == "twice").unwrap();
+        assert!(twice >= BUILT_IN.len());
+        assert_eq!(snippets[twice].body, "DUP +\n$1");
+    }
+
+    #[test]
+    fn places_move_with_the_text_typed_before_them() {
+        let mut stops = TabStops::new(vec![(0, 2), (0, 4), (1, 0)]);
+        let mut lines = vec![String::from(": ( -- )"), String::new()];
+        assert_eq!(stops.next(&lines), Some((0, 2)));
+        lines[0] = String::from(": sq ( -- )");
+        assert_eq!(stops.next(&lines), Some((0, 7)));
+        assert_eq!(stops.next(&lines), Some((1, 0)));
+        assert!(stops.is_empty());
+        assert_eq!(stops.next(&lines), None);
+    }
+}
@@ -0,0 +1,144 @@
+use forth_tui::{Forth, ForthResult, Value};
+use serde::{Deserialize, Serialize};
+
+// A manual correction made from the stack pane.
+// Edits are replayed on top of the evaluated stack after every evaluation,
+// so they survive the editor being re-evaluated on each keystroke.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub enum StackEdit {
+    Push(Value),
+    Drop(usize),
+    Dup(usize),
+    Set(usize, Value),
+    Move(usize, usize),
+}
+
+impl StackEdit {
+    // Indices are stack positions counted from the bottom, as displayed in the stack pane.
+    // An edit pointing past the current stack is ignored.
+    pub fn apply(&self, stack: &mut Vec<Value>) {
+        match *self {
+            StackEdit::Push(value) => stack.push(value),
+            StackEdit::Drop(index) => {
+                if index < stack.len() {
+                    stack.remove(index);
+                }
+            }
+            StackEdit::Dup(index) => {
+                if let Some(&value) = stack.get(index) {
+                    stack.push(value);
+                }
+            }
+            StackEdit::Set(index, value) => {
+                if let Some(cell) = stack.get_mut(index) {
+                    *cell = value;
+                }
+            }
+            StackEdit::Move(from, to) => {
+                if from < stack.len() {
+                    let value = stack.remove(from);
+                    stack.insert(to.min(stack.len()), value);
+                }
+            }
+        }
+    }
+}
+
+// Applies edits to the interpreter's stack, in order
+pub fn apply_all(forth: &mut Forth, edits: &[StackEdit]) -> ForthResult {
+    let mut stack = forth.stack().to_vec();
+    for edit in edits {
+        edit.apply(&mut stack);
+    }
+    forth.replace_stack(stack)
+}
+
+pub enum PromptKind {
+    Push,
+    Edit(usize),
+}
+
+// Value being typed in the stack pane, for a push or an in-place edit
+pub struct StackPrompt {
+    pub kind: PromptKind,
+    pub input: String,
+}
+
+impl StackPrompt {
+    pub fn push() -> StackPrompt {
+        StackPrompt {
+            kind: PromptKind::Push,
+            input: String::new(),
+        }
+    }
+
+    pub fn edit(index: usize, value: Value) -> StackPrompt {
+        StackPrompt {
+            kind: PromptKind::Edit(index),
+            input: value.to_string(),
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self.kind {
+            PromptKind::Push => "Push",
+            PromptKind::Edit(_) => "Edit",
+        }
+    }
+
+    // Returns None while the typed text is not a valid value
+    pub fn to_edit(&self) -> Option<StackEdit> {
+        let value = self.input.trim().parse::<Value>().ok()?;
+        Some(match self.kind {
+            PromptKind::Push => StackEdit::Push(value),
+            PromptKind::Edit(index) => StackEdit::Set(index, value),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_apply_at_positions_from_the_bottom() {
+        let mut stack = vec![1, 2, 3];
+        StackEdit::Set(0, 7).apply(&mut stack);
+        StackEdit::Dup(1).apply(&mut stack);
+        StackEdit::Move(0, 9).apply(&mut stack);
+        StackEdit::Drop(0).apply(&mut stack);
+        StackEdit::Push(4).apply(&mut stack);
+        assert_eq!(stack, [3, 2, 7, 4]);
+    }
+
+    #[test]
+    fn edits_past_the_stack_are_ignored() {
+        let mut stack = vec![1];
+        for edit in [StackEdit::Drop(1), StackEdit::Dup(2), StackEdit::Set(1, 5)] {
+            edit.apply(&mut stack);
+        }
+        StackEdit::Move(3, 0).apply(&mut stack);
+        assert_eq!(stack, [1]);
+    }
+
+    #[test]
+    fn edits_are_replayed_on_the_interpreter() {
+        let mut forth = Forth::new();
+        forth.eval("1 2").unwrap();
+        apply_all(&mut forth, &[StackEdit::Drop(0), StackEdit::Push(3)]).unwrap();
+        assert_eq!(forth.stack(), [2, 3]);
+    }
+
+    #[test]
+    fn prompts_make_an_edit_once_the_value_is_valid() {
+        let mut prompt = StackPrompt::edit(1, 42);
+        assert_eq!(prompt.input, "42");
+        prompt.input = String::from(" -5 ");
+        assert!(matches!(prompt.to_edit(), Some(StackEdit::Set(1, -5))));
+        prompt = StackPrompt::push();
+        assert_eq!(prompt.label(), "Push");
+        assert!(prompt.to_edit().is_none());
+        prompt.input = String::from("12x");
+        assert!(prompt.to_edit().is_none());
+    }
+}
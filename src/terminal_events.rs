@@ -0,0 +1,29 @@
+use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+use forth_tui::CancelToken;
+use std::io;
+use std::sync::mpsc::{self, Receiver};
+use std::thread;
+
+// Terminal events are read on a thread of their own, so that the stop key is
+// seen even while the UI thread is busy running a program that never ends.
+// The stop key cancels `token` and every other event is passed on in order.
+pub fn spawn(token: CancelToken) -> Receiver<io::Result<Event>> {
+    let (sender, receiver) = mpsc::channel();
+    thread::spawn(move || loop {
+        let event = crossterm::event::read();
+        if let Ok(Event::Key(key)) = &event {
+            if is_stop_key(key) {
+                token.cancel();
+                continue;
+            }
+        }
+        if sender.send(event).is_err() {
+            break;
+        }
+    });
+    receiver
+}
+
+fn is_stop_key(key: &KeyEvent) -> bool {
+    key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL)
+}